@@ -1,11 +1,19 @@
 //! Agent profile: config shape and validation.
 
+pub mod agent_dependencies;
+pub mod chunking_policy;
 pub mod config;
 pub mod metadata_types;
+pub mod output_schema;
 pub mod prompt_contract;
+pub mod provider_fallback;
+pub mod tool_policy;
 pub mod validation;
 
+pub use chunking_policy::ChunkingPolicy;
 pub use config::AgentConfig;
 pub use metadata_types::AgentMetadata;
+pub use output_schema::OutputSchema;
 pub use prompt_contract::PromptContract;
+pub use tool_policy::ToolPolicy;
 pub use validation::validate_agent_config;
@@ -0,0 +1,36 @@
+//! Agents whose frames an agent consumes, read from agent metadata.
+
+use crate::agent::profile::metadata_types::AgentMetadata;
+
+/// Comma-separated agent ids whose child frames feed this agent's directory prompts
+pub const KEY_CONSUMES_AGENTS: &str = "consumes_agents";
+
+/// Agents this agent consumes; the agent itself and duplicates are dropped
+pub fn consumed_agents(agent_id: &str, metadata: &AgentMetadata) -> Vec<String> {
+    let mut agents: Vec<String> = Vec::new();
+    for consumed in metadata.get_list(KEY_CONSUMES_AGENTS) {
+        if consumed != agent_id && !agents.contains(&consumed) {
+            agents.push(consumed);
+        }
+    }
+    agents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_self_and_duplicates() {
+        let mut metadata = AgentMetadata::new();
+        assert!(consumed_agents("docs-writer", &metadata).is_empty());
+        metadata.insert(
+            KEY_CONSUMES_AGENTS.to_string(),
+            "code-analyzer, docs-writer,code-analyzer".to_string(),
+        );
+        assert_eq!(
+            consumed_agents("docs-writer", &metadata),
+            vec!["code-analyzer".to_string()]
+        );
+    }
+}
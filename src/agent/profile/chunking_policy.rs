@@ -0,0 +1,90 @@
+//! Large-file chunking thresholds read from agent metadata.
+
+use crate::agent::identity::AgentIdentity;
+use crate::agent::profile::metadata_types::AgentMetadata;
+use crate::error::ApiError;
+
+pub const KEY_CHUNK_THRESHOLD_BYTES: &str = "chunk_threshold_bytes";
+pub const KEY_CHUNK_MAX_BYTES: &str = "chunk_max_bytes";
+
+/// Files larger than this are generated by chunked map-reduce
+pub const DEFAULT_CHUNK_THRESHOLD_BYTES: usize = 128 * 1024;
+/// Upper bound on the source bytes sent in one chunk prompt
+pub const DEFAULT_CHUNK_MAX_BYTES: usize = 32 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkingPolicy {
+    pub threshold_bytes: usize,
+    pub chunk_max_bytes: usize,
+}
+
+impl Default for ChunkingPolicy {
+    fn default() -> Self {
+        Self {
+            threshold_bytes: DEFAULT_CHUNK_THRESHOLD_BYTES,
+            chunk_max_bytes: DEFAULT_CHUNK_MAX_BYTES,
+        }
+    }
+}
+
+impl ChunkingPolicy {
+    pub fn from_agent(agent: &AgentIdentity) -> Result<Self, ApiError> {
+        Self::from_metadata(&agent.metadata)
+            .map_err(|e| ApiError::ConfigError(format!("Agent '{}': {}", agent.agent_id, e)))
+    }
+
+    /// Read thresholds from agent metadata; missing keys use the defaults
+    pub fn from_metadata(metadata: &AgentMetadata) -> Result<Self, String> {
+        let defaults = Self::default();
+        let policy = Self {
+            threshold_bytes: parse_bytes(metadata, KEY_CHUNK_THRESHOLD_BYTES)?
+                .unwrap_or(defaults.threshold_bytes),
+            chunk_max_bytes: parse_bytes(metadata, KEY_CHUNK_MAX_BYTES)?
+                .unwrap_or(defaults.chunk_max_bytes),
+        };
+        Ok(policy)
+    }
+
+    /// True when a file of `size` bytes should be chunked
+    pub fn should_chunk(&self, size: usize) -> bool {
+        size > self.threshold_bytes
+    }
+}
+
+fn parse_bytes(metadata: &AgentMetadata, key: &str) -> Result<Option<usize>, String> {
+    let Some(value) = metadata.get(key) else {
+        return Ok(None);
+    };
+    match value.trim().parse::<usize>() {
+        Ok(bytes) if bytes > 0 => Ok(Some(bytes)),
+        _ => Err(format!(
+            "{} must be a positive number of bytes, got '{}'",
+            key, value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_overrides_defaults_and_rejects_invalid_values() {
+        let mut metadata = AgentMetadata::new();
+        assert_eq!(
+            ChunkingPolicy::from_metadata(&metadata).unwrap(),
+            ChunkingPolicy::default()
+        );
+
+        metadata.insert(KEY_CHUNK_THRESHOLD_BYTES.to_string(), "2048".to_string());
+        metadata.insert(KEY_CHUNK_MAX_BYTES.to_string(), "512".to_string());
+        let policy = ChunkingPolicy::from_metadata(&metadata).unwrap();
+        assert_eq!(policy.threshold_bytes, 2048);
+        assert_eq!(policy.chunk_max_bytes, 512);
+        assert!(policy.should_chunk(2049));
+        assert!(!policy.should_chunk(2048));
+
+        metadata.insert(KEY_CHUNK_MAX_BYTES.to_string(), "0".to_string());
+        assert!(ChunkingPolicy::from_metadata(&metadata).is_err());
+    }
+}
@@ -13,6 +13,21 @@ impl AgentMetadata {
     pub fn new() -> Self {
         Self(HashMap::new())
     }
+
+    /// Comma-separated list value for `key`; empty when unset
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.0
+            .get(key)
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
 
 impl From<HashMap<String, String>> for AgentMetadata {
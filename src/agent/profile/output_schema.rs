@@ -0,0 +1,327 @@
+//! Structured output schema read from agent metadata.
+//!
+//! An agent that sets `output_schema` produces JSON frames instead of free text. The value
+//! is either an inline JSON schema or a path to a schema file (resolved like prompt paths).
+//! Responses are validated against the supported subset of JSON Schema: `type`,
+//! `properties`, `required`, `additionalProperties: false`, `items`, `enum`, `minItems`,
+//! and `maxItems`.
+//!
+//! ```toml
+//! [metadata]
+//! output_schema = "schemas/file-summary.json"
+//! output_schema_repairs = "2"
+//! ```
+
+use crate::agent::identity::AgentIdentity;
+use crate::agent::profile::metadata_types::AgentMetadata;
+use crate::agent::prompt::resolve_prompt_path;
+use crate::error::ApiError;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+pub const KEY_OUTPUT_SCHEMA: &str = "output_schema";
+pub const KEY_OUTPUT_SCHEMA_REPAIRS: &str = "output_schema_repairs";
+
+/// Repair rounds after an invalid response when the agent does not set a count
+pub const DEFAULT_OUTPUT_SCHEMA_REPAIRS: usize = 2;
+
+/// Validation errors reported back to the model are capped at this many
+const MAX_REPORTED_ERRORS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputSchema {
+    /// Schema `$id`, or a content hash when the schema does not set one
+    pub id: String,
+    pub schema: Value,
+    /// Repair rounds to ask for after an invalid response
+    pub repairs: usize,
+}
+
+impl OutputSchema {
+    /// Schema declared by the agent; `None` for free-text agents
+    pub fn from_agent(agent: &AgentIdentity) -> Result<Option<Self>, ApiError> {
+        let base_dir = crate::config::xdg::config_home()?.join("meld");
+        Self::from_metadata(&agent.metadata, &base_dir)
+            .map_err(|e| ApiError::ConfigError(format!("Agent '{}': {}", agent.agent_id, e)))
+    }
+
+    /// Read the schema from metadata; relative paths resolve against `base_dir`
+    pub fn from_metadata(
+        metadata: &AgentMetadata,
+        base_dir: &Path,
+    ) -> Result<Option<Self>, String> {
+        let Some(value) = metadata.get(KEY_OUTPUT_SCHEMA) else {
+            return Ok(None);
+        };
+        let value = value.trim();
+        let text = if value.starts_with('{') {
+            value.to_string()
+        } else {
+            let path = resolve_prompt_path(value, base_dir).map_err(|e| e.to_string())?;
+            std::fs::read_to_string(&path).map_err(|e| {
+                format!(
+                    "cannot read {} {}: {}",
+                    KEY_OUTPUT_SCHEMA,
+                    path.display(),
+                    e
+                )
+            })?
+        };
+        let schema: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("{} is not valid JSON: {}", KEY_OUTPUT_SCHEMA, e))?;
+        if !schema.is_object() {
+            return Err(format!("{} must be a JSON object", KEY_OUTPUT_SCHEMA));
+        }
+        let id = match schema.get("$id").and_then(Value::as_str) {
+            Some(id) => id.to_string(),
+            None => {
+                let canonical = serde_json::to_vec(&schema).expect("schemas serialize to JSON");
+                format!("blake3:{}", &blake3::hash(&canonical).to_hex()[..16])
+            }
+        };
+        let repairs = match metadata.get(KEY_OUTPUT_SCHEMA_REPAIRS) {
+            Some(count) => count.trim().parse::<usize>().map_err(|_| {
+                format!(
+                    "{} must be a number of repair rounds, got '{}'",
+                    KEY_OUTPUT_SCHEMA_REPAIRS, count
+                )
+            })?,
+            None => DEFAULT_OUTPUT_SCHEMA_REPAIRS,
+        };
+        Ok(Some(Self {
+            id,
+            schema,
+            repairs,
+        }))
+    }
+
+    /// Name for provider structured-output modes: the schema title, or "frame"
+    pub fn name(&self) -> String {
+        let title = self
+            .schema
+            .get("title")
+            .and_then(Value::as_str)
+            .unwrap_or("frame");
+        title
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(64)
+            .collect()
+    }
+
+    /// System prompt addition asking for schema-conforming JSON
+    pub fn instructions(&self) -> String {
+        format!(
+            "Respond with a single JSON object and nothing else. It must conform to this \
+             JSON schema:\n{}",
+            serde_json::to_string_pretty(&self.schema).expect("schemas serialize to JSON")
+        )
+    }
+
+    /// Parse a response as JSON (tolerating code fences and surrounding prose) and
+    /// validate it. Errors describe every violation found, for a repair prompt.
+    pub fn parse_response(&self, text: &str) -> Result<Value, Vec<String>> {
+        let value = extract_json(text)
+            .ok_or_else(|| vec!["response does not contain a JSON object".to_string()])?;
+        let mut errors = Vec::new();
+        validate_value(&self.schema, &value, "$", &mut errors);
+        if errors.is_empty() {
+            Ok(value)
+        } else {
+            errors.truncate(MAX_REPORTED_ERRORS);
+            Err(errors)
+        }
+    }
+}
+
+/// First JSON object in `text`: the whole text, a fenced block, or the outermost braces
+fn extract_json(text: &str) -> Option<Value> {
+    let text = text.trim();
+    let unfenced = text
+        .strip_prefix("```json")
+        .or_else(|| text.strip_prefix("```"))
+        .and_then(|rest| rest.trim_end().strip_suffix("```"))
+        .unwrap_or(text);
+    if let Ok(value @ Value::Object(_)) = serde_json::from_str(unfenced.trim()) {
+        return Some(value);
+    }
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    match serde_json::from_str(text.get(start..=end)?) {
+        Ok(value @ Value::Object(_)) => Some(value),
+        _ => None,
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn validate_value(schema: &Value, value: &Value, at: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|t| type_matches(t, value)) {
+        errors.push(format!("{}: expected {}", at, types.join(" or ")));
+        return;
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            errors.push(format!(
+                "{}: must be one of {}",
+                at,
+                Value::Array(allowed.clone())
+            ));
+        }
+    }
+    match value {
+        Value::Object(fields) => validate_object(schema, fields, at, errors),
+        Value::Array(items) => {
+            let count = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if count < min {
+                    errors.push(format!("{}: expected at least {} items", at, min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if count > max {
+                    errors.push(format!("{}: expected at most {} items", at, max));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_value(item_schema, item, &format!("{}[{}]", at, index), errors);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn validate_object(
+    schema: &Map<String, Value>,
+    fields: &Map<String, Value>,
+    at: &str,
+    errors: &mut Vec<String>,
+) {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !fields.contains_key(name) {
+                errors.push(format!("{}: missing required field '{}'", at, name));
+            }
+        }
+    }
+    for (name, field) in fields {
+        let path = format!("{}.{}", at, name);
+        match properties.and_then(|p| p.get(name)) {
+            Some(field_schema) => validate_value(field_schema, field, &path, errors),
+            None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                errors.push(format!("{}: unexpected field", path));
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summary_schema() -> OutputSchema {
+        let mut metadata = AgentMetadata::new();
+        metadata.insert(
+            KEY_OUTPUT_SCHEMA.to_string(),
+            json!({
+                "$id": "file-summary/v1",
+                "type": "object",
+                "required": ["summary", "exports"],
+                "additionalProperties": false,
+                "properties": {
+                    "summary": {"type": "string"},
+                    "exports": {"type": "array", "items": {"type": "string"}},
+                    "risks": {
+                        "type": "array",
+                        "items": {"enum": ["low", "medium", "high"]}
+                    }
+                }
+            })
+            .to_string(),
+        );
+        OutputSchema::from_metadata(&metadata, Path::new("/unused"))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn reads_inline_schema_and_repair_count() {
+        let schema = summary_schema();
+        assert_eq!(schema.id, "file-summary/v1");
+        assert_eq!(schema.repairs, DEFAULT_OUTPUT_SCHEMA_REPAIRS);
+        assert!(
+            OutputSchema::from_metadata(&AgentMetadata::new(), Path::new("/"))
+                .unwrap()
+                .is_none()
+        );
+
+        let mut metadata = AgentMetadata::new();
+        metadata.insert(
+            KEY_OUTPUT_SCHEMA.to_string(),
+            "{\"type\": \"object\"}".to_string(),
+        );
+        metadata.insert(KEY_OUTPUT_SCHEMA_REPAIRS.to_string(), "0".to_string());
+        let schema = OutputSchema::from_metadata(&metadata, Path::new("/"))
+            .unwrap()
+            .unwrap();
+        assert!(schema.id.starts_with("blake3:"));
+        assert_eq!(schema.repairs, 0);
+    }
+
+    #[test]
+    fn accepts_fenced_json_and_reports_every_violation() {
+        let schema = summary_schema();
+        let fenced = "```json\n{\"summary\": \"Parses input\", \"exports\": [\"parse\"]}\n```";
+        assert_eq!(
+            schema.parse_response(fenced).unwrap(),
+            json!({"summary": "Parses input", "exports": ["parse"]})
+        );
+        assert!(schema
+            .parse_response("Here it is: {\"summary\": \"x\", \"exports\": []} Done.")
+            .is_ok());
+
+        let errors = schema
+            .parse_response(r#"{"summary": 3, "risks": ["severe"], "extra": true}"#)
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                "$: missing required field 'exports'".to_string(),
+                "$.extra: unexpected field".to_string(),
+                "$.risks[0]: must be one of [\"low\",\"medium\",\"high\"]".to_string(),
+                "$.summary: expected string".to_string(),
+            ]
+        );
+        assert!(schema.parse_response("no json here").is_err());
+    }
+}
@@ -32,6 +32,13 @@ impl PromptContract {
         })
     }
 
+    /// Use `template` as the user prompt for both files and directories
+    pub fn with_user_prompt(mut self, template: &str) -> Self {
+        self.user_prompt_file = template.to_string();
+        self.user_prompt_directory = template.to_string();
+        self
+    }
+
     pub fn render_user_prompt(&self, node_type: NodeType, path: &str, file_size: Option<u64>) -> String {
         let template = match node_type {
             NodeType::File { .. } => &self.user_prompt_file,
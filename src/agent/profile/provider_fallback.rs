@@ -0,0 +1,30 @@
+//! Provider fallback chain read from agent metadata.
+
+use crate::agent::profile::metadata_types::AgentMetadata;
+
+/// Comma-separated provider names to fail over to, in order
+pub const KEY_FALLBACK_PROVIDERS: &str = "fallback_providers";
+
+/// Fallback providers declared by the agent; empty when none are set
+pub fn fallback_providers(metadata: &AgentMetadata) -> Vec<String> {
+    metadata.get_list(KEY_FALLBACK_PROVIDERS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_comma_separated_provider_names() {
+        let mut metadata = AgentMetadata::new();
+        assert!(fallback_providers(&metadata).is_empty());
+        metadata.insert(
+            KEY_FALLBACK_PROVIDERS.to_string(),
+            " openai-backup, ,ollama ".to_string(),
+        );
+        assert_eq!(
+            fallback_providers(&metadata),
+            vec!["openai-backup".to_string(), "ollama".to_string()]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Workspace tools an agent may call during generation, read from agent metadata.
+//!
+//! Agents that list `tools` generate agentically: the provider may call the listed tools
+//! to read other files and their context before it answers. Each frame's tool loop is
+//! bounded by a step count and a token total.
+//!
+//! ```toml
+//! [metadata]
+//! tools = "read_file,get_context,list_dir"
+//! tool_max_steps = "6"
+//! tool_max_tokens = "40000"
+//! ```
+
+use crate::agent::identity::AgentIdentity;
+use crate::agent::profile::metadata_types::AgentMetadata;
+use crate::error::ApiError;
+
+pub const KEY_TOOLS: &str = "tools";
+pub const KEY_TOOL_MAX_STEPS: &str = "tool_max_steps";
+pub const KEY_TOOL_MAX_TOKENS: &str = "tool_max_tokens";
+
+/// Tools agents can be given
+pub const WORKSPACE_TOOLS: &[&str] = &["read_file", "get_context", "list_dir"];
+
+/// Tool-calling rounds per frame when the agent does not set a limit
+pub const DEFAULT_TOOL_MAX_STEPS: usize = 8;
+/// Tokens a frame's tool loop may spend when the agent does not set a limit
+pub const DEFAULT_TOOL_MAX_TOKENS: u64 = 64_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolPolicy {
+    /// Enabled tools, in declaration order
+    pub tools: Vec<String>,
+    pub max_steps: usize,
+    pub max_tokens: u64,
+}
+
+impl ToolPolicy {
+    /// Tool policy of the agent; `None` for single-shot agents
+    pub fn from_agent(agent: &AgentIdentity) -> Result<Option<Self>, ApiError> {
+        Self::from_metadata(&agent.metadata)
+            .map_err(|e| ApiError::ConfigError(format!("Agent '{}': {}", agent.agent_id, e)))
+    }
+
+    /// Read the policy from metadata; missing limits use the defaults
+    pub fn from_metadata(metadata: &AgentMetadata) -> Result<Option<Self>, String> {
+        let mut tools: Vec<String> = Vec::new();
+        for name in metadata.get_list(KEY_TOOLS) {
+            if !WORKSPACE_TOOLS.contains(&name.as_str()) {
+                return Err(format!(
+                    "unknown tool '{}' in {} (available: {})",
+                    name,
+                    KEY_TOOLS,
+                    WORKSPACE_TOOLS.join(", ")
+                ));
+            }
+            if !tools.contains(&name) {
+                tools.push(name);
+            }
+        }
+        if tools.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            tools,
+            max_steps: parse_limit(metadata, KEY_TOOL_MAX_STEPS)?
+                .map(|steps| steps as usize)
+                .unwrap_or(DEFAULT_TOOL_MAX_STEPS),
+            max_tokens: parse_limit(metadata, KEY_TOOL_MAX_TOKENS)?
+                .unwrap_or(DEFAULT_TOOL_MAX_TOKENS),
+        }))
+    }
+}
+
+fn parse_limit(metadata: &AgentMetadata, key: &str) -> Result<Option<u64>, String> {
+    let Some(value) = metadata.get(key) else {
+        return Ok(None);
+    };
+    match value.trim().parse::<u64>() {
+        Ok(limit) if limit > 0 => Ok(Some(limit)),
+        _ => Err(format!(
+            "{} must be a positive number, got '{}'",
+            key, value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tools_and_limits_and_rejects_unknown_tools() {
+        let mut metadata = AgentMetadata::new();
+        assert!(ToolPolicy::from_metadata(&metadata).unwrap().is_none());
+
+        metadata.insert(
+            KEY_TOOLS.to_string(),
+            "list_dir, read_file,list_dir".to_string(),
+        );
+        metadata.insert(KEY_TOOL_MAX_STEPS.to_string(), "3".to_string());
+        let policy = ToolPolicy::from_metadata(&metadata).unwrap().unwrap();
+        assert_eq!(policy.tools, vec!["list_dir", "read_file"]);
+        assert_eq!(policy.max_steps, 3);
+        assert_eq!(policy.max_tokens, DEFAULT_TOOL_MAX_TOKENS);
+
+        metadata.insert(KEY_TOOL_MAX_TOKENS.to_string(), "0".to_string());
+        assert!(ToolPolicy::from_metadata(&metadata).is_err());
+        metadata.remove(KEY_TOOL_MAX_TOKENS);
+        metadata.insert(KEY_TOOLS.to_string(), "read_file,shell".to_string());
+        assert!(ToolPolicy::from_metadata(&metadata)
+            .unwrap_err()
+            .contains("unknown tool 'shell'"));
+    }
+}
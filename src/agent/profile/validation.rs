@@ -1,6 +1,9 @@
 //! Agent configuration validation owned by the agent domain.
 
+use super::chunking_policy::ChunkingPolicy;
 use super::config::AgentConfig;
+use super::output_schema::OutputSchema;
+use super::tool_policy::ToolPolicy;
 use crate::agent::identity::AgentRole;
 use std::collections::HashMap;
 
@@ -34,5 +37,17 @@ pub fn validate_agent_config(
         }
     }
 
+    ChunkingPolicy::from_metadata(&agent.metadata)?;
+    ToolPolicy::from_metadata(&agent.metadata)?;
+    if agent
+        .metadata
+        .contains_key(super::output_schema::KEY_OUTPUT_SCHEMA)
+    {
+        let base_dir = crate::config::xdg::config_home()
+            .map_err(|e| e.to_string())?
+            .join("meld");
+        OutputSchema::from_metadata(&agent.metadata, &base_dir)?;
+    }
+
     Ok(())
 }
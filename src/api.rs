@@ -9,10 +9,12 @@ use crate::concurrency::NodeLockManager;
 use crate::context::frame::{Basis, Frame, FrameStorage};
 use crate::context::query::get_node_query;
 use crate::context::queue::FrameGenerationQueue;
+use crate::context::search::{LexicalIndex, SearchIndex};
 use crate::error::ApiError;
 use crate::heads::HeadIndex;
 use crate::metadata::frame_write_contract::validate_frame_metadata;
-use crate::store::NodeRecordStore;
+use crate::provider::cache::ResponseCache;
+use crate::store::{BlobStore, NodeRecordStore};
 use crate::types::{FrameID, NodeID};
 use crate::views::ViewPolicy;
 use hex;
@@ -44,6 +46,14 @@ pub struct ContextApi {
     lock_manager: Arc<NodeLockManager>,
     /// Workspace root for persistence (optional)
     workspace_root: Option<PathBuf>,
+    /// Scanned file content cache (optional)
+    blob_store: Option<Arc<BlobStore>>,
+    /// Provider response cache (optional)
+    response_cache: Option<Arc<ResponseCache>>,
+    /// Semantic search index fed by new heads (optional)
+    search_index: Option<Arc<SearchIndex>>,
+    /// Lexical search index fed by head changes (optional)
+    lexical_index: Option<Arc<LexicalIndex>>,
 }
 
 impl ContextApi {
@@ -64,6 +74,10 @@ impl ContextApi {
             provider_registry,
             lock_manager,
             workspace_root: None,
+            blob_store: None,
+            response_cache: None,
+            search_index: None,
+            lexical_index: None,
         }
     }
 
@@ -85,9 +99,37 @@ impl ContextApi {
             provider_registry,
             lock_manager,
             workspace_root: Some(workspace_root),
+            blob_store: None,
+            response_cache: None,
+            search_index: None,
+            lexical_index: None,
         }
     }
 
+    /// Cache scanned file bytes in `blob_store` and ground file generation on them.
+    pub fn with_blob_store(mut self, blob_store: Arc<BlobStore>) -> Self {
+        self.blob_store = Some(blob_store);
+        self
+    }
+
+    /// Answer repeated provider requests from `response_cache`.
+    pub fn with_response_cache(mut self, response_cache: Arc<ResponseCache>) -> Self {
+        self.response_cache = Some(response_cache);
+        self
+    }
+
+    /// Journal new heads in `search_index` so searches embed them.
+    pub fn with_search_index(mut self, search_index: Arc<SearchIndex>) -> Self {
+        self.search_index = Some(search_index);
+        self
+    }
+
+    /// Journal nodes whose heads change in `lexical_index` so searches re-index them.
+    pub fn with_lexical_index(mut self, lexical_index: Arc<LexicalIndex>) -> Self {
+        self.lexical_index = Some(lexical_index);
+        self
+    }
+
     /// Persist indices to disk if workspace root is configured
     fn persist_indices(&self) -> Result<(), ApiError> {
         if let Some(ref workspace_root) = self.workspace_root {
@@ -261,6 +303,9 @@ impl ContextApi {
         // Persist indices to disk
         self.persist_indices()?;
 
+        // Queue the new head for the search indexes
+        self.journal_search_update(node_id, Some(frame.frame_id));
+
         // TODO: Update node record's frame_set_root
         // This requires retrieving/updating the FrameMerkleSet and storing it.
         // For Phase 2B MVP, we'll skip this and rely on head index.
@@ -275,6 +320,21 @@ impl ContextApi {
         Ok(frame.frame_id)
     }
 
+    /// Journal a head change for the attached search indexes. Searches still work from a
+    /// stale index, so failures are only logged.
+    fn journal_search_update(&self, node_id: NodeID, new_head: Option<FrameID>) {
+        if let (Some(search_index), Some(frame_id)) = (&self.search_index, new_head) {
+            if let Err(e) = search_index.record_head(node_id, frame_id) {
+                warn!(error = %e, "Failed to journal frame for the search index");
+            }
+        }
+        if let Some(lexical_index) = &self.lexical_index {
+            if let Err(e) = lexical_index.record_node(node_id) {
+                warn!(error = %e, "Failed to journal node for the lexical index");
+            }
+        }
+    }
+
     /// Collect node_id and all descendant node IDs (BFS from record.children).
     pub fn collect_subtree_node_ids(&self, node_id: NodeID) -> Result<HashSet<NodeID>, ApiError> {
         let mut set = HashSet::new();
@@ -325,6 +385,7 @@ impl ContextApi {
             let before = head_index.get_all_heads_for_node(&nid).len();
             head_index.tombstone_heads_for_node(&nid);
             head_entries_tombstoned += before as u64;
+            self.journal_search_update(nid, None);
         }
         self.persist_indices()?;
         Ok(TombstoneResult {
@@ -356,6 +417,7 @@ impl ContextApi {
             let before = head_index.get_all_heads_for_node(&nid).len();
             head_index.restore_heads_for_node(&nid);
             head_entries_restored += before as u64;
+            self.journal_search_update(nid, None);
         }
         self.persist_indices()?;
         Ok(RestoreResult {
@@ -406,9 +468,9 @@ impl ContextApi {
 
     /// Compose frames from multiple sources
     ///
-    /// Combines context frames from multiple sources (current node, parent, siblings, related)
-    /// into a composite view. Composition is read-time only, policy-driven, and produces
-    /// bounded, deterministic results.
+    /// Combines context frames from multiple sources (current node, parent, siblings, related,
+    /// ancestors, descendants) into a composite view. Composition is read-time only,
+    /// policy-driven, and produces bounded, deterministic results.
     ///
     /// # Arguments
     /// * `node_id` - NodeID to compose context for
@@ -668,6 +730,26 @@ impl ContextApi {
         &self.node_store
     }
 
+    /// Scanned file content cache, when enabled
+    pub fn blob_store(&self) -> Option<&Arc<BlobStore>> {
+        self.blob_store.as_ref()
+    }
+
+    /// Provider response cache, when enabled
+    pub fn response_cache(&self) -> Option<&Arc<ResponseCache>> {
+        self.response_cache.as_ref()
+    }
+
+    /// Semantic search index, when attached
+    pub fn search_index(&self) -> Option<&Arc<SearchIndex>> {
+        self.search_index.as_ref()
+    }
+
+    /// Lexical search index, when attached
+    pub fn lexical_index(&self) -> Option<&Arc<LexicalIndex>> {
+        self.lexical_index.as_ref()
+    }
+
     /// Get access to head index (for tooling)
     pub fn head_index(&self) -> &Arc<parking_lot::RwLock<HeadIndex>> {
         &self.head_index
@@ -13,7 +13,9 @@ pub use parse::{
     AgentCommands, Cli, Commands, ContextCommands, ProviderCommands, WorkspaceCommands,
 };
 pub use presentation::{
-    format_context_json_output, format_context_text_output,
+    format_composed_json_output, format_composed_text_output,
+    format_context_json_output, format_context_text_output, format_generation_estimate_text, format_generation_runs_text,
+    format_import_report_text, format_search_report_text,
     format_ignore_result, format_init_preview, format_init_summary,
     format_list_deleted_result, format_validate_result_text,
     format_agent_list_result_json, format_agent_list_result_text,
@@ -38,6 +38,12 @@ pub fn context_command_name(command: &ContextCommands) -> &'static str {
         ContextCommands::Generate { .. } => "generate",
         ContextCommands::Regenerate { .. } => "regenerate",
         ContextCommands::Get { .. } => "get",
+        ContextCommands::Compose { .. } => "compose",
+        ContextCommands::Export { .. } => "export",
+        ContextCommands::Import { .. } => "import",
+        ContextCommands::Search { .. } => "search",
+        ContextCommands::Resume { .. } => "resume",
+        ContextCommands::Runs { .. } => "runs",
     }
 }
 
@@ -370,7 +370,7 @@ pub enum ProviderCommands {
         /// Endpoint URL
         #[arg(long)]
         endpoint: Option<String>,
-        /// API key
+        /// API key, or a reference: env:VAR, file:PATH, cmd:COMMAND
         #[arg(long)]
         api_key: Option<String>,
         /// Use interactive mode (default)
@@ -390,7 +390,7 @@ pub enum ProviderCommands {
         /// Update endpoint URL
         #[arg(long)]
         endpoint: Option<String>,
-        /// Update API key
+        /// Update API key (or env:VAR, file:PATH, cmd:COMMAND reference)
         #[arg(long)]
         api_key: Option<String>,
         /// Editor to use (default: $EDITOR)
@@ -423,24 +423,50 @@ pub enum ContextCommands {
         #[arg(value_name = "PATH", index = 1, conflicts_with = "node")]
         path_positional: Option<PathBuf>,
 
-        /// Agent to use for generation
+        /// Agent to use for generation (repeat to write one frame per agent in a single plan)
         #[arg(long)]
-        agent: Option<String>,
+        agent: Vec<String>,
 
         /// Provider to use for generation (required)
         #[arg(long)]
         provider: Option<String>,
 
+        /// Provider to fail over to when the previous one keeps failing (repeat for a chain;
+        /// defaults to the agent's fallback_providers)
+        #[arg(long = "fallback-provider", value_name = "PROVIDER")]
+        fallback_provider: Vec<String>,
+
         /// Frame type (defaults to context-<agent_id>)
         #[arg(long)]
         frame_type: Option<String>,
 
-        /// Generate even if head frame exists
+        /// Generate even if head frame exists; providers are called instead of the response cache
         #[arg(long)]
         force: bool,
         /// Disable recursive generation for directory targets
         #[arg(long)]
         no_recursive: bool,
+
+        /// Call providers even when an identical request is in the response cache
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Estimate tokens and cost for the plan without calling providers
+        #[arg(long)]
+        estimate: bool,
+
+        /// Stop generating once spend reaches this many USD (needs provider pricing)
+        #[arg(long, value_name = "USD")]
+        max_cost: Option<f64>,
+
+        /// Stop generating once prompt plus completion tokens reach this count
+        #[arg(long, value_name = "TOKENS")]
+        max_tokens: Option<u64>,
+
+        /// Stream responses: a single node's text is printed as it arrives, batch runs
+        /// emit partial output as provider_response_chunk progress events
+        #[arg(long, conflicts_with = "estimate")]
+        stream: bool,
     },
     /// Re generate a context frame for a node and prefer directory only reroll
     Regenerate {
@@ -494,10 +520,15 @@ pub enum ContextCommands {
         #[arg(long, default_value = "10")]
         max_frames: usize,
 
-        /// Ordering policy: recency or deterministic
+        /// Ordering policy: recency, deterministic, or relevance
         #[arg(long, default_value = "recency")]
         ordering: String,
 
+        /// Filter expression, repeatable: key=value, key!=value, key~=glob, since=<time>,
+        /// until=<time>; join alternatives with '|' and negate with a leading '!'
+        #[arg(long = "where", value_name = "EXPR")]
+        where_clauses: Vec<String>,
+
         /// Concatenate frame contents with separator
         #[arg(long)]
         combine: bool,
@@ -518,4 +549,139 @@ pub enum ContextCommands {
         #[arg(long)]
         include_deleted: bool,
     },
+    /// Compose frames from the target node and related nodes into one bundle
+    Compose {
+        /// Target node by NodeID (hex string)
+        #[arg(long, conflicts_with_all = ["path", "path_positional"])]
+        node: Option<String>,
+
+        /// Target node by workspace-relative or absolute path
+        #[arg(long, value_name = "PATH", conflicts_with = "node")]
+        path: Option<PathBuf>,
+
+        /// Target path (positional; same as --path)
+        #[arg(value_name = "PATH", index = 1, conflicts_with = "node")]
+        path_positional: Option<PathBuf>,
+
+        /// Comma separated sources: current, parent, siblings, ancestors[:depth],
+        /// descendants[:depth[:max_nodes]], node:<id>
+        #[arg(long, default_value = "current,ancestors")]
+        sources: String,
+
+        /// Filter by frame type
+        #[arg(long)]
+        frame_type: Option<String>,
+
+        /// Maximum frames to return
+        #[arg(long, default_value = "50")]
+        max_frames: usize,
+
+        /// Ordering policy: recency, deterministic, or relevance
+        #[arg(long, default_value = "relevance")]
+        ordering: String,
+
+        /// Output format: text or json
+        #[arg(long, default_value = "text")]
+        format: String,
+
+        /// Include metadata fields in output
+        #[arg(long)]
+        include_metadata: bool,
+    },
+    /// Export head frames for a subtree as a single document
+    Export {
+        /// Target node by NodeID (hex string)
+        #[arg(long, conflicts_with_all = ["path", "path_positional"])]
+        node: Option<String>,
+
+        /// Target node by workspace-relative or absolute path
+        #[arg(long, value_name = "PATH", conflicts_with = "node")]
+        path: Option<PathBuf>,
+
+        /// Target path (positional; same as --path)
+        #[arg(value_name = "PATH", index = 1, conflicts_with = "node")]
+        path_positional: Option<PathBuf>,
+
+        /// Output format: markdown, json, or jsonl
+        #[arg(long, default_value = "markdown")]
+        format: String,
+
+        /// Only export frames of this type
+        #[arg(long)]
+        frame_type: Option<String>,
+
+        /// Maximum depth below the target (0 exports only the target)
+        #[arg(long)]
+        max_depth: Option<usize>,
+
+        /// Approximate token budget for frame content
+        #[arg(long)]
+        max_tokens: Option<usize>,
+
+        /// Write the bundle to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Import frames from an exported json or jsonl bundle
+    Import {
+        /// Bundle file written by `context export --format json|jsonl`
+        bundle: PathBuf,
+
+        /// Report what would be imported without writing
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output format: text or json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+    /// Search head frames by meaning, or by keywords and "quoted phrases" with --lexical
+    Search {
+        /// Natural-language query; with --lexical, words and "quoted phrases"
+        query: String,
+
+        /// Number of results to return
+        #[arg(long, default_value = "10")]
+        top_k: usize,
+
+        /// Only search frames of this type
+        #[arg(long)]
+        frame_type: Option<String>,
+
+        /// Only search frames written by this agent
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Rank with the offline BM25 index over frame text and paths
+        #[arg(long)]
+        lexical: bool,
+
+        /// Output format: text or json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+    /// Resume an interrupted or failed generation plan
+    Resume {
+        /// Plan to resume (defaults to the newest incomplete plan)
+        #[arg(value_name = "PLAN_ID")]
+        plan_id: Option<String>,
+
+        /// Stop generating once spend reaches this many USD (needs provider pricing)
+        #[arg(long, value_name = "USD")]
+        max_cost: Option<f64>,
+
+        /// Stop generating once prompt plus completion tokens reach this count
+        #[arg(long, value_name = "TOKENS")]
+        max_tokens: Option<u64>,
+    },
+    /// List in-flight and past generation plans
+    Runs {
+        /// Output format: text or json
+        #[arg(long, default_value = "text")]
+        format: String,
+
+        /// Maximum number of plans to list
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
 }
@@ -11,7 +11,11 @@ pub use agent::{
     format_agent_show_result_json, format_agent_show_result_text,
     format_validation_result, format_validation_results_all,
 };
-pub use context::{format_context_json_output, format_context_text_output};
+pub use context::{
+    format_composed_json_output, format_composed_text_output, format_context_json_output,
+    format_context_text_output, format_generation_estimate_text, format_generation_runs_text,
+    format_import_report_text, format_search_report_text,
+};
 pub use init::{format_init_preview, format_init_summary};
 pub use provider::{
     format_provider_list_result_json, format_provider_list_result_text,
@@ -1,6 +1,11 @@
-//! Context get presentation: text and json formatters.
+//! Context get, compose, import, search, generation estimate, and generation runs presentation:
+//! text and json formatters.
 
 use crate::api::NodeContext;
+use crate::context::bundle::{ImportReport, SkipReason};
+use crate::context::generation::{GenerationBudget, GenerationRunSummary, PlanEstimate};
+use crate::context::query::{ComposedContext, ComposedFrame};
+use crate::context::search::SearchReport;
 use crate::error::ApiError;
 use crate::metadata::frame_types::project_visible_metadata;
 use serde_json::json;
@@ -102,7 +107,13 @@ pub fn format_context_json_output(
                 }
                 frame_obj["metadata"] = json!(project_visible_metadata(&frame.metadata));
             }
-            if let Ok(text) = frame.text_content() {
+            if let Some(value) = frame
+                .is_json()
+                .then(|| frame.json_content::<serde_json::Value>().ok())
+                .flatten()
+            {
+                frame_obj["content"] = value;
+            } else if let Ok(text) = frame.text_content() {
                 frame_obj["content"] = json!(text);
             } else {
                 frame_obj["content"] = json!(null);
@@ -127,3 +138,221 @@ pub fn format_context_json_output(
     serde_json::to_string_pretty(&result)
         .map_err(|e| ApiError::ConfigError(format!("Failed to serialize JSON: {}", e)))
 }
+
+pub fn format_composed_text_output(
+    composed: &ComposedContext,
+    include_metadata: bool,
+) -> Result<String, ApiError> {
+    let frames: Vec<&ComposedFrame> = composed
+        .frames
+        .iter()
+        .filter(|f| !f.frame.is_deleted())
+        .collect();
+
+    let mut output = format!(
+        "Node: {}\nPath: {}\nFrames: {}\n\n",
+        hex::encode(composed.node_id),
+        composed.path.display(),
+        frames.len()
+    );
+    if frames.is_empty() {
+        output.push_str("No frames found.");
+        return Ok(output);
+    }
+    for (i, composed_frame) in frames.iter().enumerate() {
+        let frame = &composed_frame.frame;
+        let path = composed_frame
+            .path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| hex::encode(composed_frame.node_id));
+        output.push_str(&format!("--- Frame {} ({}) ---\n", i + 1, path));
+        if include_metadata {
+            output.push_str(&format!("Frame ID: {}\n", hex::encode(frame.frame_id)));
+            output.push_str(&format!("Frame Type: {}\n", frame.frame_type));
+            if let Some(agent_id) = frame.agent_id() {
+                output.push_str(&format!("Agent: {}\n", agent_id));
+            }
+            output.push_str(&format!("Timestamp: {:?}\n", frame.timestamp));
+            output.push('\n');
+        }
+        if let Ok(text) = frame.text_content() {
+            output.push_str(&format!("Content:\n{}\n", text));
+        } else {
+            output.push_str("Content: [Binary content - not UTF-8]\n");
+        }
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+pub fn format_composed_json_output(
+    composed: &ComposedContext,
+    include_metadata: bool,
+) -> Result<String, ApiError> {
+    let frames_json: Vec<serde_json::Value> = composed
+        .frames
+        .iter()
+        .filter(|f| !f.frame.is_deleted())
+        .map(|composed_frame| {
+            let frame = &composed_frame.frame;
+            let mut frame_obj = json!({
+                "node_id": hex::encode(composed_frame.node_id),
+                "path": composed_frame.path.as_ref().map(|p| p.to_string_lossy()),
+                "frame_id": hex::encode(frame.frame_id),
+                "frame_type": frame.frame_type,
+                "timestamp": frame.timestamp,
+            });
+            if include_metadata {
+                if let Some(agent_id) = frame.agent_id() {
+                    frame_obj["agent_id"] = json!(agent_id);
+                }
+                frame_obj["metadata"] = json!(project_visible_metadata(&frame.metadata));
+            }
+            if let Ok(text) = frame.text_content() {
+                frame_obj["content"] = json!(text);
+            } else {
+                frame_obj["content"] = json!(null);
+                frame_obj["content_binary"] = json!(true);
+            }
+            frame_obj
+        })
+        .collect();
+
+    let result = json!({
+        "node_id": hex::encode(composed.node_id),
+        "path": composed.path.to_string_lossy(),
+        "frames": frames_json,
+        "frame_count": frames_json.len(),
+    });
+
+    serde_json::to_string_pretty(&result)
+        .map_err(|e| ApiError::ConfigError(format!("Failed to serialize JSON: {}", e)))
+}
+
+pub fn format_import_report_text(report: &ImportReport) -> String {
+    let mut output = format!(
+        "{}Matched {}/{} nodes ({:.0}% coverage)\n",
+        if report.dry_run { "[dry run] " } else { "" },
+        report.nodes_matched,
+        report.nodes_total,
+        report.coverage() * 100.0
+    );
+    output.push_str(&format!(
+        "Frames imported: {}\nFrames unchanged: {}\nFrames rejected: {}\n",
+        report.frames_imported,
+        report.frames_unchanged,
+        report.rejected.len()
+    ));
+    for skipped in &report.skipped {
+        let reason = match skipped.reason {
+            SkipReason::Missing => "not in tree",
+            SkipReason::ContentChanged => "content changed",
+        };
+        output.push_str(&format!("  skipped {}: {}\n", skipped.path, reason));
+    }
+    for rejected in &report.rejected {
+        output.push_str(&format!(
+            "  rejected {} [{}]: {}\n",
+            rejected.path, rejected.frame_type, rejected.error
+        ));
+    }
+    output
+}
+
+pub fn format_search_report_text(report: &SearchReport) -> String {
+    if report.hits.is_empty() {
+        return format!("No frames match '{}'.", report.query);
+    }
+    let mut output = String::new();
+    for (rank, hit) in report.hits.iter().enumerate() {
+        output.push_str(&format!(
+            "{}. {} [{}] score {:.3}
+   {}
+",
+            rank + 1,
+            hit.path,
+            hit.frame_type,
+            hit.score,
+            hit.snippet
+        ));
+    }
+    output
+}
+
+pub fn format_generation_runs_text(runs: &[GenerationRunSummary]) -> String {
+    if runs.is_empty() {
+        return "No generation plans found.".to_string();
+    }
+    use comfy_table::Table;
+    let mut table = Table::new();
+    table.load_preset(comfy_table::presets::UTF8_FULL);
+    table.set_header(vec![
+        "Plan ID", "Status", "Done", "Failed", "Pending", "Attempts", "Updated", "Target",
+    ]);
+    for run in runs {
+        let updated = chrono::DateTime::from_timestamp_millis(run.updated_at_ms as i64)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        table.add_row(vec![
+            run.plan_id.clone(),
+            run.status.as_str().to_string(),
+            format!("{}/{}", run.completed, run.total_nodes),
+            run.failed.to_string(),
+            run.pending.to_string(),
+            run.attempts.to_string(),
+            updated,
+            run.target_path.clone(),
+        ]);
+    }
+    format!(
+        "{}\n\nResume with `meld context resume <plan_id>`. \
+         A running plan with no active command was interrupted.",
+        table
+    )
+}
+
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map(|c| format!("${:.4}", c))
+        .unwrap_or_else(|| "unknown (no pricing)".to_string())
+}
+
+pub fn format_generation_estimate_text(
+    estimate: &PlanEstimate,
+    budget: &GenerationBudget,
+) -> String {
+    if estimate.total_nodes == 0 {
+        return "Nothing to generate: frames already exist for the requested target.".to_string();
+    }
+    let mut output = format!(
+        "Estimate for {} ({} nodes, {} levels)\n",
+        estimate.target_path, estimate.total_nodes, estimate.total_levels
+    );
+    for provider in &estimate.providers {
+        output.push_str(&format!(
+            "  {} ({}): {} items, ~{} input + ~{} output tokens, cost {}\n",
+            provider.provider_name,
+            provider.model,
+            provider.items,
+            provider.input_tokens,
+            provider.output_tokens,
+            format_cost(provider.cost)
+        ));
+    }
+    output.push_str(&format!(
+        "Total: ~{} tokens, cost {}\n",
+        estimate.total_tokens(),
+        format_cost(estimate.cost)
+    ));
+    if budget
+        .max_tokens
+        .is_some_and(|max| estimate.total_tokens() > max)
+        || matches!((budget.max_cost, estimate.cost), (Some(max), Some(cost)) if cost > max)
+    {
+        output.push_str("Warning: the estimate exceeds the requested budget.\n");
+    }
+    output.push_str(
+        "Output tokens assume each response reaches the provider max_tokens (512 when unset).",
+    );
+    output
+}
@@ -80,6 +80,45 @@ pub fn format_provider_show_result_text(result: &ProviderShowResult) -> String {
     if let Some(ref stop) = provider.default_options.stop {
         output.push_str(&format!("  stop: {:?}\n", stop));
     }
+    if let Some(pricing) = &provider.pricing {
+        output.push_str("\nPricing (USD per million tokens):\n");
+        output.push_str(&format!("  input: {}\n", pricing.input_per_million));
+        output.push_str(&format!("  output: {}\n", pricing.output_per_million));
+    }
+    if let Some(limits) = &provider.rate_limits {
+        output.push_str("\nRate Limits:\n");
+        if let Some(rpm) = limits.requests_per_minute {
+            output.push_str(&format!("  requests_per_minute: {}\n", rpm));
+        }
+        if let Some(tpm) = limits.tokens_per_minute {
+            output.push_str(&format!("  tokens_per_minute: {}\n", tpm));
+        }
+        if let Some(concurrency) = limits.max_concurrency {
+            output.push_str(&format!("  max_concurrency: {}\n", concurrency));
+        }
+    }
+    if let Some(transport) = &provider.transport {
+        output.push_str("\nTransport:\n");
+        if let Some(proxy) = &transport.proxy {
+            output.push_str(&format!("  proxy: {}\n", proxy));
+        }
+        if !transport.no_proxy.is_empty() {
+            output.push_str(&format!("  no_proxy: {}\n", transport.no_proxy.join(", ")));
+        }
+        if let Some(ca_bundle) = &transport.ca_bundle {
+            output.push_str(&format!("  ca_bundle: {}\n", ca_bundle.display()));
+        }
+        if let Some(secs) = transport.connect_timeout_secs {
+            output.push_str(&format!("  connect_timeout_secs: {}\n", secs));
+        }
+        if let Some(secs) = transport.request_timeout_secs {
+            output.push_str(&format!("  request_timeout_secs: {}\n", secs));
+        }
+        if !transport.headers.is_empty() {
+            let names: Vec<&str> = transport.headers.keys().map(String::as_str).collect();
+            output.push_str(&format!("  headers: {}\n", names.join(", ")));
+        }
+    }
     output
 }
 
@@ -89,10 +128,19 @@ pub fn format_provider_show_result_json(result: &ProviderShowResult) -> String {
     let api_key_status_str = result.api_key_status.as_deref().map(|s| match s {
         s if s.contains("from config") => "set_from_config",
         s if s.contains("from environment") => "set_from_env",
+        s if s.contains("command not run") => "command_not_run",
+        s if s.starts_with("Set (from ") => "set_from_reference",
+        s if s.starts_with("Unresolved") => "unresolved",
         s if s.contains("Not set") => "not_set",
         s if s.contains("Not required") => "not_required",
         _ => "unknown",
     });
+    // Only the reference is shown; plaintext keys display as "plaintext"
+    let api_key_source = result
+        .api_key_status
+        .as_ref()
+        .and_then(|_| provider.api_key_source())
+        .map(|source| source.to_string());
     let default_options = json!({
         "temperature": provider.default_options.temperature,
         "max_tokens": provider.default_options.max_tokens,
@@ -101,13 +149,28 @@ pub fn format_provider_show_result_json(result: &ProviderShowResult) -> String {
         "presence_penalty": provider.default_options.presence_penalty,
         "stop": provider.default_options.stop,
     });
+    // Header values may be gateway credentials, so only their names are listed
+    let transport = provider.transport.as_ref().map(|transport| {
+        json!({
+            "proxy": transport.proxy,
+            "no_proxy": transport.no_proxy,
+            "ca_bundle": transport.ca_bundle,
+            "connect_timeout_secs": transport.connect_timeout_secs,
+            "request_timeout_secs": transport.request_timeout_secs,
+            "headers": transport.headers.keys().collect::<Vec<_>>(),
+        })
+    });
     let out = json!({
         "provider_name": provider.provider_name.as_deref().unwrap_or("unknown"),
         "provider_type": type_str,
         "model": provider.model,
         "endpoint": provider.endpoint,
         "api_key_status": api_key_status_str,
+        "api_key_source": api_key_source,
         "default_options": default_options,
+        "pricing": provider.pricing,
+        "rate_limits": provider.rate_limits,
+        "transport": transport,
     });
     serde_json::to_string_pretty(&out).unwrap_or_else(|_| "{}".to_string())
 }
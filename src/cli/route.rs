@@ -3,8 +3,16 @@
 use crate::agent::AgentCommandService;
 use crate::api::ContextApi;
 use crate::config::ConfigLoader;
-use crate::context::generation::run::{run_generate, GenerateRequest};
-use crate::context::query::get_node_for_cli;
+use crate::context::bundle::{export_for_cli, import_for_cli, ExportRequest};
+use crate::context::generation::run::{
+    estimate_generate, resume_generate, run_generate, GenerateRequest,
+};
+use crate::context::generation::{ClassifiedFilesConfig, GenerationBudget, GenerationRunStore};
+use crate::context::query::{compose_for_cli, get_node_for_cli, RelevanceModel};
+use crate::context::queue::StreamSink;
+use crate::context::search::{
+    search_for_cli, LexicalIndex, SearchConfig, SearchIndex, SearchRequest,
+};
 use crate::error::ApiError;
 use crate::heads::HeadIndex;
 use crate::ignore;
@@ -18,7 +26,9 @@ use crate::workspace::{
     WorkspaceCommandService, WorkspaceStatusRequest,
 };
 use serde_json::json;
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -38,6 +48,10 @@ pub struct RunContext {
     store_path: PathBuf,
     frame_storage_path: PathBuf,
     progress: Arc<ProgressRuntime>,
+    generation_runs: Arc<GenerationRunStore>,
+    relevance: RelevanceModel,
+    classified_files: ClassifiedFilesConfig,
+    search: SearchConfig,
 }
 
 impl RunContext {
@@ -51,6 +65,11 @@ impl RunContext {
         Arc::clone(&self.progress)
     }
 
+    /// Persisted generation plans and per-item status.
+    pub fn generation_runs(&self) -> Arc<GenerationRunStore> {
+        Arc::clone(&self.generation_runs)
+    }
+
     /// Create run context from workspace root and optional config path. Uses ConfigLoader only.
     pub fn new(workspace_root: PathBuf, config_path: Option<PathBuf>) -> Result<Self, ApiError> {
         let config = if let Some(ref cfg_path) = config_path {
@@ -72,6 +91,8 @@ impl RunContext {
             )))
         })?;
         let node_store = Arc::new(SledNodeRecordStore::from_db(db.clone()));
+        let generation_runs =
+            Arc::new(GenerationRunStore::new(db.clone()).map_err(ApiError::StorageError)?);
         let progress = Arc::new(ProgressRuntime::new(db).map_err(ApiError::StorageError)?);
 
         std::fs::create_dir_all(&frame_storage_path)
@@ -98,12 +119,13 @@ impl RunContext {
         let mut provider_registry = crate::provider::ProviderRegistry::new();
         provider_registry.load_from_config(&config)?;
         provider_registry.load_from_xdg()?;
+        provider_registry.set_workspace_root(&workspace_root);
 
         let agent_registry = Arc::new(parking_lot::RwLock::new(agent_registry));
         let provider_registry = Arc::new(parking_lot::RwLock::new(provider_registry));
         let lock_manager = Arc::new(crate::concurrency::NodeLockManager::new());
 
-        let api = ContextApi::with_workspace_root(
+        let mut api = ContextApi::with_workspace_root(
             node_store,
             frame_storage,
             head_index,
@@ -112,6 +134,23 @@ impl RunContext {
             lock_manager,
             workspace_root.clone(),
         );
+        if config.system.storage.blob_cache {
+            let blobs_path = crate::config::StorageConfig::blobs_path(&store_path);
+            let blob_store =
+                crate::store::BlobStore::open(&blobs_path).map_err(ApiError::StorageError)?;
+            api = api.with_blob_store(Arc::new(blob_store));
+        }
+        let cache_config = config.system.storage.response_cache;
+        if cache_config.enabled {
+            let cache_path = crate::config::StorageConfig::response_cache_path(&store_path);
+            let cache = crate::provider::cache::ResponseCache::open(&cache_path, &cache_config)
+                .map_err(ApiError::StorageError)?;
+            api = api.with_response_cache(Arc::new(cache));
+        }
+        let search_index_path = crate::config::StorageConfig::search_index_path(&store_path);
+        api = api.with_search_index(Arc::new(SearchIndex::new(search_index_path)));
+        let lexical_index_path = crate::config::StorageConfig::lexical_index_path(&store_path);
+        api = api.with_lexical_index(Arc::new(LexicalIndex::new(lexical_index_path)));
 
         let (store_path, frame_storage_path) =
             config.system.storage.resolve_paths(&workspace_root)?;
@@ -123,6 +162,10 @@ impl RunContext {
             store_path,
             frame_storage_path,
             progress,
+            generation_runs,
+            relevance: config.context.relevance,
+            classified_files: config.context.classified_files,
+            search: config.context.search,
         })
     }
 
@@ -963,6 +1006,7 @@ impl RunContext {
                 )
             };
 
+        let warning = plaintext_api_key_warning(final_api_key.as_deref());
         let mut registry = self.api.provider_registry().write();
         let result = ProviderCommandService::run_create(
             &mut registry,
@@ -974,9 +1018,10 @@ impl RunContext {
             default_options,
         )?;
         Ok(format!(
-            "Provider created: {}\nConfiguration file: {}",
+            "Provider created: {}\nConfiguration file: {}{}",
             result.provider_name,
-            result.config_path.display()
+            result.config_path.display(),
+            warning
         ))
     }
 
@@ -1044,11 +1089,11 @@ impl RunContext {
         } else {
             let prompt = if !env_var.is_empty() {
                 format!(
-                    "API key (optional, will use {} env var if not set)",
+                    "API key or env:VAR / file:PATH / cmd:COMMAND (optional, uses {} if unset)",
                     env_var
                 )
             } else {
-                "API key (optional)".to_string()
+                "API key or env:VAR / file:PATH / cmd:COMMAND (optional)".to_string()
             };
 
             let input: String = Input::new()
@@ -1115,7 +1160,11 @@ impl RunContext {
         } else {
             self.edit_provider_with_editor(provider_name, editor)?;
         }
-        Ok(format!("Provider updated: {}", provider_name))
+        Ok(format!(
+            "Provider updated: {}{}",
+            provider_name,
+            plaintext_api_key_warning(api_key)
+        ))
     }
 
     fn edit_provider_with_editor(
@@ -1247,27 +1296,84 @@ impl RunContext {
                 path_positional,
                 agent,
                 provider,
+                fallback_provider,
                 frame_type,
                 force,
                 no_recursive,
+                no_cache,
+                estimate,
+                max_cost,
+                max_tokens,
+                stream,
             } => {
                 let path_merged = path.as_ref().or(path_positional.as_ref());
                 let request = GenerateRequest {
                     node: node.clone(),
                     path: path_merged.cloned(),
-                    agent: agent.clone(),
+                    agents: agent.clone(),
                     provider: provider.clone(),
+                    fallback_providers: fallback_provider.clone(),
                     frame_type: frame_type.clone(),
                     force: *force,
                     no_recursive: *no_recursive,
+                    no_cache: *no_cache,
+                    stream: *stream,
+                    budget: GenerationBudget {
+                        max_cost: *max_cost,
+                        max_tokens: *max_tokens,
+                    },
+                    classified_files: self.classified_files,
                 };
-                run_generate(
+                if *estimate {
+                    let estimate = estimate_generate(
+                        self.api.as_ref(),
+                        &self.workspace_root,
+                        Some(&self.progress),
+                        Some(session_id),
+                        &request,
+                    )?;
+                    self.progress.emit_event_best_effort(
+                        session_id,
+                        "generation_estimate",
+                        json!({
+                            "plan_id": estimate.plan_id,
+                            "total_nodes": estimate.total_nodes,
+                            "input_tokens": estimate.input_tokens,
+                            "output_tokens": estimate.output_tokens,
+                            "cost": estimate.cost,
+                        }),
+                    );
+                    return Ok(super::format_generation_estimate_text(
+                        &estimate,
+                        &request.budget,
+                    ));
+                }
+                // A single node's streamed response is printed as it arrives; the summary
+                // then starts on its own line
+                let streamed = Arc::new(AtomicBool::new(false));
+                let live_sink: Arc<StreamSink> = {
+                    let streamed = Arc::clone(&streamed);
+                    Arc::new(move |text: &str| {
+                        streamed.store(true, Ordering::Relaxed);
+                        let mut stdout = std::io::stdout().lock();
+                        let _ = stdout.write_all(text.as_bytes());
+                        let _ = stdout.flush();
+                    })
+                };
+                let summary = run_generate(
                     Arc::clone(&self.api),
                     &self.workspace_root,
                     Some(Arc::clone(&self.progress)),
                     Some(session_id),
+                    Some(Arc::clone(&self.generation_runs)),
                     &request,
-                )
+                    Some(live_sink),
+                )?;
+                if streamed.load(Ordering::Relaxed) {
+                    Ok(format!("\n{}", summary))
+                } else {
+                    Ok(summary)
+                }
             }
             ContextCommands::Regenerate {
                 node,
@@ -1282,18 +1388,25 @@ impl RunContext {
                 let request = GenerateRequest {
                     node: node.clone(),
                     path: path_merged.cloned(),
-                    agent: agent.clone(),
+                    agents: agent.iter().cloned().collect(),
                     provider: provider.clone(),
+                    fallback_providers: Vec::new(),
                     frame_type: frame_type.clone(),
                     force: true,
                     no_recursive: !*recursive,
+                    no_cache: true,
+                    stream: false,
+                    budget: GenerationBudget::default(),
+                    classified_files: self.classified_files,
                 };
                 run_generate(
                     Arc::clone(&self.api),
                     &self.workspace_root,
                     Some(Arc::clone(&self.progress)),
                     Some(session_id),
+                    Some(Arc::clone(&self.generation_runs)),
                     &request,
+                    None,
                 )
             }
             ContextCommands::Get {
@@ -1303,6 +1416,7 @@ impl RunContext {
                 frame_type,
                 max_frames,
                 ordering,
+                where_clauses,
                 combine,
                 separator,
                 format,
@@ -1318,6 +1432,8 @@ impl RunContext {
                     frame_type.as_deref(),
                     *max_frames,
                     ordering,
+                    &self.relevance,
+                    where_clauses,
                     *include_deleted,
                 )?;
                 let formatted = match format.as_str() {
@@ -1352,6 +1468,211 @@ impl RunContext {
                 );
                 Ok(formatted)
             }
+            ContextCommands::Compose {
+                node,
+                path,
+                path_positional,
+                sources,
+                frame_type,
+                max_frames,
+                ordering,
+                format,
+                include_metadata,
+            } => {
+                let path_merged = path.as_ref().or(path_positional.as_ref());
+                let composed = compose_for_cli(
+                    self.api.as_ref(),
+                    &self.workspace_root,
+                    node.as_deref(),
+                    path_merged.map(|p| p.as_path()),
+                    sources,
+                    frame_type.as_deref(),
+                    *max_frames,
+                    ordering,
+                    &self.relevance,
+                )?;
+                let formatted = match format.as_str() {
+                    "text" => super::format_composed_text_output(&composed, *include_metadata),
+                    "json" => super::format_composed_json_output(&composed, *include_metadata),
+                    _ => Err(ApiError::ConfigError(format!(
+                        "Invalid format: '{}'. Must be 'text' or 'json'.",
+                        format
+                    ))),
+                }?;
+                self.progress.emit_event_best_effort(
+                    session_id,
+                    "context_compose_summary",
+                    json!({
+                        "node_id": hex::encode(composed.node_id),
+                        "frame_count": composed.frames.len(),
+                        "max_frames": max_frames,
+                        "sources": sources,
+                        "ordering": ordering,
+                        "format": format
+                    }),
+                );
+                Ok(formatted)
+            }
+            ContextCommands::Export {
+                node,
+                path,
+                path_positional,
+                format,
+                frame_type,
+                max_depth,
+                max_tokens,
+                output,
+            } => {
+                let path_merged = path.as_ref().or(path_positional.as_ref());
+                let request = ExportRequest {
+                    frame_type: frame_type.clone(),
+                    max_depth: *max_depth,
+                    max_tokens: *max_tokens,
+                };
+                let (bundle, rendered) = export_for_cli(
+                    self.api.as_ref(),
+                    &self.workspace_root,
+                    node.as_deref(),
+                    path_merged.map(|p| p.as_path()),
+                    format,
+                    &request,
+                )?;
+                let frame_count: usize = bundle.nodes.iter().map(|n| n.frames.len()).sum();
+                self.progress.emit_event_best_effort(
+                    session_id,
+                    "context_export_summary",
+                    json!({
+                        "root": bundle.root,
+                        "node_count": bundle.nodes.len(),
+                        "frame_count": frame_count,
+                        "truncated": bundle.truncated,
+                        "format": format
+                    }),
+                );
+                match output {
+                    Some(output_path) => {
+                        std::fs::write(output_path, rendered).map_err(|e| {
+                            ApiError::StorageError(crate::error::StorageError::IoError(e))
+                        })?;
+                        Ok(format!(
+                            "Exported {} frames from {} nodes to {}",
+                            frame_count,
+                            bundle.nodes.len(),
+                            output_path.display()
+                        ))
+                    }
+                    None => Ok(rendered),
+                }
+            }
+            ContextCommands::Import {
+                bundle,
+                dry_run,
+                format,
+            } => {
+                if !matches!(format.as_str(), "text" | "json") {
+                    return Err(ApiError::ConfigError(format!(
+                        "Invalid format: '{}'. Must be 'text' or 'json'.",
+                        format
+                    )));
+                }
+                let report =
+                    import_for_cli(self.api.as_ref(), &self.workspace_root, bundle, *dry_run)?;
+                self.progress.emit_event_best_effort(
+                    session_id,
+                    "context_import_summary",
+                    json!({
+                        "nodes_total": report.nodes_total,
+                        "nodes_matched": report.nodes_matched,
+                        "frames_imported": report.frames_imported,
+                        "frames_rejected": report.rejected.len(),
+                        "dry_run": dry_run
+                    }),
+                );
+                match format.as_str() {
+                    "text" => Ok(super::format_import_report_text(&report)),
+                    _ => serde_json::to_string_pretty(&report).map_err(|e| {
+                        ApiError::ConfigError(format!("Failed to serialize JSON: {}", e))
+                    }),
+                }
+            }
+            ContextCommands::Search {
+                query,
+                top_k,
+                frame_type,
+                agent,
+                lexical,
+                format,
+            } => {
+                let request = SearchRequest {
+                    query: query.clone(),
+                    top_k: *top_k,
+                    frame_type: frame_type.clone(),
+                    agent_id: agent.clone(),
+                    lexical: *lexical,
+                };
+                let report = search_for_cli(
+                    self.api.as_ref(),
+                    &self.workspace_root,
+                    &self.search,
+                    &request,
+                )?;
+                self.progress.emit_event_best_effort(
+                    session_id,
+                    "context_search_summary",
+                    json!({
+                        "mode": report.mode,
+                        "embedder": report.embedder,
+                        "frames_updated": report.refresh.updated,
+                        "frames_indexed": report.refresh.indexed,
+                        "rebuilt": report.refresh.rebuilt,
+                        "hits": report.hits.len()
+                    }),
+                );
+                match format.as_str() {
+                    "text" => Ok(super::format_search_report_text(&report)),
+                    "json" => serde_json::to_string_pretty(&report).map_err(|e| {
+                        ApiError::ConfigError(format!("Failed to serialize JSON: {}", e))
+                    }),
+                    _ => Err(ApiError::ConfigError(format!(
+                        "Invalid format: '{}'. Must be 'text' or 'json'.",
+                        format
+                    ))),
+                }
+            }
+            ContextCommands::Resume {
+                plan_id,
+                max_cost,
+                max_tokens,
+            } => resume_generate(
+                Arc::clone(&self.api),
+                Some(Arc::clone(&self.progress)),
+                Some(session_id),
+                Arc::clone(&self.generation_runs),
+                plan_id.as_deref(),
+                GenerationBudget {
+                    max_cost: *max_cost,
+                    max_tokens: *max_tokens,
+                },
+            ),
+            ContextCommands::Runs { format, limit } => {
+                let summaries = self
+                    .generation_runs
+                    .list_runs()?
+                    .iter()
+                    .take(*limit)
+                    .map(|record| self.generation_runs.summarize(record))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match format.as_str() {
+                    "text" => Ok(super::format_generation_runs_text(&summaries)),
+                    "json" => serde_json::to_string_pretty(&summaries).map_err(|e| {
+                        ApiError::ConfigError(format!("Failed to serialize JSON: {}", e))
+                    }),
+                    _ => Err(ApiError::ConfigError(format!(
+                        "Invalid format: '{}'. Must be 'text' or 'json'.",
+                        format
+                    ))),
+                }
+            }
         }
     }
 
@@ -1434,3 +1755,13 @@ impl RunContext {
         );
     }
 }
+
+/// Notice appended to provider create/edit output when `--api-key` is a plaintext key
+fn plaintext_api_key_warning(api_key: Option<&str>) -> &'static str {
+    match api_key.map(crate::provider::profile::ApiKeySource::parse) {
+        Some(source) if source.is_plaintext() => {
+            "\nWarning: API key stored in plaintext; use env:VAR, file:PATH, or cmd:COMMAND instead"
+        }
+        _ => "",
+    }
+}
@@ -6,6 +6,9 @@
 
 #[cfg(test)]
 use crate::agent::AgentRole;
+use crate::context::generation::ClassifiedFilesConfig;
+use crate::context::query::RelevanceModel;
+use crate::context::search::SearchConfig;
 use crate::error::ApiError;
 use crate::logging::LoggingConfig;
 #[cfg(test)]
@@ -58,6 +61,24 @@ pub struct MerkleConfig {
     /// Logging configuration
     #[serde(default)]
     pub logging: LoggingConfig,
+
+    /// Context query configuration
+    #[serde(default)]
+    pub context: ContextConfig,
+}
+
+/// Context query configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContextConfig {
+    /// Scoring model used by relevance ordering
+    #[serde(default)]
+    pub relevance: RelevanceModel,
+    /// Generation policy for binary, generated, and vendored files
+    #[serde(default)]
+    pub classified_files: ClassifiedFilesConfig,
+    /// Embedder and batching for semantic search
+    #[serde(default)]
+    pub search: SearchConfig,
 }
 
 /// System-wide configuration
@@ -93,6 +114,7 @@ impl Default for MerkleConfig {
             agents: HashMap::new(),
             system: SystemConfig::default(),
             logging: LoggingConfig::default(),
+            context: ContextConfig::default(),
         }
     }
 }
@@ -250,6 +272,10 @@ mod tests {
             api_key: Some("test-key".to_string()),
             endpoint: None,
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         };
         assert!(provider.validate().is_ok());
 
@@ -275,6 +301,10 @@ mod tests {
                 api_key: None,
                 endpoint: None,
                 default_options: CompletionOptions::default(),
+                pricing: None,
+                rate_limits: None,
+                replay: None,
+                transport: None,
             },
         );
 
@@ -322,6 +352,10 @@ mod tests {
                 api_key: None,
                 endpoint: None,
                 default_options: CompletionOptions::default(),
+                pricing: None,
+                rate_limits: None,
+                replay: None,
+                transport: None,
             },
         );
 
@@ -363,6 +397,10 @@ mod tests {
             api_key: None,
             endpoint: Some("http://localhost:11434".to_string()),
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         };
 
         let model_provider = provider_config.to_model_provider().unwrap();
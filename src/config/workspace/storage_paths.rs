@@ -2,6 +2,7 @@
 
 use crate::config::xdg;
 use crate::error::ApiError;
+use crate::provider::cache::ResponseCacheConfig;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -23,6 +24,15 @@ pub struct StorageConfig {
     /// Path to frame storage (relative to workspace root)
     #[serde(default = "default_frames_path")]
     pub frames_path: PathBuf,
+
+    /// Keep scanned file bytes in a content-addressed blob cache so generation can
+    /// prompt from the scanned snapshot after the live file changes
+    #[serde(default)]
+    pub blob_cache: bool,
+
+    /// Provider response cache: repeated identical requests are answered from disk
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
 }
 
 impl StorageConfig {
@@ -47,6 +57,26 @@ impl StorageConfig {
 
         Ok((store_path, frames_path))
     }
+
+    /// Blob cache directory: a `blobs` sibling of the resolved store path.
+    pub fn blobs_path(store_path: &Path) -> PathBuf {
+        store_path.with_file_name("blobs")
+    }
+
+    /// Provider response cache directory: a `response_cache` sibling of the store path.
+    pub fn response_cache_path(store_path: &Path) -> PathBuf {
+        store_path.with_file_name("response_cache")
+    }
+
+    /// Semantic search index file: a `search_index.bin` sibling of the store path.
+    pub fn search_index_path(store_path: &Path) -> PathBuf {
+        store_path.with_file_name("search_index.bin")
+    }
+
+    /// Lexical search index file: a `lexical_index.bin` sibling of the store path.
+    pub fn lexical_index_path(store_path: &Path) -> PathBuf {
+        store_path.with_file_name("lexical_index.bin")
+    }
 }
 
 impl Default for StorageConfig {
@@ -54,6 +84,8 @@ impl Default for StorageConfig {
         Self {
             store_path: default_store_path(),
             frames_path: default_frames_path(),
+            blob_cache: false,
+            response_cache: ResponseCacheConfig::default(),
         }
     }
 }
@@ -1,11 +1,14 @@
 //! Context domain: frame model, query, mutation, generation, and queue.
 //! Owns context behavior; CLI, agent adapter, and workspace watch consume via explicit contracts.
 
+pub mod bundle;
 pub mod facade;
 pub mod frame;
 pub mod generation;
+pub mod glob;
 pub mod query;
 pub mod queue;
+pub mod search;
 pub mod types;
 
 pub use facade::ContextFacade;
@@ -0,0 +1,118 @@
+//! Context bundles: portable snapshots of head frames for a subtree.
+//! Export renders a bundle for humans or tools; import re-attaches frames by workspace-relative path.
+
+pub mod export;
+pub mod import;
+
+pub use export::{export_bundle, export_for_cli, render_bundle, ExportFormat, ExportRequest};
+pub use import::{import_bundle, import_for_cli, parse_bundle, ImportReport, SkipReason};
+
+use crate::error::ApiError;
+use crate::store::{NodeRecord, NodeRecordStore, NodeType};
+use crate::types::{Hash, NodeID};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Bundle schema version written by export
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Exported context for a subtree
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContextBundle {
+    /// Bundle schema version
+    pub version: u32,
+    /// Workspace-relative path of the export root ("." for the workspace root)
+    pub root: String,
+    /// True when the token budget cut frames from the bundle
+    pub truncated: bool,
+    /// Nodes in deterministic path order
+    pub nodes: Vec<BundleNode>,
+}
+
+/// One node and its head frames
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BundleNode {
+    /// Workspace-relative path ("." for the workspace root)
+    pub path: String,
+    /// NodeID (hex)
+    pub node_id: String,
+    /// "file" or "directory"
+    pub node_type: String,
+    /// File size in bytes (files only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Path-independent content hash (hex): the file content hash, or for directories a
+    /// digest over child names and child content hashes
+    pub content_hash: String,
+    /// Node metadata
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// Head frames ordered by frame type
+    pub frames: Vec<BundleFrame>,
+}
+
+/// One exported frame
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BundleFrame {
+    /// FrameID (hex) at export time
+    pub frame_id: String,
+    /// Frame type
+    pub frame_type: String,
+    /// Agent that produced the frame
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    /// Visible frame metadata
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// Frame creation time (RFC 3339)
+    pub timestamp: String,
+    /// UTF-8 frame content
+    pub content: String,
+}
+
+/// Path-independent content hash for a node
+///
+/// NodeIDs include the absolute path, so they differ between checkouts. Files use their
+/// content hash; directories hash their active children's names and content hashes in name
+/// order. Results are memoized in `cache`.
+pub fn content_digest(
+    node_store: &dyn NodeRecordStore,
+    record: &NodeRecord,
+    cache: &mut HashMap<NodeID, Hash>,
+) -> Result<Hash, ApiError> {
+    if let Some(hash) = cache.get(&record.node_id) {
+        return Ok(*hash);
+    }
+    let digest = match &record.node_type {
+        NodeType::File { content_hash, .. } => *content_hash,
+        NodeType::Directory => {
+            let mut children = Vec::new();
+            for child_id in &record.children {
+                let Some(child) = node_store.get(child_id).map_err(ApiError::from)? else {
+                    continue;
+                };
+                if child.tombstoned_at.is_some() {
+                    continue;
+                }
+                let name = child
+                    .path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                children.push((name, content_digest(node_store, &child, cache)?));
+            }
+            children.sort();
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(b"directory");
+            hasher.update(&(children.len() as u64).to_be_bytes());
+            for (name, hash) in &children {
+                hasher.update(&(name.len() as u64).to_be_bytes());
+                hasher.update(name.as_bytes());
+                hasher.update(hash);
+            }
+            *hasher.finalize().as_bytes()
+        }
+    };
+    cache.insert(record.node_id, digest);
+    Ok(digest)
+}
@@ -0,0 +1,314 @@
+//! Context export: collect head frames for a subtree and render markdown, json, or jsonl.
+
+use super::{content_digest, BundleFrame, BundleNode, ContextBundle, BUNDLE_VERSION};
+use crate::api::{ContextApi, ContextView};
+use crate::context::generation::estimate::estimate_tokens;
+use crate::context::query::get::resolve_target_node_id;
+use crate::error::ApiError;
+use crate::metadata::frame_types::project_visible_metadata;
+use crate::store::{NodeRecord, NodeType};
+use crate::types::{Hash, NodeID};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Export output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Jsonl,
+}
+
+impl ExportFormat {
+    /// Parse the CLI `--format` value
+    pub fn parse(value: &str) -> Result<Self, ApiError> {
+        match value {
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "json" => Ok(ExportFormat::Json),
+            "jsonl" => Ok(ExportFormat::Jsonl),
+            _ => Err(ApiError::ConfigError(format!(
+                "Invalid format: '{}'. Must be 'markdown', 'json', or 'jsonl'.",
+                value
+            ))),
+        }
+    }
+}
+
+/// Export options
+#[derive(Debug, Clone, Default)]
+pub struct ExportRequest {
+    /// Only export frames of this type
+    pub frame_type: Option<String>,
+    /// Maximum depth below the export root (0 exports only the root)
+    pub max_depth: Option<usize>,
+    /// Approximate token budget for frame content; frames past the budget are dropped
+    pub max_tokens: Option<usize>,
+}
+
+/// Build a bundle for the subtree rooted at `root_id`
+///
+/// Nodes are ordered by workspace-relative path (component-wise), frames by frame type.
+/// Tombstoned nodes, deleted frames, non-UTF-8 frames, and nodes without frames are omitted.
+pub fn export_bundle(
+    api: &ContextApi,
+    workspace_root: &Path,
+    root_id: NodeID,
+    request: &ExportRequest,
+) -> Result<ContextBundle, ApiError> {
+    let workspace_root = crate::tree::path::canonicalize_path(workspace_root)
+        .unwrap_or_else(|_| workspace_root.to_path_buf());
+    let root_record = api
+        .node_store()
+        .get(&root_id)
+        .map_err(ApiError::from)?
+        .filter(|record| record.tombstoned_at.is_none())
+        .ok_or(ApiError::NodeNotFound(root_id))?;
+
+    let mut records = vec![(
+        relative_path(&workspace_root, &root_record.path),
+        root_record,
+    )];
+    let mut stack = vec![(records[0].1.clone(), 0usize)];
+    while let Some((record, depth)) = stack.pop() {
+        if request.max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+        for child_id in &record.children {
+            let Some(child) = api.node_store().get(child_id).map_err(ApiError::from)? else {
+                continue;
+            };
+            if child.tombstoned_at.is_some() {
+                continue;
+            }
+            records.push((relative_path(&workspace_root, &child.path), child.clone()));
+            stack.push((child, depth + 1));
+        }
+    }
+    records.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut bundle = ContextBundle {
+        version: BUNDLE_VERSION,
+        root: display_relative(&records[0].0),
+        truncated: false,
+        nodes: Vec::new(),
+    };
+    let mut tokens_used = 0usize;
+    let mut digests = HashMap::new();
+
+    for (rel_path, record) in records {
+        let mut builder = ContextView::builder()
+            .max_frames(usize::MAX)
+            .by_type_ordering();
+        if let Some(ft) = &request.frame_type {
+            builder = builder.by_type(ft.clone());
+        }
+        let context = api.get_node(record.node_id, builder.build())?;
+
+        let mut frames = Vec::new();
+        for frame in &context.frames {
+            let Ok(content) = frame.text_content() else {
+                continue;
+            };
+            if let Some(budget) = request.max_tokens {
+                let cost = estimate_tokens(&content);
+                if bundle.truncated || tokens_used + cost > budget {
+                    bundle.truncated = true;
+                    continue;
+                }
+                tokens_used += cost;
+            }
+            frames.push(BundleFrame {
+                frame_id: hex::encode(frame.frame_id),
+                frame_type: frame.frame_type.clone(),
+                agent_id: frame.agent_id().map(str::to_string),
+                metadata: project_visible_metadata(&frame.metadata),
+                timestamp: DateTime::<Utc>::from(frame.timestamp)
+                    .to_rfc3339_opts(SecondsFormat::Secs, true),
+                content,
+            });
+        }
+        if frames.is_empty() {
+            continue;
+        }
+        let digest = content_digest(api.node_store().as_ref(), &record, &mut digests)?;
+        bundle
+            .nodes
+            .push(bundle_node(&rel_path, &record, digest, frames));
+    }
+
+    Ok(bundle)
+}
+
+fn bundle_node(
+    rel_path: &Path,
+    record: &NodeRecord,
+    digest: Hash,
+    frames: Vec<BundleFrame>,
+) -> BundleNode {
+    let (node_type, size) = match &record.node_type {
+        NodeType::File { size, .. } => ("file", Some(*size)),
+        NodeType::Directory => ("directory", None),
+    };
+    BundleNode {
+        path: display_relative(rel_path),
+        node_id: hex::encode(record.node_id),
+        node_type: node_type.to_string(),
+        size,
+        content_hash: hex::encode(digest),
+        metadata: record
+            .metadata
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+        frames,
+    }
+}
+
+fn relative_path(workspace_root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(workspace_root)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn display_relative(path: &Path) -> String {
+    if path.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        path.to_string_lossy().replace('\\', "/")
+    }
+}
+
+/// Single export entry point for CLI: resolve node_id, build the bundle, render it.
+pub fn export_for_cli(
+    api: &ContextApi,
+    workspace_root: &PathBuf,
+    node: Option<&str>,
+    path: Option<&Path>,
+    format: &str,
+    request: &ExportRequest,
+) -> Result<(ContextBundle, String), ApiError> {
+    let format = ExportFormat::parse(format)?;
+    let node_id = resolve_target_node_id(api, workspace_root, node, path)?;
+    let bundle = export_bundle(api, workspace_root, node_id, request)?;
+    let rendered = render_bundle(&bundle, format)?;
+    Ok((bundle, rendered))
+}
+
+/// Render a bundle in the requested format
+///
+/// `jsonl` writes a header line (the bundle without nodes) followed by one node per line.
+pub fn render_bundle(bundle: &ContextBundle, format: ExportFormat) -> Result<String, ApiError> {
+    let to_json_error =
+        |e: serde_json::Error| ApiError::ConfigError(format!("Failed to serialize JSON: {}", e));
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(bundle).map_err(to_json_error),
+        ExportFormat::Jsonl => {
+            let header = ContextBundle {
+                version: bundle.version,
+                root: bundle.root.clone(),
+                truncated: bundle.truncated,
+                nodes: Vec::new(),
+            };
+            let mut lines = vec![serde_json::to_string(&header).map_err(to_json_error)?];
+            for node in &bundle.nodes {
+                lines.push(serde_json::to_string(node).map_err(to_json_error)?);
+            }
+            Ok(lines.join("\n") + "\n")
+        }
+        ExportFormat::Markdown => Ok(render_markdown(bundle)),
+    }
+}
+
+fn render_markdown(bundle: &ContextBundle) -> String {
+    let mut output = format!("# Context: {}\n", bundle.root);
+    if bundle.truncated {
+        output.push_str("\n_Truncated to fit the token budget._\n");
+    }
+    for node in &bundle.nodes {
+        output.push_str(&format!("\n## {}\n\n", node.path));
+        output.push_str(&format!("- Node: `{}`\n", node.node_id));
+        match node.size {
+            Some(size) => output.push_str(&format!("- Type: {}, {} bytes\n", node.node_type, size)),
+            None => output.push_str(&format!("- Type: {}\n", node.node_type)),
+        }
+        output.push_str(&format!("- Content hash: `{}`\n", node.content_hash));
+        for (key, value) in &node.metadata {
+            output.push_str(&format!("- {}: {}\n", key, value));
+        }
+        for frame in &node.frames {
+            output.push_str(&format!("\n### {}\n\n", frame.frame_type));
+            if let Some(agent_id) = &frame.agent_id {
+                output.push_str(&format!("- Agent: {}\n", agent_id));
+            }
+            for (key, value) in &frame.metadata {
+                if key == crate::metadata::frame_write_contract::KEY_PROMPT {
+                    continue;
+                }
+                output.push_str(&format!("- {}: {}\n", key, value));
+            }
+            output.push_str(&format!("- Generated: {}\n\n", frame.timestamp));
+            output.push_str(frame.content.trim_end());
+            output.push('\n');
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle() -> ContextBundle {
+        ContextBundle {
+            version: BUNDLE_VERSION,
+            root: "src".to_string(),
+            truncated: false,
+            nodes: vec![BundleNode {
+                path: "src/lib.rs".to_string(),
+                node_id: "ab".repeat(32),
+                node_type: "file".to_string(),
+                size: Some(12),
+                content_hash: "cd".repeat(32),
+                metadata: Default::default(),
+                frames: vec![BundleFrame {
+                    frame_id: "ef".repeat(32),
+                    frame_type: "context-docs-writer".to_string(),
+                    agent_id: Some("docs-writer".to_string()),
+                    metadata: [("model".to_string(), "llama3".to_string())]
+                        .into_iter()
+                        .collect(),
+                    timestamp: "2026-01-01T00:00:00Z".to_string(),
+                    content: "Library entry point.\n".to_string(),
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let output = render_bundle(&bundle(), ExportFormat::Markdown).unwrap();
+        assert!(output.starts_with("# Context: src\n"));
+        assert!(output.contains("\n## src/lib.rs\n"));
+        assert!(output.contains("- Type: file, 12 bytes\n"));
+        assert!(output.contains("\n### context-docs-writer\n"));
+        assert!(output.contains("- model: llama3\n"));
+        assert!(output.contains("Library entry point.\n"));
+    }
+
+    #[test]
+    fn test_render_json_roundtrip() {
+        let original = bundle();
+        let json = render_bundle(&original, ExportFormat::Json).unwrap();
+        let parsed: ContextBundle = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, original);
+
+        let jsonl = render_bundle(&original, ExportFormat::Jsonl).unwrap();
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let header: ContextBundle = serde_json::from_str(lines[0]).unwrap();
+        assert!(header.nodes.is_empty());
+        let node: BundleNode = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(node, original.nodes[0]);
+    }
+}
@@ -0,0 +1,298 @@
+//! Context import: re-attach bundle frames to matching nodes by workspace-relative path.
+//! Nodes whose content changed since export are skipped; frames pass the shared write contract.
+
+use super::{content_digest, BundleNode, ContextBundle, BUNDLE_VERSION};
+use crate::api::ContextApi;
+use crate::context::frame::{Basis, Frame};
+use crate::error::ApiError;
+use crate::metadata::frame_types::FrameMetadata;
+use crate::metadata::frame_write_contract::{validate_frame_metadata, KEY_AGENT_ID};
+use crate::store::{NodeRecord, NodeType};
+use crate::types::{Hash, NodeID};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Why a bundle node was not imported
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Path is not in the local tree
+    Missing,
+    /// Local content hash differs from the bundle
+    ContentChanged,
+}
+
+/// A bundle node that was skipped
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedNode {
+    pub path: String,
+    pub reason: SkipReason,
+}
+
+/// A frame rejected at the write boundary
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedFrame {
+    pub path: String,
+    pub frame_type: String,
+    pub error: String,
+}
+
+/// Import outcome and coverage
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    /// Nodes present in the bundle
+    pub nodes_total: usize,
+    /// Nodes that matched local content
+    pub nodes_matched: usize,
+    /// Frames written (or that would be written in dry run)
+    pub frames_imported: usize,
+    /// Frames already present as the head for their type
+    pub frames_unchanged: usize,
+    pub skipped: Vec<SkippedNode>,
+    pub rejected: Vec<RejectedFrame>,
+    pub dry_run: bool,
+}
+
+impl ImportReport {
+    /// Fraction of bundle nodes that matched local content
+    pub fn coverage(&self) -> f64 {
+        if self.nodes_total == 0 {
+            0.0
+        } else {
+            self.nodes_matched as f64 / self.nodes_total as f64
+        }
+    }
+}
+
+/// Parse a bundle written by export in json or jsonl format
+pub fn parse_bundle(text: &str) -> Result<ContextBundle, ApiError> {
+    let invalid =
+        |e: serde_json::Error| ApiError::ConfigError(format!("Invalid context bundle: {}", e));
+    let bundle = match serde_json::from_str::<ContextBundle>(text) {
+        Ok(bundle) => bundle,
+        Err(json_err) => {
+            let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+            let header = lines.next().ok_or_else(|| invalid(json_err))?;
+            let mut bundle: ContextBundle = serde_json::from_str(header).map_err(|_| {
+                ApiError::ConfigError(
+                    "Invalid context bundle: expected json or jsonl export output".to_string(),
+                )
+            })?;
+            for line in lines {
+                bundle
+                    .nodes
+                    .push(serde_json::from_str::<BundleNode>(line).map_err(invalid)?);
+            }
+            bundle
+        }
+    };
+    if bundle.version > BUNDLE_VERSION {
+        return Err(ApiError::ConfigError(format!(
+            "Unsupported context bundle version {} (supported up to {})",
+            bundle.version, BUNDLE_VERSION
+        )));
+    }
+    Ok(bundle)
+}
+
+fn content_matches(
+    api: &ContextApi,
+    node: &BundleNode,
+    record: &NodeRecord,
+    digests: &mut HashMap<NodeID, Hash>,
+) -> Result<bool, ApiError> {
+    let type_matches = match record.node_type {
+        NodeType::File { .. } => node.node_type == "file",
+        NodeType::Directory => node.node_type == "directory",
+    };
+    if !type_matches {
+        return Ok(false);
+    }
+    let digest = content_digest(api.node_store().as_ref(), record, digests)?;
+    Ok(node.content_hash == hex::encode(digest))
+}
+
+fn parse_timestamp(value: &str) -> Option<SystemTime> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(SystemTime::from)
+}
+
+/// Import a bundle into the workspace
+///
+/// Frames are rebuilt against the local NodeID so their FrameIDs stay content-addressed.
+/// Per-frame failures are reported, not fatal.
+pub fn import_bundle(
+    api: &ContextApi,
+    workspace_root: &Path,
+    bundle: &ContextBundle,
+    dry_run: bool,
+) -> Result<ImportReport, ApiError> {
+    let mut report = ImportReport {
+        nodes_total: bundle.nodes.len(),
+        dry_run,
+        ..ImportReport::default()
+    };
+    let mut digests = HashMap::new();
+
+    for node in &bundle.nodes {
+        let local_path = if node.path == "." {
+            workspace_root.to_path_buf()
+        } else {
+            workspace_root.join(&node.path)
+        };
+        let record = match crate::tree::path::canonicalize_path(&local_path) {
+            Ok(canonical) => api
+                .node_store()
+                .find_by_path(&canonical)
+                .map_err(ApiError::from)?,
+            Err(_) => None,
+        };
+        let Some(record) = record else {
+            report.skipped.push(SkippedNode {
+                path: node.path.clone(),
+                reason: SkipReason::Missing,
+            });
+            continue;
+        };
+        if !content_matches(api, node, &record, &mut digests)? {
+            report.skipped.push(SkippedNode {
+                path: node.path.clone(),
+                reason: SkipReason::ContentChanged,
+            });
+            continue;
+        }
+        report.nodes_matched += 1;
+
+        for bundle_frame in &node.frames {
+            let reject = |error: ApiError| RejectedFrame {
+                path: node.path.clone(),
+                frame_type: bundle_frame.frame_type.clone(),
+                error: error.to_string(),
+            };
+            let Some(agent_id) = bundle_frame.agent_id.clone() else {
+                report.rejected.push(reject(ApiError::InvalidFrame(format!(
+                    "Frame missing {} in metadata",
+                    KEY_AGENT_ID
+                ))));
+                continue;
+            };
+            let mut metadata: FrameMetadata = bundle_frame
+                .metadata
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            metadata.insert(KEY_AGENT_ID.to_string(), agent_id.clone());
+            if let Err(e) = validate_frame_metadata(&metadata, &agent_id) {
+                report.rejected.push(reject(e));
+                continue;
+            }
+
+            let mut frame = match Frame::new(
+                Basis::Node(record.node_id),
+                bundle_frame.content.clone().into_bytes(),
+                bundle_frame.frame_type.clone(),
+                agent_id.clone(),
+                metadata,
+            ) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    report.rejected.push(reject(ApiError::from(e)));
+                    continue;
+                }
+            };
+            if let Some(timestamp) = parse_timestamp(&bundle_frame.timestamp) {
+                frame.timestamp = timestamp;
+            }
+
+            if api.get_head(&record.node_id, &frame.frame_type)? == Some(frame.frame_id) {
+                report.frames_unchanged += 1;
+                continue;
+            }
+            if dry_run {
+                if let Err(e) = api.get_agent(&agent_id).and_then(|a| a.verify_write()) {
+                    report.rejected.push(reject(e));
+                } else {
+                    report.frames_imported += 1;
+                }
+                continue;
+            }
+            match api.put_frame(record.node_id, frame, agent_id) {
+                Ok(_) => report.frames_imported += 1,
+                Err(e) => report.rejected.push(reject(e)),
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Single import entry point for CLI: read the bundle file and import it.
+pub fn import_for_cli(
+    api: &ContextApi,
+    workspace_root: &Path,
+    bundle_path: &Path,
+    dry_run: bool,
+) -> Result<ImportReport, ApiError> {
+    let text = std::fs::read_to_string(bundle_path).map_err(|e| {
+        ApiError::ConfigError(format!(
+            "Failed to read bundle {}: {}",
+            bundle_path.display(),
+            e
+        ))
+    })?;
+    let bundle = parse_bundle(&text)?;
+    import_bundle(api, workspace_root, &bundle, dry_run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::bundle::{render_bundle, BundleFrame, ExportFormat};
+
+    fn bundle() -> ContextBundle {
+        ContextBundle {
+            version: BUNDLE_VERSION,
+            root: ".".to_string(),
+            truncated: false,
+            nodes: vec![BundleNode {
+                path: "a.txt".to_string(),
+                node_id: "00".repeat(32),
+                node_type: "file".to_string(),
+                size: Some(1),
+                content_hash: "11".repeat(32),
+                metadata: Default::default(),
+                frames: vec![BundleFrame {
+                    frame_id: "22".repeat(32),
+                    frame_type: "context-test".to_string(),
+                    agent_id: Some("test".to_string()),
+                    metadata: Default::default(),
+                    timestamp: "2026-01-01T00:00:00Z".to_string(),
+                    content: "summary".to_string(),
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_parse_bundle_json_and_jsonl() {
+        let original = bundle();
+        for format in [ExportFormat::Json, ExportFormat::Jsonl] {
+            let text = render_bundle(&original, format).unwrap();
+            assert_eq!(parse_bundle(&text).unwrap(), original);
+        }
+    }
+
+    #[test]
+    fn test_parse_bundle_rejects_markdown_and_newer_versions() {
+        let markdown = render_bundle(&bundle(), ExportFormat::Markdown).unwrap();
+        assert!(parse_bundle(&markdown).is_err());
+
+        let mut newer = bundle();
+        newer.version = BUNDLE_VERSION + 1;
+        let text = render_bundle(&newer, ExportFormat::Json).unwrap();
+        assert!(parse_bundle(&text).is_err());
+    }
+}
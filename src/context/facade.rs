@@ -4,9 +4,9 @@
 pub use crate::context::frame::{Basis, Frame, FrameMerkleSet, FrameStorage};
 pub use crate::context::generation::{
     FailurePolicy, GenerationExecutor, GenerationItem, GenerationNodeType, GenerationPlan,
-    GenerationResult, PlanPriority, QueueSubmitter,
+    GenerationResult, GenerationRunStore, PlanPriority, QueueSubmitter,
 };
-pub use crate::context::generation::run::{run_generate, GenerateRequest};
+pub use crate::context::generation::run::{resume_generate, run_generate, GenerateRequest};
 pub use crate::context::queue::{
     FrameGenerationQueue, GenerationConfig, GenerationRequestOptions, Priority, QueueEventContext,
     QueueStats,
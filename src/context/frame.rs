@@ -12,6 +12,7 @@ pub use storage::FrameStorage;
 
 use crate::error::StorageError;
 use crate::metadata::frame_types::FrameMetadata;
+use crate::metadata::frame_write_contract::{CONTENT_TYPE_JSON, KEY_CONTENT_TYPE};
 use crate::types::{FrameID, NodeID};
 use std::path::Path;
 
@@ -100,6 +101,11 @@ impl Frame {
         self.metadata.get(key).map(|s| s.as_str())
     }
 
+    /// Check if the content is JSON (`content_type` is `application/json`).
+    pub fn is_json(&self) -> bool {
+        self.metadata_value(KEY_CONTENT_TYPE) == Some(CONTENT_TYPE_JSON)
+    }
+
     /// Check if this frame is marked as deleted.
     pub fn is_deleted(&self) -> bool {
         self.metadata_value("deleted") == Some("true")
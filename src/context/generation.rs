@@ -1,13 +1,24 @@
 //! Context generation: plan and executor for running generation plans against the queue.
 //! Behavior-named; executor runs the plan; queue and provider stay in their domains.
 
+pub mod budget;
+pub mod chunking;
+pub mod estimate;
 pub mod executor;
 pub mod plan;
+pub mod routing;
 pub mod run;
+pub mod store;
+pub mod tools;
 
+pub use budget::{GenerationBudget, UsageLedger, UsageTotals};
+pub use estimate::{estimate_plan, PlanEstimate, ProviderEstimate};
 pub use executor::{GenerationExecutor, QueueSubmitter};
 pub use plan::{
-    FailurePolicy, GenerationErrorDetail, GenerationItem, GenerationNodeType, GenerationPlan,
-    GenerationResult, LevelSummary, PlanPriority,
+    ClassifiedFilePolicy, ClassifiedFilesConfig, ConsumedFrames, FailurePolicy,
+    GenerationErrorDetail, GenerationItem, GenerationNodeType, GenerationPlan, GenerationResult,
+    LevelSummary, PlanPriority,
 };
-pub use run::{run_generate, GenerateRequest};
+pub use routing::{RoutedAgent, RoutingRule, RoutingRules};
+pub use run::{estimate_generate, resume_generate, run_generate, GenerateRequest};
+pub use store::{GenerationRunRecord, GenerationRunStore, GenerationRunSummary, RunStatus};
@@ -0,0 +1,193 @@
+//! Token usage accounting and budget caps for a generation session.
+//! The queue records provider usage here and checks the budget before each provider call.
+
+use crate::error::ApiError;
+use crate::provider::{ModelPricing, TokenUsage};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+/// Caps applied to a generation session; `None` means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationBudget {
+    /// Maximum spend in USD (requires provider pricing)
+    pub max_cost: Option<f64>,
+    /// Maximum prompt plus completion tokens
+    pub max_tokens: Option<u64>,
+}
+
+impl GenerationBudget {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_cost.is_none() && self.max_tokens.is_none()
+    }
+}
+
+/// Accumulated provider usage
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    /// Provider responses recorded
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    /// Cost in USD of priced responses
+    pub cost: f64,
+    /// Responses from providers without pricing (not included in `cost`)
+    pub unpriced_requests: u64,
+    /// Responses whose usage the provider did not report; their estimated tokens are
+    /// included in the totals above so budgets overcount rather than undercount
+    #[serde(default)]
+    pub estimated_requests: u64,
+    #[serde(default)]
+    pub estimated_tokens: u64,
+}
+
+/// Thread-safe usage ledger shared by queue workers for one session
+#[derive(Debug, Default)]
+pub struct UsageLedger {
+    budget: GenerationBudget,
+    totals: Mutex<UsageTotals>,
+}
+
+impl UsageLedger {
+    pub fn new(budget: GenerationBudget) -> Self {
+        Self {
+            budget,
+            totals: Mutex::new(UsageTotals::default()),
+        }
+    }
+
+    pub fn budget(&self) -> GenerationBudget {
+        self.budget
+    }
+
+    /// Add one provider response to the totals
+    pub fn record(&self, usage: &TokenUsage, pricing: Option<&ModelPricing>) {
+        let prompt = u64::from(usage.prompt_tokens);
+        let completion = u64::from(usage.completion_tokens);
+        let total = u64::from(usage.total_tokens).max(prompt + completion);
+        let mut totals = self.totals.lock();
+        if usage.estimated {
+            totals.estimated_requests += 1;
+            totals.estimated_tokens += total;
+        }
+        totals.requests += 1;
+        totals.prompt_tokens += prompt;
+        totals.completion_tokens += completion;
+        totals.total_tokens += total;
+        match pricing {
+            Some(pricing) => totals.cost += pricing.cost(prompt, completion),
+            None => totals.unpriced_requests += 1,
+        }
+    }
+
+    pub fn totals(&self) -> UsageTotals {
+        self.totals.lock().clone()
+    }
+
+    /// Error once spend or tokens have reached a cap; checked before each provider call
+    pub fn check(&self) -> Result<(), ApiError> {
+        let totals = self.totals.lock();
+        if let Some(max_tokens) = self.budget.max_tokens {
+            if totals.total_tokens >= max_tokens {
+                return Err(ApiError::BudgetExceeded(format!(
+                    "used {} of {} tokens",
+                    totals.total_tokens, max_tokens
+                )));
+            }
+        }
+        if let Some(max_cost) = self.budget.max_cost {
+            if totals.cost >= max_cost {
+                return Err(ApiError::BudgetExceeded(format!(
+                    "spent ${:.4} of ${:.4}",
+                    totals.cost, max_cost
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.check().is_err()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt: u32, completion: u32) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: prompt + completion,
+            estimated: false,
+        }
+    }
+
+    #[test]
+    fn record_accumulates_tokens_and_cost() {
+        let ledger = UsageLedger::new(GenerationBudget::default());
+        let pricing = ModelPricing {
+            input_per_million: 2.0,
+            output_per_million: 10.0,
+        };
+        ledger.record(&usage(1_000, 100), Some(&pricing));
+        ledger.record(&usage(500, 50), None);
+
+        let totals = ledger.totals();
+        assert_eq!(totals.requests, 2);
+        assert_eq!(totals.prompt_tokens, 1_500);
+        assert_eq!(totals.completion_tokens, 150);
+        assert_eq!(totals.total_tokens, 1_650);
+        assert_eq!(totals.unpriced_requests, 1);
+        assert!((totals.cost - 0.003).abs() < 1e-12);
+        assert!(ledger.check().is_ok());
+    }
+
+    #[test]
+    fn check_fails_once_a_cap_is_reached() {
+        let ledger = UsageLedger::new(GenerationBudget {
+            max_cost: None,
+            max_tokens: Some(100),
+        });
+        ledger.record(&usage(60, 30), None);
+        assert!(ledger.check().is_ok());
+        ledger.record(&usage(5, 5), None);
+        assert!(matches!(ledger.check(), Err(ApiError::BudgetExceeded(_))));
+
+        let ledger = UsageLedger::new(GenerationBudget {
+            max_cost: Some(0.001),
+            max_tokens: None,
+        });
+        let pricing = ModelPricing {
+            input_per_million: 1_000.0,
+            output_per_million: 0.0,
+        };
+        ledger.record(&usage(1, 0), Some(&pricing));
+        assert!(ledger.is_exceeded());
+    }
+
+    #[test]
+    fn estimated_usage_is_charged_and_reported() {
+        let ledger = UsageLedger::new(GenerationBudget {
+            max_cost: Some(0.5),
+            max_tokens: Some(100),
+        });
+        let pricing = ModelPricing {
+            input_per_million: 1_000.0,
+            output_per_million: 0.0,
+        };
+        let mut estimate = usage(500, 500);
+        estimate.estimated = true;
+        ledger.record(&estimate, Some(&pricing));
+        assert!(matches!(ledger.check(), Err(ApiError::BudgetExceeded(_))));
+
+        let totals = ledger.totals();
+        assert_eq!((totals.requests, totals.total_tokens), (1, 1_000));
+        assert!((totals.cost - 0.5).abs() < 1e-12);
+        assert_eq!(
+            (totals.estimated_requests, totals.estimated_tokens),
+            (1, 1_000)
+        );
+    }
+}
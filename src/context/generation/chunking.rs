@@ -0,0 +1,394 @@
+//! Large-file chunking for map-reduce generation: split source at syntax-aware boundaries,
+//! then render the per-chunk, merge, and synthesis prompts.
+
+use std::ops::Range;
+use std::path::Path;
+
+/// One contiguous slice of a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceChunk {
+    pub index: usize,
+    /// First line (1-based, inclusive)
+    pub start_line: usize,
+    /// Last line (1-based, inclusive)
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// Frame type for the chunk summaries of `frame_type`
+pub fn chunk_frame_type(frame_type: &str) -> String {
+    format!("{}.chunk", frame_type)
+}
+
+/// Line prefixes that start a top-level definition, by file extension
+fn definition_prefixes(extension: &str) -> &'static [&'static str] {
+    match extension {
+        "rs" => &[
+            "fn ",
+            "pub ",
+            "impl",
+            "struct ",
+            "enum ",
+            "trait ",
+            "mod ",
+            "const ",
+            "static ",
+            "type ",
+            "async fn ",
+            "unsafe ",
+            "macro_rules!",
+            "#[",
+            "///",
+        ],
+        "py" => &["def ", "async def ", "class ", "@"],
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" => &[
+            "function ",
+            "async function ",
+            "export ",
+            "class ",
+            "const ",
+            "let ",
+            "interface ",
+            "type ",
+            "/**",
+        ],
+        "go" => &["func ", "type ", "var ", "const ", "//"],
+        "java" | "kt" | "cs" | "scala" | "swift" => &[
+            "public ",
+            "private ",
+            "protected ",
+            "internal ",
+            "class ",
+            "interface ",
+            "fun ",
+            "func ",
+            "@",
+            "/**",
+        ],
+        "c" | "h" | "cc" | "cpp" | "hpp" => &["static ", "struct ", "typedef ", "#define ", "/*"],
+        "md" | "markdown" => &["#"],
+        _ => &[],
+    }
+}
+
+fn is_definition_start(line: &str, prefixes: &[&str]) -> bool {
+    prefixes.iter().any(|p| line.starts_with(p))
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+/// Split `text` into chunks of at most `max_bytes` bytes (single lines longer than that
+/// are split on character boundaries).
+///
+/// Cuts prefer the start of a top-level definition for the file's extension, then the line
+/// after a blank line, as long as the chunk stays at least half full.
+pub fn split_source(text: &str, path: &Path, max_bytes: usize) -> Vec<SourceChunk> {
+    let max_bytes = max_bytes.max(1);
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    let prefixes = definition_prefixes(&extension);
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+
+    let mut chunks: Vec<SourceChunk> = Vec::new();
+    let mut push = |start_line: usize, end_line: usize, text: String| {
+        chunks.push(SourceChunk {
+            index: chunks.len(),
+            start_line: start_line + 1,
+            end_line: end_line + 1,
+            text,
+        });
+    };
+
+    let mut start = 0;
+    while start < lines.len() {
+        if lines[start].len() > max_bytes {
+            for piece in split_long_line(lines[start], max_bytes) {
+                push(start, start, piece.to_string());
+            }
+            start += 1;
+            continue;
+        }
+
+        let mut end = start;
+        let mut size = 0;
+        while end < lines.len() && size + lines[end].len() <= max_bytes {
+            size += lines[end].len();
+            end += 1;
+        }
+
+        if end < lines.len() {
+            let mut offset = 0;
+            let mut offsets = Vec::with_capacity(end - start);
+            for line in &lines[start..end] {
+                offsets.push(offset);
+                offset += line.len();
+            }
+            let mut candidates = (start + 1..end).filter(|&i| offsets[i - start] * 2 >= max_bytes);
+            let definition = candidates
+                .clone()
+                .rfind(|&i| is_definition_start(lines[i], prefixes));
+            let blank = candidates.rfind(|&i| is_blank(lines[i - 1]));
+            if let Some(cut) = definition.or(blank) {
+                end = cut;
+            }
+        }
+
+        push(start, end - 1, lines[start..end].concat());
+        start = end;
+    }
+    chunks
+}
+
+fn split_long_line(line: &str, max_bytes: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while rest.len() > max_bytes {
+        let mut cut = max_bytes;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        if cut == 0 {
+            cut = rest
+                .chars()
+                .next()
+                .map(char::len_utf8)
+                .unwrap_or(rest.len());
+        }
+        let (piece, tail) = rest.split_at(cut);
+        pieces.push(piece);
+        rest = tail;
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+/// User prompt for one chunk: the agent's file prompt plus the chunk's position and source
+pub fn render_chunk_prompt(
+    user_prompt: &str,
+    path: &Path,
+    chunk: &SourceChunk,
+    total: usize,
+) -> String {
+    format!(
+        "{}\n\nThis file is too large for a single prompt. Below is part {} of {} \
+         (lines {}-{}). Summarize this part; the part summaries are combined afterwards.\n\n\
+         Path: {}\nType: File (part {}/{})\nContent:\n{}",
+        user_prompt,
+        chunk.index + 1,
+        total,
+        chunk.start_line,
+        chunk.end_line,
+        path.display(),
+        chunk.index + 1,
+        total,
+        chunk.text
+    )
+}
+
+/// Summary of a contiguous line range of a chunked file: one chunk, or several merged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartSummary {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+impl PartSummary {
+    pub fn from_chunk(chunk: &SourceChunk, text: String) -> Self {
+        Self {
+            start_line: chunk.start_line,
+            end_line: chunk.end_line,
+            text,
+        }
+    }
+
+    fn render(&self, number: usize) -> String {
+        format!(
+            "\n### Part {} (lines {}-{})\n{}\n",
+            number,
+            self.start_line,
+            self.end_line,
+            self.text.trim()
+        )
+    }
+}
+
+fn render_parts(parts: &[PartSummary]) -> String {
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| part.render(i + 1))
+        .collect()
+}
+
+/// Bytes the part summaries take up in a synthesis or merge prompt
+pub fn rendered_parts_len(parts: &[PartSummary]) -> usize {
+    render_parts(parts).len()
+}
+
+/// Split `parts` into runs of consecutive summaries that fit in `max_bytes` when rendered;
+/// a summary larger than the budget forms a run of its own
+pub fn group_parts(parts: &[PartSummary], max_bytes: usize) -> Vec<Range<usize>> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+    for (i, part) in parts.iter().enumerate() {
+        let len = part.render(i + 1).len();
+        if i > start && size + len > max_bytes {
+            groups.push(start..i);
+            start = i;
+            size = 0;
+        }
+        size += len;
+    }
+    if start < parts.len() {
+        groups.push(start..parts.len());
+    }
+    groups
+}
+
+/// User prompt that merges consecutive part summaries into one, used while all the part
+/// summaries do not fit in one synthesis prompt
+pub fn render_merge_prompt(user_prompt: &str, path: &Path, parts: &[PartSummary]) -> String {
+    let (start_line, end_line) = match (parts.first(), parts.last()) {
+        (Some(first), Some(last)) => (first.start_line, last.end_line),
+        _ => (0, 0),
+    };
+    format!(
+        "{}\n\nThis file was summarized in parts because it is too large for a single prompt. \
+         Merge the consecutive part summaries below into one summary of lines {}-{}; it is \
+         combined with the other parts afterwards.\n\nPath: {}\nType: File\n{}",
+        user_prompt,
+        start_line,
+        end_line,
+        path.display(),
+        render_parts(parts)
+    )
+}
+
+/// User prompt for the file frame: the agent's file prompt plus the part summaries, each
+/// cut to an equal share of `max_bytes` if together they still exceed it
+pub fn render_synthesis_prompt(
+    user_prompt: &str,
+    path: &Path,
+    parts: &[PartSummary],
+    max_bytes: usize,
+) -> String {
+    let mut prompt = format!(
+        "{}\n\nThis file was summarized in {} parts because it is too large for a single \
+         prompt. Combine the part summaries below into one description of the whole file.\n\n\
+         Path: {}\nType: File\n",
+        user_prompt,
+        parts.len(),
+        path.display()
+    );
+    if rendered_parts_len(parts) <= max_bytes {
+        prompt.push_str(&render_parts(parts));
+        return prompt;
+    }
+    let share = max_bytes / parts.len().max(1);
+    for (i, part) in parts.iter().enumerate() {
+        let text = part.text.trim();
+        let mut end = share.min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let cut = PartSummary {
+            text: text[..end].to_string(),
+            ..part.clone()
+        };
+        prompt.push_str(&cut.render(i + 1));
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust_source(functions: usize) -> String {
+        (0..functions)
+            .map(|i| format!("fn f{}() {{\n    let x = {};\n    x + 1;\n}}\n\n", i, i))
+            .collect()
+    }
+
+    #[test]
+    fn chunks_cover_the_source_in_order_within_the_limit() {
+        let source = rust_source(40);
+        let chunks = split_source(&source, Path::new("lib.rs"), 200);
+        assert!(chunks.len() > 1);
+        assert_eq!(
+            chunks.iter().map(|c| c.text.as_str()).collect::<String>(),
+            source
+        );
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.index, i);
+            assert!(chunk.text.len() <= 200);
+        }
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].end_line + 1, pair[1].start_line);
+        }
+    }
+
+    #[test]
+    fn rust_chunks_start_at_function_boundaries() {
+        let chunks = split_source(&rust_source(40), Path::new("lib.rs"), 200);
+        for chunk in &chunks[1..] {
+            assert!(chunk.text.starts_with("fn "), "{:?}", chunk.text);
+        }
+    }
+
+    #[test]
+    fn unknown_extensions_cut_after_blank_lines() {
+        let source = "alpha\nbeta\n\ngamma\ndelta\n\nepsilon\nzeta\n";
+        let chunks = split_source(source, Path::new("notes.txt"), 16);
+        assert_eq!(chunks[0].text, "alpha\nbeta\n\n");
+        assert_eq!(chunks[1].start_line, 4);
+    }
+
+    fn part(start_line: usize, text: &str) -> PartSummary {
+        PartSummary {
+            start_line,
+            end_line: start_line + 9,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn parts_are_grouped_to_fit_the_budget() {
+        let parts: Vec<PartSummary> = (0..6).map(|i| part(i * 10 + 1, "summary")).collect();
+        let one = rendered_parts_len(&parts[..1]);
+        let groups = group_parts(&parts, one * 2 + 2);
+        assert_eq!(groups, vec![0..2, 2..4, 4..6]);
+
+        let oversized = vec![part(1, &"x".repeat(100)), part(11, "short")];
+        assert_eq!(group_parts(&oversized, 50), vec![0..1, 1..2]);
+    }
+
+    #[test]
+    fn synthesis_prompt_cuts_parts_that_exceed_the_budget() {
+        let parts = vec![part(1, &"a".repeat(500)), part(11, &"b".repeat(500))];
+        let prompt = render_synthesis_prompt("Describe", Path::new("big.rs"), &parts, 200);
+        assert!(prompt.contains(&format!("### Part 1 (lines 1-10)\n{}\n", "a".repeat(100))));
+        assert!(prompt.contains(&format!("### Part 2 (lines 11-20)\n{}\n", "b".repeat(100))));
+    }
+
+    #[test]
+    fn long_lines_are_split_on_char_boundaries() {
+        let source = "é".repeat(10);
+        let chunks = split_source(&source, Path::new("data.txt"), 5);
+        assert!(chunks.iter().all(|c| c.text.len() <= 5));
+        assert_eq!(
+            chunks.iter().map(|c| c.text.as_str()).collect::<String>(),
+            source
+        );
+        assert!(chunks.iter().all(|c| c.start_line == 1 && c.end_line == 1));
+    }
+}
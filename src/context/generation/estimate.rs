@@ -0,0 +1,235 @@
+//! Generation cost estimation: render each planned prompt the way the queue would,
+//! estimate input and output tokens, and price them with provider pricing.
+
+use crate::agent::profile::prompt_contract::PromptContract;
+use crate::agent::profile::ChunkingPolicy;
+use crate::api::ContextApi;
+use crate::context::generation::chunking::{render_chunk_prompt, split_source};
+use crate::context::generation::plan::{GenerationNodeType, GenerationPlan};
+use crate::context::queue::FrameGenerationQueue;
+use crate::error::ApiError;
+use crate::provider::ProviderConfig;
+use crate::store::NodeType;
+use crate::types::NodeID;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Output tokens assumed per item when the provider sets no `max_tokens`
+pub const DEFAULT_ESTIMATED_OUTPUT_TOKENS: u64 = 512;
+
+/// Rough token estimate (about four characters per token)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Estimated usage for the items routed to one provider
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderEstimate {
+    pub provider_name: String,
+    pub model: String,
+    pub items: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// None when the provider has no pricing configured
+    pub cost: Option<f64>,
+}
+
+/// Estimated usage for a whole plan
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanEstimate {
+    pub plan_id: String,
+    pub target_path: String,
+    pub total_nodes: usize,
+    pub total_levels: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// None when any provider in the plan has no pricing configured
+    pub cost: Option<f64>,
+    pub providers: Vec<ProviderEstimate>,
+}
+
+impl PlanEstimate {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Estimate the tokens and cost of running `plan`
+///
+/// Output is assumed to fill the provider's `max_tokens` (or
+/// [`DEFAULT_ESTIMATED_OUTPUT_TOKENS`]). Directory prompts include existing child heads;
+/// planned children without a head count as one expected output each. Files above the
+/// agent's chunk threshold count one request per chunk plus the synthesis request. Files
+/// edited since the scan, with no cached copy of the scanned content, are estimated from
+/// their scanned size. Template items make no provider call and are not counted.
+pub fn estimate_plan(api: &ContextApi, plan: &GenerationPlan) -> Result<PlanEstimate, ApiError> {
+    let planned: HashSet<(NodeID, &str)> = plan
+        .levels
+        .iter()
+        .flatten()
+        .map(|item| (item.node_id, item.frame_type.as_str()))
+        .collect();
+    let mut providers: BTreeMap<String, (ProviderConfig, ProviderEstimate)> = BTreeMap::new();
+
+    for item in plan.levels.iter().flatten().filter(|item| !item.template) {
+        if !providers.contains_key(&item.provider_name) {
+            let config = api
+                .provider_registry()
+                .read()
+                .get_or_error(&item.provider_name)?
+                .clone();
+            let estimate = ProviderEstimate {
+                provider_name: item.provider_name.clone(),
+                model: config.model.clone(),
+                items: 0,
+                input_tokens: 0,
+                output_tokens: 0,
+                cost: None,
+            };
+            providers.insert(item.provider_name.clone(), (config, estimate));
+        }
+        let (config, estimate) = providers
+            .get_mut(&item.provider_name)
+            .expect("provider estimate inserted above");
+        let output_per_item = config
+            .default_options
+            .max_tokens
+            .map(u64::from)
+            .unwrap_or(DEFAULT_ESTIMATED_OUTPUT_TOKENS);
+
+        let agent = api.get_agent(&item.agent_id)?;
+        let mut contract = PromptContract::from_agent(&agent)?;
+        if let Some(template) = &item.user_prompt {
+            contract = contract.with_user_prompt(template);
+        }
+        let record = api
+            .node_store()
+            .get(&item.node_id)
+            .map_err(ApiError::from)?
+            .ok_or(ApiError::NodeNotFound(item.node_id))?;
+        let (system_prompt, user_prompt) =
+            FrameGenerationQueue::generate_prompts(&contract, &record);
+
+        let policy = ChunkingPolicy::from_agent(&agent)?;
+        let system_tokens = estimate_tokens(&system_prompt) as u64;
+        if let NodeType::File { size, content_hash } = record.node_type {
+            if policy.should_chunk(size as usize) {
+                let chunk_tokens: Vec<u64> = match FrameGenerationQueue::read_scanned_file_bytes(
+                    api,
+                    &record,
+                    &content_hash,
+                ) {
+                    Ok(bytes) => {
+                        let text = String::from_utf8_lossy(&bytes);
+                        let chunks = split_source(&text, &record.path, policy.chunk_max_bytes);
+                        chunks
+                            .iter()
+                            .map(|chunk| {
+                                let prompt = render_chunk_prompt(
+                                    &user_prompt,
+                                    &record.path,
+                                    chunk,
+                                    chunks.len(),
+                                );
+                                estimate_tokens(&prompt) as u64
+                            })
+                            .collect()
+                    }
+                    // Edited since the scan with no cached copy: assume full chunks
+                    Err(ApiError::SourceChanged(_)) => {
+                        let count = (size as usize).div_ceil(policy.chunk_max_bytes);
+                        let tokens = estimate_tokens(&user_prompt) + policy.chunk_max_bytes / 4;
+                        vec![tokens as u64; count]
+                    }
+                    Err(e) => return Err(e),
+                };
+                let chunk_count = chunk_tokens.len() as u64;
+                let input_tokens = chunk_tokens.iter().map(|t| system_tokens + t).sum::<u64>()
+                    + system_tokens
+                    + estimate_tokens(&user_prompt) as u64
+                    + chunk_count * output_per_item;
+
+                estimate.items += 1;
+                estimate.input_tokens += input_tokens;
+                estimate.output_tokens += (chunk_count + 1) * output_per_item;
+                continue;
+            }
+        }
+
+        let user_tokens = estimate_tokens(&user_prompt) as u64;
+        let messages = FrameGenerationQueue::build_prompt_messages(
+            api,
+            &record,
+            system_prompt,
+            &user_prompt,
+            &item.agent_id,
+            &item.frame_type,
+            &item.consumes,
+        );
+        let mut input_tokens: u64 = match (messages, &record.node_type) {
+            (Ok(messages), _) => messages
+                .iter()
+                .map(|m| estimate_tokens(&m.content) as u64)
+                .sum(),
+            // Edited since the scan with no cached copy: estimate from the scanned size
+            (Err(ApiError::SourceChanged(_)), NodeType::File { size, .. }) => {
+                system_tokens + user_tokens + size.div_ceil(4)
+            }
+            (Err(e), _) => return Err(e),
+        };
+
+        if item.node_type == GenerationNodeType::Directory {
+            let frame_types = std::iter::once(item.frame_type.as_str())
+                .chain(item.consumes.iter().map(|c| c.frame_type.as_str()));
+            for frame_type in frame_types {
+                for child_id in &record.children {
+                    if planned.contains(&(*child_id, frame_type))
+                        && api.get_head(child_id, frame_type)?.is_none()
+                    {
+                        input_tokens += output_per_item;
+                    }
+                }
+            }
+        }
+
+        estimate.items += 1;
+        estimate.input_tokens += input_tokens;
+        estimate.output_tokens += output_per_item;
+    }
+
+    let mut result = PlanEstimate {
+        plan_id: plan.plan_id.clone(),
+        target_path: plan.target_path.clone(),
+        total_nodes: plan.total_nodes,
+        total_levels: plan.total_levels,
+        input_tokens: 0,
+        output_tokens: 0,
+        cost: Some(0.0),
+        providers: Vec::new(),
+    };
+    for (_, (config, mut estimate)) in providers {
+        estimate.cost = config
+            .pricing
+            .map(|pricing| pricing.cost(estimate.input_tokens, estimate.output_tokens));
+        result.input_tokens += estimate.input_tokens;
+        result.output_tokens += estimate.output_tokens;
+        result.cost = match (result.cost, estimate.cost) {
+            (Some(total), Some(cost)) => Some(total + cost),
+            _ => None,
+        };
+        result.providers.push(estimate);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+}
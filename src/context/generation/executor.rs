@@ -5,12 +5,14 @@ use crate::context::generation::plan::{
     FailurePolicy, GenerationErrorDetail, GenerationItem, GenerationPlan, GenerationResult,
     LevelSummary,
 };
+use crate::context::generation::store::{GenerationRunStore, RunStatus};
 use crate::context::queue::{FrameGenerationQueue, Priority};
 use crate::error::ApiError;
 use crate::telemetry::ProgressRuntime;
 use crate::types::FrameID;
 use futures::stream::{FuturesUnordered, StreamExt};
 use serde_json::json;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -43,6 +45,11 @@ impl QueueSubmitter for FrameGenerationQueue {
             crate::context::queue::GenerationRequestOptions {
                 force: item.force,
                 plan_id: Some(plan_id.to_string()),
+                template: item.template,
+                fallback_providers: item.fallback_providers.clone(),
+                failed_providers: Vec::new(),
+                consumes: item.consumes.clone(),
+                user_prompt: item.user_prompt.clone(),
             },
         )
         .await
@@ -53,6 +60,7 @@ impl QueueSubmitter for FrameGenerationQueue {
 pub struct GenerationExecutor {
     progress: Option<Arc<ProgressRuntime>>,
     wait_timeout: Option<Duration>,
+    runs: Option<Arc<GenerationRunStore>>,
 }
 
 impl GenerationExecutor {
@@ -62,6 +70,7 @@ impl GenerationExecutor {
         Self {
             progress,
             wait_timeout: Some(Self::DEFAULT_WAIT_TIMEOUT),
+            runs: None,
         }
     }
 
@@ -72,9 +81,17 @@ impl GenerationExecutor {
         Self {
             progress,
             wait_timeout,
+            runs: None,
         }
     }
 
+    /// Record per-item outcomes and the final plan status in `runs`.
+    /// The plan must already be persisted there.
+    pub fn with_run_store(mut self, runs: Arc<GenerationRunStore>) -> Self {
+        self.runs = Some(runs);
+        self
+    }
+
     pub async fn execute<Q: QueueSubmitter>(
         &self,
         queue: &Q,
@@ -83,6 +100,8 @@ impl GenerationExecutor {
         plan.validate()?;
         let mut result = GenerationResult::new(plan.plan_id.clone());
         let session_id = plan.session_id.clone();
+        // Keys of items that failed or were blocked; their dependents are not submitted
+        let mut failed_keys: HashSet<String> = HashSet::new();
 
         self.emit_event(
             session_id.as_deref(),
@@ -112,6 +131,33 @@ impl GenerationExecutor {
             let mut failed_count = 0usize;
             let mut futures = FuturesUnordered::new();
             for item in level_items {
+                if let Some(dependency) = item.depends_on.iter().find(|k| failed_keys.contains(*k))
+                {
+                    let message = format!(
+                        "Skipped {} for agent {}: dependency {} did not generate",
+                        item.path, item.agent_id, dependency
+                    );
+                    self.record_item(&plan.plan_id, item, Err(message.clone()));
+                    failed_count += 1;
+                    failed_keys.insert(item.key());
+                    result
+                        .failures
+                        .insert(item.node_id, GenerationErrorDetail { message });
+                    self.emit_event(
+                        session_id.as_deref(),
+                        "node_generation_blocked",
+                        json!({
+                            "plan_id": plan.plan_id,
+                            "level_index": level_index,
+                            "node_id": hex::encode(item.node_id),
+                            "path": item.path,
+                            "agent_id": item.agent_id,
+                            "frame_type": item.frame_type,
+                            "dependency": dependency,
+                        }),
+                    );
+                    continue;
+                }
                 let item_plan_id = plan.plan_id.clone();
                 self.emit_event(
                     session_id.as_deref(),
@@ -141,6 +187,7 @@ impl GenerationExecutor {
             while let Some((item, outcome)) = futures.next().await {
                 match outcome {
                     Ok(frame_id) => {
+                        self.record_item(&plan.plan_id, item, Ok(frame_id));
                         generated_count += 1;
                         result.successes.insert(item.node_id, frame_id);
                         self.emit_event(
@@ -156,7 +203,9 @@ impl GenerationExecutor {
                         );
                     }
                     Err(err) => {
+                        self.record_item(&plan.plan_id, item, Err(err.to_string()));
                         failed_count += 1;
+                        failed_keys.insert(item.key());
                         result.failures.insert(
                             item.node_id,
                             GenerationErrorDetail {
@@ -204,6 +253,7 @@ impl GenerationExecutor {
             );
 
             if fail_immediately_hit {
+                self.finish_run(&plan.plan_id, RunStatus::Failed, Some("fail_immediately"));
                 self.emit_event(
                     session_id.as_deref(),
                     "generation_failed",
@@ -222,6 +272,11 @@ impl GenerationExecutor {
 
             if failed_count > 0 && matches!(plan.failure_policy, FailurePolicy::StopOnLevelFailure)
             {
+                self.finish_run(
+                    &plan.plan_id,
+                    RunStatus::Failed,
+                    Some("stop_on_level_failure"),
+                );
                 self.emit_event(
                     session_id.as_deref(),
                     "generation_failed",
@@ -237,6 +292,12 @@ impl GenerationExecutor {
             }
         }
 
+        let status = if result.total_failed > 0 {
+            RunStatus::Failed
+        } else {
+            RunStatus::Completed
+        };
+        self.finish_run(&plan.plan_id, status, None);
         self.emit_event(
             session_id.as_deref(),
             "generation_completed",
@@ -249,6 +310,22 @@ impl GenerationExecutor {
         Ok(result)
     }
 
+    fn record_item(&self, plan_id: &str, item: &GenerationItem, outcome: Result<FrameID, String>) {
+        if let Some(runs) = &self.runs {
+            if let Err(e) = runs.record_item(plan_id, item, outcome) {
+                tracing::warn!("Failed to record generation item status: {}", e);
+            }
+        }
+    }
+
+    fn finish_run(&self, plan_id: &str, status: RunStatus, reason: Option<&str>) {
+        if let Some(runs) = &self.runs {
+            if let Err(e) = runs.finish_run(plan_id, status, reason.map(String::from)) {
+                tracing::warn!("Failed to record generation plan status: {}", e);
+            }
+        }
+    }
+
     fn emit_event(&self, session_id: Option<&str>, event_type: &str, payload: serde_json::Value) {
         if let (Some(progress), Some(session_id)) = (&self.progress, session_id) {
             progress.emit_event_best_effort(session_id, event_type, payload);
@@ -303,6 +380,11 @@ mod tests {
             provider_name: "provider".to_string(),
             frame_type: "context-writer".to_string(),
             force: false,
+            template: false,
+            fallback_providers: Vec::new(),
+            consumes: Vec::new(),
+            depends_on: Vec::new(),
+            user_prompt: None,
         }
     }
 
@@ -354,6 +436,25 @@ mod tests {
         assert_eq!(result.total_failed, 1);
     }
 
+    #[tokio::test]
+    async fn dependents_of_failed_items_are_not_submitted() {
+        let mut outcomes = HashMap::new();
+        outcomes.insert(
+            hex::encode(Hash::from([1u8; 32])),
+            Err(ApiError::GenerationFailed("boom".to_string())),
+        );
+        let queue = MockQueue::new(outcomes);
+        let mut plan = plan(FailurePolicy::Continue);
+        plan.levels[1][0].depends_on = vec![item(1).key()];
+        let result = GenerationExecutor::new(None)
+            .execute(&queue, plan)
+            .await
+            .unwrap();
+        assert_eq!(result.total_generated, 1);
+        assert_eq!(result.total_failed, 2);
+        assert_eq!(queue.received_timeouts.lock().len(), 2);
+    }
+
     #[tokio::test]
     async fn executor_uses_default_wait_timeout() {
         let queue = MockQueue::new(HashMap::new());
@@ -1,5 +1,8 @@
+use crate::context::generation::budget::UsageTotals;
 use crate::context::queue::Priority;
 use crate::error::ApiError;
+use crate::store::NodeRecord;
+use crate::tree::classify::{FileClass, KEY_FILE_CLASS};
 use crate::types::{FrameID, NodeID};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -45,6 +48,85 @@ pub struct GenerationItem {
     pub provider_name: String,
     pub frame_type: String,
     pub force: bool,
+    /// Write a template frame instead of calling the provider
+    #[serde(default)]
+    pub template: bool,
+    /// Providers to fail over to, in order, once retries on `provider_name` are exhausted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_providers: Vec<String>,
+    /// Other agents' child frames included in this item's directory prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub consumes: Vec<ConsumedFrames>,
+    /// Keys (see `store::item_key`) of plan items that must succeed before this one runs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// User prompt template from a routing rule, replacing the agent's prompts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_prompt: Option<String>,
+}
+
+impl GenerationItem {
+    /// Stable per-plan key: node and frame type
+    pub fn key(&self) -> String {
+        item_key_for(&self.node_id, &self.frame_type)
+    }
+}
+
+/// Plan item key for the `frame_type` frame of `node_id`
+pub fn item_key_for(node_id: &NodeID, frame_type: &str) -> String {
+    format!("{}:{}", hex::encode(node_id), frame_type)
+}
+
+/// Frames of another agent that a generation item reads
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConsumedFrames {
+    pub agent_id: String,
+    pub frame_type: String,
+}
+
+/// How plans treat files the scanner classified as binary, generated, or vendored
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClassifiedFilePolicy {
+    /// Leave the file out of the plan
+    #[default]
+    Skip,
+    /// Write a short descriptive frame without calling the provider
+    Template,
+    /// Generate like any other file
+    Generate,
+}
+
+/// Per-class generation policy (`[context.classified_files]`)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClassifiedFilesConfig {
+    #[serde(default)]
+    pub binary: ClassifiedFilePolicy,
+    #[serde(default)]
+    pub generated: ClassifiedFilePolicy,
+    #[serde(default)]
+    pub vendored: ClassifiedFilePolicy,
+}
+
+impl ClassifiedFilesConfig {
+    pub fn policy_for(&self, class: FileClass) -> ClassifiedFilePolicy {
+        match class {
+            FileClass::Text => ClassifiedFilePolicy::Generate,
+            FileClass::Binary => self.binary,
+            FileClass::Generated => self.generated,
+            FileClass::Vendored => self.vendored,
+        }
+    }
+
+    /// Class and policy of a node; directories and unclassified records are text
+    pub fn resolve(&self, record: &NodeRecord) -> (FileClass, ClassifiedFilePolicy) {
+        let class = record
+            .metadata
+            .get(KEY_FILE_CLASS)
+            .and_then(|value| FileClass::parse(value))
+            .unwrap_or(FileClass::Text);
+        (class, self.policy_for(class))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,6 +198,12 @@ pub struct GenerationResult {
     pub level_summaries: Vec<LevelSummary>,
     pub total_generated: usize,
     pub total_failed: usize,
+    /// Provider usage recorded while executing the plan
+    #[serde(default)]
+    pub usage: Option<UsageTotals>,
+    /// True when execution stopped because a budget cap was reached
+    #[serde(default)]
+    pub budget_exceeded: bool,
 }
 
 impl GenerationResult {
@@ -127,6 +215,8 @@ impl GenerationResult {
             level_summaries: Vec::new(),
             total_generated: 0,
             total_failed: 0,
+            usage: None,
+            budget_exceeded: false,
         }
     }
 }
@@ -145,6 +235,11 @@ mod tests {
             provider_name: "provider".to_string(),
             frame_type: "context-writer".to_string(),
             force: false,
+            template: false,
+            fallback_providers: Vec::new(),
+            consumes: Vec::new(),
+            depends_on: Vec::new(),
+            user_prompt: None,
         }
     }
 
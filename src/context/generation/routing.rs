@@ -0,0 +1,231 @@
+//! Per-path routing rules from the workspace rule file (`.meld/routing.toml`).
+//!
+//! Rules map workspace-relative globs to an agent, a frame type, and a user prompt
+//! override, or skip matching nodes entirely. The first matching rule wins; nodes no
+//! rule matches keep the agents chosen by the caller.
+//!
+//! ```toml
+//! [[rule]]
+//! glob = "**/*.sql"
+//! agent = "schema-analyzer"
+//!
+//! [[rule]]
+//! glob = "docs/**"
+//! skip = true
+//!
+//! [[rule]]
+//! glob = "src/api/**"
+//! agent = "code-analyzer"
+//! user_prompt = "Document the HTTP endpoints defined in {path}"
+//! ```
+
+use crate::context::glob::glob_match_path;
+use crate::error::ApiError;
+use crate::tree::path::canonicalize_path;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Rule file location relative to the workspace root
+pub const ROUTING_FILE: &str = ".meld/routing.toml";
+
+/// One glob rule
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RoutingRule {
+    /// Workspace-relative glob; `**` matches any number of path segments
+    pub glob: String,
+    /// Agent that generates matching nodes instead of the requested agents
+    #[serde(default)]
+    pub agent: Option<String>,
+    /// Frame type for matching nodes (defaults to `context-<agent_id>`)
+    #[serde(default)]
+    pub frame_type: Option<String>,
+    /// User prompt template replacing the agent's file and directory prompts
+    #[serde(default)]
+    pub user_prompt: Option<String>,
+    /// Leave matching nodes out of generation
+    #[serde(default)]
+    pub skip: bool,
+}
+
+/// Agent a rule sends matching nodes to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutedAgent {
+    pub agent_id: String,
+    /// Frame type override; `None` keeps `context-<agent_id>`
+    pub frame_type: Option<String>,
+    /// User prompt template replacing the agent's file and directory prompts
+    pub user_prompt: Option<String>,
+}
+
+/// Ordered routing rules for a workspace
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RoutingRules {
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<RoutingRule>,
+    /// Canonical workspace root the globs are relative to
+    #[serde(skip)]
+    root: PathBuf,
+}
+
+impl RoutingRules {
+    /// Load `.meld/routing.toml` under `workspace_root`; no file means no rules
+    pub fn load(workspace_root: &Path) -> Result<Self, ApiError> {
+        let path = workspace_root.join(ROUTING_FILE);
+        let mut rules = if path.is_file() {
+            let text = std::fs::read_to_string(&path).map_err(|e| {
+                ApiError::ConfigError(format!("Failed to read {}: {}", path.display(), e))
+            })?;
+            Self::parse(&text)
+                .map_err(|e| ApiError::ConfigError(format!("{}: {}", path.display(), e)))?
+        } else {
+            Self::default()
+        };
+        rules.root =
+            canonicalize_path(workspace_root).unwrap_or_else(|_| workspace_root.to_path_buf());
+        Ok(rules)
+    }
+
+    /// Parse and validate rule file contents
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let rules: Self = toml::from_str(text).map_err(|e| e.to_string())?;
+        for (index, rule) in rules.rules.iter().enumerate() {
+            if rule.glob.trim().is_empty() {
+                return Err(format!("rule {} has an empty glob", index + 1));
+            }
+            if rule.skip
+                && (rule.agent.is_some() || rule.frame_type.is_some() || rule.user_prompt.is_some())
+            {
+                return Err(format!(
+                    "rule {} ({}) sets skip together with agent, frame_type, or user_prompt",
+                    index + 1,
+                    rule.glob
+                ));
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Agents each rule routes to, in rule order, for a caller that requested `agent_ids`
+    /// with an optional `frame_type`. Skip rules route to no agents. A rule that sets
+    /// frame_type without an agent is rejected when several agents were requested, since
+    /// they would all write the same frame type.
+    pub fn resolve_agents(
+        &self,
+        agent_ids: &[String],
+        frame_type: Option<&str>,
+    ) -> Result<Vec<Vec<RoutedAgent>>, ApiError> {
+        self.rules
+            .iter()
+            .map(|rule| {
+                let routed = |agent_id: &str, frame_type: Option<&str>| RoutedAgent {
+                    agent_id: agent_id.to_string(),
+                    frame_type: frame_type.map(str::to_string),
+                    user_prompt: rule.user_prompt.clone(),
+                };
+                if rule.skip {
+                    return Ok(Vec::new());
+                }
+                if let Some(agent_id) = &rule.agent {
+                    return Ok(vec![routed(agent_id, rule.frame_type.as_deref())]);
+                }
+                if rule.frame_type.is_some() && agent_ids.len() > 1 {
+                    return Err(ApiError::ConfigError(format!(
+                        "Routing rule `{}` sets frame_type without an agent, but {} agents were requested.",
+                        rule.glob,
+                        agent_ids.len()
+                    )));
+                }
+                let frame_type = rule.frame_type.as_deref().or(frame_type);
+                Ok(agent_ids
+                    .iter()
+                    .map(|agent_id| routed(agent_id, frame_type))
+                    .collect())
+            })
+            .collect()
+    }
+
+    /// Index and rule of the first rule matching `path`; paths outside the workspace
+    /// match nothing
+    pub fn route(&self, path: &Path) -> Option<(usize, &RoutingRule)> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| glob_match_path(&rule.glob, &relative))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let mut rules = RoutingRules::parse(
+            r#"
+            [[rule]]
+            glob = "docs/**"
+            skip = true
+
+            [[rule]]
+            glob = "**/*.md"
+            agent = "docs-writer"
+            "#,
+        )
+        .unwrap();
+        rules.root = PathBuf::from("/ws");
+        assert!(rules.route(Path::new("/ws/docs/a.md")).unwrap().1.skip);
+        let (index, rule) = rules.route(Path::new("/ws/README.md")).unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(rule.agent.as_deref(), Some("docs-writer"));
+        assert!(rules.route(Path::new("/ws/src/lib.rs")).is_none());
+        assert!(rules.route(Path::new("/elsewhere/README.md")).is_none());
+    }
+
+    #[test]
+    fn resolves_agents_per_rule() {
+        let rules = RoutingRules::parse(
+            r#"
+            [[rule]]
+            glob = "docs/**"
+            skip = true
+
+            [[rule]]
+            glob = "**/*.sql"
+            agent = "schema-analyzer"
+
+            [[rule]]
+            glob = "src/api/**"
+            user_prompt = "Document the endpoints in {path}"
+            "#,
+        )
+        .unwrap();
+        let agents = vec!["writer".to_string(), "reviewer".to_string()];
+        let resolved = rules.resolve_agents(&agents, None).unwrap();
+        assert!(resolved[0].is_empty());
+        assert_eq!(resolved[1].len(), 1);
+        assert_eq!(resolved[1][0].agent_id, "schema-analyzer");
+        assert_eq!(resolved[2].len(), 2);
+        assert!(resolved[2].iter().all(|agent| agent.frame_type.is_none()
+            && agent.user_prompt.as_deref() == Some("Document the endpoints in {path}")));
+
+        let rules =
+            RoutingRules::parse("[[rule]]\nglob = \"a/**\"\nframe_type = \"notes\"\n").unwrap();
+        let err = rules.resolve_agents(&agents, None).unwrap_err();
+        assert!(err.to_string().contains("2 agents"), "{}", err);
+        let resolved = rules.resolve_agents(&agents[..1], Some("summary")).unwrap();
+        assert_eq!(resolved[0][0].frame_type.as_deref(), Some("notes"));
+    }
+
+    #[test]
+    fn skip_rules_cannot_route() {
+        let err = RoutingRules::parse("[[rule]]\nglob = \"a/**\"\nskip = true\nagent = \"x\"\n")
+            .unwrap_err();
+        assert!(err.contains("skip"));
+    }
+}
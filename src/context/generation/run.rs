@@ -2,12 +2,22 @@
 //! CLI and other callers use this only; no plan/queue/executor orchestration in adapters.
 
 use crate::api::ContextApi;
+use crate::agent::profile::agent_dependencies;
 use crate::agent::profile::prompt_contract::PromptContract;
+use crate::agent::profile::provider_fallback;
+use crate::agent::AgentIdentity;
 use crate::context::generation::plan::{
-    FailurePolicy, GenerationItem, GenerationNodeType, GenerationPlan, PlanPriority,
+    item_key_for, ClassifiedFilePolicy, ClassifiedFilesConfig, ConsumedFrames, FailurePolicy,
+    GenerationItem, GenerationNodeType, GenerationPlan, PlanPriority,
 };
+use crate::context::generation::budget::{GenerationBudget, UsageLedger};
+use crate::context::generation::estimate::{estimate_plan, PlanEstimate};
+use crate::context::generation::routing::{RoutingRules, ROUTING_FILE};
+use crate::context::generation::store::{GenerationRunStore, RunStatus};
 use crate::context::generation::GenerationExecutor;
-use crate::context::queue::{FrameGenerationQueue, GenerationConfig, QueueEventContext};
+use crate::context::queue::{
+    FrameGenerationQueue, GenerationConfig, QueueEventContext, StreamSink,
+};
 use crate::error::ApiError;
 use crate::store::NodeType;
 use crate::telemetry::{now_millis, ProgressRuntime};
@@ -94,10 +104,38 @@ fn resolve_provider_name(api: &ContextApi, provider_name: Option<&str>) -> Resul
     Ok(provider_name.to_string())
 }
 
+/// Fallback chain for a run: the request's list, else the agent's `fallback_providers`.
+/// Every provider must exist; the primary and duplicates are dropped.
+fn resolve_fallback_providers(
+    api: &ContextApi,
+    agent: &AgentIdentity,
+    primary: &str,
+    requested: &[String],
+) -> Result<Vec<String>, ApiError> {
+    let candidates = if requested.is_empty() {
+        provider_fallback::fallback_providers(&agent.metadata)
+    } else {
+        requested.to_vec()
+    };
+    let registry = api.provider_registry().read();
+    let mut chain: Vec<String> = Vec::new();
+    for name in candidates {
+        registry.get_or_error(&name)?;
+        if name != primary && !chain.contains(&name) {
+            chain.push(name);
+        }
+    }
+    Ok(chain)
+}
+
+/// Descendants of the target missing a head for one of their routed agents or for a
+/// frame type the target's agents consume
 fn find_missing_descendant_heads(
     api: &ContextApi,
     target_node_id: NodeID,
-    frame_type: &str,
+    routing: &PlanRouting,
+    consumed_frame_types: &[&str],
+    classified_files: &ClassifiedFilesConfig,
 ) -> Result<Vec<String>, ApiError> {
     let mut missing = Vec::new();
     let mut visited: HashSet<NodeID> = HashSet::new();
@@ -119,8 +157,18 @@ fn find_missing_descendant_heads(
             .get(&node_id)
             .map_err(ApiError::from)?
             .ok_or_else(|| ApiError::NodeNotFound(node_id))?;
-        if api.get_head(&node_id, frame_type)?.is_none() {
-            missing.push(record.path.to_string_lossy().to_string());
+        let skipped = classified_files.resolve(&record).1 == ClassifiedFilePolicy::Skip;
+        if let (false, NodeRoute::Agents(agents)) = (skipped, routing.route(&record.path)) {
+            let required = agents
+                .iter()
+                .map(|a| a.frame_type.as_str())
+                .chain(consumed_frame_types.iter().copied());
+            for frame_type in required {
+                if api.get_head(&node_id, frame_type)?.is_none() {
+                    missing.push(record.path.to_string_lossy().to_string());
+                    break;
+                }
+            }
         }
         for child in &record.children {
             queue.push_back(*child);
@@ -153,6 +201,135 @@ fn collect_subtree_levels(api: &ContextApi, target_node_id: NodeID) -> Result<Ve
     Ok(ordered_depths.into_iter().map(|(_, nodes)| nodes).collect())
 }
 
+/// One agent's share of a plan
+#[derive(Clone)]
+struct PlanAgent {
+    agent_id: String,
+    frame_type: String,
+    fallback_providers: Vec<String>,
+    consumes: Vec<ConsumedFrames>,
+    user_prompt: Option<String>,
+}
+
+/// Agents per node: the first matching routing rule, else the requested agents
+struct PlanRouting {
+    rules: RoutingRules,
+    /// Agents for each rule, parallel to `rules.rules`; empty for skip rules
+    rule_agents: Vec<Vec<PlanAgent>>,
+    default_agents: Vec<PlanAgent>,
+}
+
+enum NodeRoute<'a> {
+    Agents(&'a [PlanAgent]),
+    /// Skipped by the rule with this glob
+    Skip(&'a str),
+}
+
+impl PlanRouting {
+    fn route(&self, path: &Path) -> NodeRoute<'_> {
+        match self.rules.route(path) {
+            Some((_, rule)) if rule.skip => NodeRoute::Skip(&rule.glob),
+            Some((index, _)) => NodeRoute::Agents(&self.rule_agents[index]),
+            None => NodeRoute::Agents(&self.default_agents),
+        }
+    }
+}
+
+/// Validate a Writer agent and resolve its fallback chain and consumed agents
+fn resolve_plan_agent(
+    api: &ContextApi,
+    agent_id: &str,
+    frame_type: Option<&str>,
+    provider_name: &str,
+    requested_fallbacks: &[String],
+) -> Result<PlanAgent, ApiError> {
+    let agent = api.get_agent(agent_id)?;
+    if agent.role != crate::agent::AgentRole::Writer {
+        return Err(ApiError::Unauthorized(format!(
+            "Agent '{}' has role {:?}, but only Writer agents can generate frames.",
+            agent_id, agent.role
+        )));
+    }
+    PromptContract::from_agent(&agent)?;
+    let fallback_providers =
+        resolve_fallback_providers(api, &agent, provider_name, requested_fallbacks)?;
+    let mut consumes = Vec::new();
+    for consumed in agent_dependencies::consumed_agents(agent_id, &agent.metadata) {
+        api.get_agent(&consumed)?;
+        consumes.push(ConsumedFrames {
+            frame_type: format!("context-{}", consumed),
+            agent_id: consumed,
+        });
+    }
+    Ok(PlanAgent {
+        agent_id: agent_id.to_string(),
+        frame_type: frame_type
+            .map(String::from)
+            .unwrap_or_else(|| format!("context-{}", agent_id)),
+        fallback_providers,
+        consumes,
+        user_prompt: None,
+    })
+}
+
+/// Resolve the requested agents (or the only Writer agent) and the agents of every
+/// routing rule
+fn resolve_plan_routing(
+    api: &ContextApi,
+    request: &GenerateRequest,
+    provider_name: &str,
+    rules: RoutingRules,
+) -> Result<PlanRouting, ApiError> {
+    let mut agent_ids: Vec<String> = Vec::new();
+    if request.agents.is_empty() {
+        agent_ids.push(resolve_agent_id(api, None)?);
+    }
+    for agent_id in &request.agents {
+        if !agent_ids.contains(agent_id) {
+            agent_ids.push(resolve_agent_id(api, Some(agent_id))?);
+        }
+    }
+    if agent_ids.len() > 1 && request.frame_type.is_some() {
+        return Err(ApiError::ConfigError(
+            "--frame-type applies to a single agent; omit it so each agent writes context-<agent_id>.".to_string(),
+        ));
+    }
+    let default_agents = agent_ids
+        .iter()
+        .map(|agent_id| {
+            resolve_plan_agent(
+                api,
+                agent_id,
+                request.frame_type.as_deref(),
+                provider_name,
+                &request.fallback_providers,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut rule_agents = Vec::with_capacity(rules.rules.len());
+    for routed_agents in rules.resolve_agents(&agent_ids, request.frame_type.as_deref())? {
+        let mut agents = Vec::with_capacity(routed_agents.len());
+        for routed in routed_agents {
+            let mut agent = resolve_plan_agent(
+                api,
+                &routed.agent_id,
+                routed.frame_type.as_deref(),
+                provider_name,
+                &request.fallback_providers,
+            )?;
+            agent.user_prompt = routed.user_prompt;
+            agents.push(agent);
+        }
+        rule_agents.push(agents);
+    }
+    Ok(PlanRouting {
+        rules,
+        rule_agents,
+        default_agents,
+    })
+}
+
 #[allow(clippy::too_many_arguments)]
 fn build_plan(
     api: &ContextApi,
@@ -163,11 +340,34 @@ fn build_plan(
     is_directory_target: bool,
     recursive: bool,
     force: bool,
-    agent_id: &str,
+    routing: &PlanRouting,
     provider_name: &str,
-    frame_type: &str,
+    classified_files: &ClassifiedFilesConfig,
 ) -> Result<GenerationPlan, ApiError> {
+    let target_agents = match routing.route(target_path) {
+        NodeRoute::Agents(agents) => agents,
+        NodeRoute::Skip(glob) if !recursive => {
+            return Err(ApiError::GenerationFailed(format!(
+                "{} is skipped by routing rule `{}` in {}.",
+                target_path.display(),
+                glob,
+                ROUTING_FILE
+            )));
+        }
+        NodeRoute::Skip(_) => &[],
+    };
     if !recursive && is_directory_target && !force {
+        let frame_types: Vec<&str> = target_agents
+            .iter()
+            .map(|a| a.frame_type.as_str())
+            .collect();
+        // Descendants need their own routed heads plus the heads the target consumes
+        let mut consumed_frame_types: Vec<&str> = Vec::new();
+        for consumed in target_agents.iter().flat_map(|a| &a.consumes) {
+            if !consumed_frame_types.contains(&consumed.frame_type.as_str()) {
+                consumed_frame_types.push(&consumed.frame_type);
+            }
+        }
         if let (Some(prog), Some(sid)) = (progress, session_id) {
             prog.emit_event_best_effort(
                 sid,
@@ -175,11 +375,17 @@ fn build_plan(
                 json!({
                     "node_id": hex::encode(target_node_id),
                     "path": target_path.to_string_lossy(),
-                    "frame_type": frame_type,
+                    "frame_types": frame_types,
                 }),
             );
         }
-        let missing = find_missing_descendant_heads(api, target_node_id, frame_type)?;
+        let missing = find_missing_descendant_heads(
+            api,
+            target_node_id,
+            routing,
+            &consumed_frame_types,
+            classified_files,
+        )?;
         if !missing.is_empty() {
             if let (Some(prog), Some(sid)) = (progress, session_id) {
                 prog.emit_event_best_effort(
@@ -203,24 +409,31 @@ fn build_plan(
                 json!({
                     "node_id": hex::encode(target_node_id),
                     "path": target_path.to_string_lossy(),
-                    "frame_type": frame_type,
+                    "frame_types": frame_types,
                 }),
             );
         }
     }
 
+    let depth_levels = if recursive {
+        collect_subtree_levels(api, target_node_id)?
+    } else {
+        vec![vec![target_node_id]]
+    };
     let mut levels: Vec<Vec<GenerationItem>> = Vec::new();
-    if recursive {
-        let depth_levels = collect_subtree_levels(api, target_node_id)?;
-        for level in depth_levels {
-            let mut items = Vec::new();
-            for node_id in level {
-                let record = api
-                    .node_store()
-                    .get(&node_id)
-                    .map_err(ApiError::from)?
-                    .ok_or_else(|| ApiError::NodeNotFound(node_id))?;
-                if !force && api.get_head(&node_id, frame_type)?.is_some() {
+    // Levels run deepest first, so children are planned before their parent
+    let mut planned: HashSet<String> = HashSet::new();
+    for level in depth_levels {
+        let mut items = Vec::new();
+        for node_id in level {
+            let record = api
+                .node_store()
+                .get(&node_id)
+                .map_err(ApiError::from)?
+                .ok_or_else(|| ApiError::NodeNotFound(node_id))?;
+            let agents = match routing.route(&record.path) {
+                NodeRoute::Agents(agents) => agents,
+                NodeRoute::Skip(glob) => {
                     if let (Some(prog), Some(sid)) = (progress, session_id) {
                         prog.emit_event_best_effort(
                             sid,
@@ -228,81 +441,105 @@ fn build_plan(
                             json!({
                                 "node_id": hex::encode(node_id),
                                 "path": record.path.to_string_lossy(),
-                                "agent_id": agent_id,
                                 "provider_name": provider_name,
-                                "frame_type": frame_type,
-                                "reason": "head_reuse",
+                                "reason": "routing",
+                                "rule": glob,
                             }),
                         );
                     }
                     continue;
                 }
-                items.push(GenerationItem {
+            };
+            let mut pending: Vec<&PlanAgent> = Vec::new();
+            for agent in agents {
+                if !force && api.get_head(&node_id, &agent.frame_type)?.is_some() {
+                    if let (Some(prog), Some(sid)) = (progress, session_id) {
+                        prog.emit_event_best_effort(
+                            sid,
+                            "node_skipped",
+                            json!({
+                                "node_id": hex::encode(node_id),
+                                "path": record.path.to_string_lossy(),
+                                "agent_id": agent.agent_id,
+                                "provider_name": provider_name,
+                                "frame_type": agent.frame_type,
+                                "reason": "head_reuse",
+                            }),
+                        );
+                    }
+                } else {
+                    pending.push(agent);
+                }
+            }
+            if pending.is_empty() {
+                continue;
+            }
+            let (file_class, policy) = classified_files.resolve(&record);
+            if policy == ClassifiedFilePolicy::Skip {
+                if !recursive {
+                    return Err(ApiError::GenerationFailed(format!(
+                        "{} is classified as a {} file and skipped. Set `{}` under \
+                         [context.classified_files] to \"template\" or \"generate\" to include it.",
+                        target_path.display(),
+                        file_class.as_str(),
+                        file_class.as_str()
+                    )));
+                }
+                if let (Some(prog), Some(sid)) = (progress, session_id) {
+                    for agent in &pending {
+                        prog.emit_event_best_effort(
+                            sid,
+                            "node_skipped",
+                            json!({
+                                "node_id": hex::encode(node_id),
+                                "path": record.path.to_string_lossy(),
+                                "agent_id": agent.agent_id,
+                                "provider_name": provider_name,
+                                "frame_type": agent.frame_type,
+                                "reason": "classified",
+                                "file_class": file_class.as_str(),
+                            }),
+                        );
+                    }
+                }
+                continue;
+            }
+            for agent in pending {
+                let depends_on: Vec<String> = record
+                    .children
+                    .iter()
+                    .flat_map(|child| {
+                        agent
+                            .consumes
+                            .iter()
+                            .map(move |c| item_key_for(child, &c.frame_type))
+                    })
+                    .filter(|key| planned.contains(key))
+                    .collect();
+                let item = GenerationItem {
                     node_id,
                     path: record.path.to_string_lossy().to_string(),
                     node_type: match record.node_type {
                         NodeType::File { .. } => GenerationNodeType::File,
                         NodeType::Directory => GenerationNodeType::Directory,
                     },
-                    agent_id: agent_id.to_string(),
+                    agent_id: agent.agent_id.clone(),
                     provider_name: provider_name.to_string(),
-                    frame_type: frame_type.to_string(),
+                    frame_type: agent.frame_type.clone(),
                     force,
-                });
-            }
-            if !items.is_empty() {
-                levels.push(items);
+                    template: policy == ClassifiedFilePolicy::Template,
+                    fallback_providers: agent.fallback_providers.clone(),
+                    consumes: agent.consumes.clone(),
+                    depends_on,
+                    user_prompt: agent.user_prompt.clone(),
+                };
+                planned.insert(item.key());
+                items.push(item);
             }
         }
-    } else {
-        if !force && api.get_head(&target_node_id, frame_type)?.is_some() {
-            if let (Some(prog), Some(sid)) = (progress, session_id) {
-                prog.emit_event_best_effort(
-                    sid,
-                    "node_skipped",
-                    json!({
-                        "node_id": hex::encode(target_node_id),
-                        "path": target_path.to_string_lossy(),
-                        "agent_id": agent_id,
-                        "provider_name": provider_name,
-                        "frame_type": frame_type,
-                        "reason": "head_reuse",
-                    }),
-                );
-            }
-            return Ok(GenerationPlan {
-                plan_id: format!(
-                    "plan-{}-{}",
-                    now_millis(),
-                    &hex::encode(target_node_id)[..8]
-                ),
-                source: format!("context generate {}", target_path.to_string_lossy()),
-                session_id: session_id.map(String::from),
-                levels: Vec::new(),
-                priority: PlanPriority::Urgent,
-                failure_policy: FailurePolicy::StopOnLevelFailure,
-                target_path: target_path.to_string_lossy().to_string(),
-                total_nodes: 0,
-                total_levels: 0,
-            });
+        if !items.is_empty() {
+            levels.push(items);
         }
-        let target_record = api
-            .node_store()
-            .get(&target_node_id)
-            .map_err(ApiError::from)?
-            .ok_or_else(|| ApiError::NodeNotFound(target_node_id))?;
-        levels.push(vec![GenerationItem {
-            node_id: target_node_id,
-            path: target_record.path.to_string_lossy().to_string(),
-            node_type: match target_record.node_type {
-                NodeType::File { .. } => GenerationNodeType::File,
-                NodeType::Directory => GenerationNodeType::Directory,
-            },
-            agent_id: agent_id.to_string(),
-            provider_name: provider_name.to_string(),
-            frame_type: frame_type.to_string(),
-            force,
-        }]);
     }
 
     let total_nodes: usize = levels.iter().map(Vec::len).sum();
@@ -328,26 +565,38 @@ fn build_plan(
 pub struct GenerateRequest {
     pub node: Option<String>,
     pub path: Option<PathBuf>,
-    pub agent: Option<String>,
+    /// Agents to generate with; each node gets one frame per agent. Empty uses the only
+    /// Writer agent.
+    pub agents: Vec<String>,
     pub provider: Option<String>,
+    /// Providers to fail over to, in order; empty uses the agent's `fallback_providers`
+    pub fallback_providers: Vec<String>,
     pub frame_type: Option<String>,
     pub force: bool,
     pub no_recursive: bool,
+    /// Bypass the provider response cache
+    pub no_cache: bool,
+    /// Stream responses: printed live for a single node, emitted as progress events for
+    /// batch runs
+    pub stream: bool,
+    /// Spend and token caps; generation stops once a cap is reached
+    pub budget: GenerationBudget,
+    /// How binary, generated, and vendored files are planned
+    pub classified_files: ClassifiedFilesConfig,
 }
 
-/// Single generate entry point: resolve node/agent/provider, build plan, create queue, execute.
-/// Returns human-readable summary string or error.
-pub fn run_generate(
-    api: Arc<ContextApi>,
+/// Resolve node/agent/provider and build the plan for a request.
+fn prepare_plan(
+    api: &ContextApi,
     workspace_root: &PathBuf,
-    progress: Option<Arc<ProgressRuntime>>,
+    progress: Option<&Arc<ProgressRuntime>>,
     session_id: Option<&str>,
     request: &GenerateRequest,
-) -> Result<String, ApiError> {
+) -> Result<GenerationPlan, ApiError> {
     let node_id = match (request.node.as_deref(), request.path.as_deref()) {
         (Some(node_str), None) => parse_node_id(node_str)?,
         (None, Some(p)) => workspace::resolve_workspace_node_id(
-            api.as_ref(),
+            api,
             workspace_root,
             Some(p),
             None,
@@ -365,20 +614,9 @@ pub fn run_generate(
         }
     };
 
-    let agent_id = resolve_agent_id(api.as_ref(), request.agent.as_deref())?;
-    let provider_name = resolve_provider_name(api.as_ref(), request.provider.as_deref())?;
-    let frame_type = request
-        .frame_type
-        .clone()
-        .unwrap_or_else(|| format!("context-{}", agent_id));
-
-    let agent = api.get_agent(&agent_id)?;
-    if agent.role != crate::agent::AgentRole::Writer {
-        return Err(ApiError::Unauthorized(format!(
-            "Agent '{}' has role {:?}, but only Writer agents can generate frames.",
-            agent_id, agent.role
-        )));
-    }
+    let provider_name = resolve_provider_name(api, request.provider.as_deref())?;
+    let rules = RoutingRules::load(workspace_root)?;
+    let routing = resolve_plan_routing(api, request, &provider_name, rules)?;
 
     let node_record = api
         .node_store()
@@ -387,26 +625,25 @@ pub fn run_generate(
         .ok_or_else(|| ApiError::NodeNotFound(node_id))?;
     let node_path = node_record.path.to_string_lossy().to_string();
 
-    PromptContract::from_agent(&agent)?;
-
     let is_directory_target = matches!(node_record.node_type, NodeType::Directory);
     let recursive = is_directory_target && !request.no_recursive;
 
     let plan = build_plan(
-        api.as_ref(),
-        progress.as_ref(),
+        api,
+        progress,
         session_id,
         node_id,
         &node_record.path,
         is_directory_target,
         recursive,
         request.force,
-        &agent_id,
+        &routing,
         &provider_name,
-        &frame_type,
+        &request.classified_files,
     )?;
 
-    if let (Some(prog), Some(sid)) = (progress.as_deref(), session_id) {
+    let agents = &routing.default_agents;
+    if let (Some(prog), Some(sid)) = (progress, session_id) {
         prog.emit_event_best_effort(
             sid,
             "plan_constructed",
@@ -414,9 +651,13 @@ pub fn run_generate(
                 "plan_id": plan.plan_id,
                 "node_id": hex::encode(node_id),
                 "path": node_path,
-                "agent_id": agent_id,
+                "agent_id": agents[0].agent_id,
+                "agent_ids": agents.iter().map(|a| a.agent_id.as_str()).collect::<Vec<_>>(),
                 "provider_name": provider_name,
-                "frame_type": frame_type,
+                "fallback_providers": agents[0].fallback_providers,
+                "frame_type": agents[0].frame_type,
+                "frame_types": agents.iter().map(|a| a.frame_type.as_str()).collect::<Vec<_>>(),
+                "routing_rules": routing.rules.rules.len(),
                 "force": request.force,
                 "recursive": recursive,
                 "total_nodes": plan.total_nodes,
@@ -424,6 +665,41 @@ pub fn run_generate(
             }),
         );
     }
+    Ok(plan)
+}
+
+/// Build the plan for a request and estimate its tokens and cost without calling providers.
+pub fn estimate_generate(
+    api: &ContextApi,
+    workspace_root: &PathBuf,
+    progress: Option<&Arc<ProgressRuntime>>,
+    session_id: Option<&str>,
+    request: &GenerateRequest,
+) -> Result<PlanEstimate, ApiError> {
+    let plan = prepare_plan(api, workspace_root, progress, session_id, request)?;
+    estimate_plan(api, &plan)
+}
+
+/// Single generate entry point: resolve node/agent/provider, build plan, create queue, execute.
+/// When `runs` is given the plan and per-item status are persisted so the run can be resumed.
+/// With `request.stream`, `live_sink` receives the streamed response of a single-node run.
+/// Returns human-readable summary string or error.
+pub fn run_generate(
+    api: Arc<ContextApi>,
+    workspace_root: &PathBuf,
+    progress: Option<Arc<ProgressRuntime>>,
+    session_id: Option<&str>,
+    runs: Option<Arc<GenerationRunStore>>,
+    request: &GenerateRequest,
+    live_sink: Option<Arc<StreamSink>>,
+) -> Result<String, ApiError> {
+    let plan = prepare_plan(
+        api.as_ref(),
+        workspace_root,
+        progress.as_ref(),
+        session_id,
+        request,
+    )?;
 
     if plan.total_nodes == 0 {
         return Ok(
@@ -432,6 +708,120 @@ pub fn run_generate(
         );
     }
 
+    ensure_budget_priceable(api.as_ref(), &plan, &request.budget)?;
+    if let Some(runs) = &runs {
+        runs.create_run(&plan)?;
+    }
+    let plan_id = plan.plan_id.clone();
+    let stream_sink: Option<Arc<StreamSink>> = match (request.stream, live_sink) {
+        (true, Some(sink)) if plan.total_nodes == 1 => Some(sink),
+        (true, _) => Some(Arc::new(|_: &str| {})),
+        (false, _) => None,
+    };
+    let result = execute_plan(
+        api,
+        progress,
+        session_id,
+        runs,
+        plan,
+        request.budget,
+        request.no_cache,
+        stream_sink,
+    );
+    summarize_result(&plan_id, &result?)
+}
+
+/// Resume a persisted plan: run only the items that have not completed.
+/// Without `plan_id`, resumes the newest plan that is not completed.
+pub fn resume_generate(
+    api: Arc<ContextApi>,
+    progress: Option<Arc<ProgressRuntime>>,
+    session_id: Option<&str>,
+    runs: Arc<GenerationRunStore>,
+    plan_id: Option<&str>,
+    budget: GenerationBudget,
+) -> Result<String, ApiError> {
+    let record = match plan_id {
+        Some(plan_id) => runs.get_run(plan_id)?.ok_or_else(|| {
+            ApiError::ConfigError(format!(
+                "Generation plan not found: {}. Use `meld context runs` to list plans.",
+                plan_id
+            ))
+        })?,
+        None => runs.latest_resumable()?.ok_or_else(|| {
+            ApiError::ConfigError("No incomplete generation plans to resume.".to_string())
+        })?,
+    };
+    let plan_id = record.plan.plan_id.clone();
+    let mut plan = runs.remaining_plan(&record)?;
+    if plan.total_nodes == 0 {
+        runs.finish_run(&plan_id, RunStatus::Completed, None)?;
+        return Ok(format!("Plan {} has no remaining items.", plan_id));
+    }
+    ensure_budget_priceable(api.as_ref(), &plan, &budget)?;
+    runs.start_attempt(&plan_id, session_id)?;
+    plan.session_id = session_id.map(String::from);
+
+    if let (Some(prog), Some(sid)) = (progress.as_deref(), session_id) {
+        prog.emit_event_best_effort(
+            sid,
+            "plan_resumed",
+            json!({
+                "plan_id": plan_id,
+                "remaining_nodes": plan.total_nodes,
+                "remaining_levels": plan.total_levels,
+                "total_nodes": record.plan.total_nodes,
+            }),
+        );
+    }
+
+    let result = execute_plan(
+        api,
+        progress,
+        session_id,
+        Some(runs),
+        plan,
+        budget,
+        false,
+        None,
+    )?;
+    summarize_result(&plan_id, &result)
+}
+
+/// A cost cap can only be enforced when every provider in the plan has pricing.
+fn ensure_budget_priceable(
+    api: &ContextApi,
+    plan: &GenerationPlan,
+    budget: &GenerationBudget,
+) -> Result<(), ApiError> {
+    if budget.max_cost.is_none() {
+        return Ok(());
+    }
+    let registry = api.provider_registry().read();
+    for item in plan.levels.iter().flatten() {
+        for provider_name in std::iter::once(&item.provider_name).chain(&item.fallback_providers) {
+            if registry.get_or_error(provider_name)?.pricing.is_none() {
+                return Err(ApiError::ConfigError(format!(
+                    "Provider '{}' has no pricing configured; --max-cost needs [pricing] in the provider config.",
+                    provider_name
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_plan(
+    api: Arc<ContextApi>,
+    progress: Option<Arc<ProgressRuntime>>,
+    session_id: Option<&str>,
+    runs: Option<Arc<GenerationRunStore>>,
+    plan: GenerationPlan,
+    budget: GenerationBudget,
+    no_cache: bool,
+    stream_sink: Option<Arc<StreamSink>>,
+) -> Result<crate::context::generation::plan::GenerationResult, ApiError> {
     let rt = if let Ok(_handle) = tokio::runtime::Handle::try_current() {
         return Err(ApiError::ProviderError(
             "Cannot generate context from within an async runtime context. This is a limitation when running from async tests.".to_string()
@@ -449,27 +839,97 @@ pub fn run_generate(
         }),
         _ => None,
     };
-    let queue = Arc::new(FrameGenerationQueue::with_event_context(
-        api,
-        gen_config,
-        event_context,
-    ));
+    let usage = Arc::new(UsageLedger::new(budget));
+    let mut queue = FrameGenerationQueue::with_event_context(api, gen_config, event_context)
+        .with_usage_ledger(Arc::clone(&usage));
+    if no_cache {
+        queue = queue.without_response_cache();
+    }
+    if let Some(sink) = stream_sink {
+        queue = queue.with_streaming(sink);
+    }
+    let queue = Arc::new(queue);
 
     let _guard = rt.enter();
     queue.start()?;
-    let executor = GenerationExecutor::new(progress);
+    let mut executor = GenerationExecutor::new(progress.clone());
+    if let Some(runs) = runs {
+        executor = executor.with_run_store(runs);
+    }
     drop(_guard);
-    let result = rt.block_on(async { executor.execute(queue.as_ref(), plan).await })?;
+    let outcome = rt.block_on(async { executor.execute(queue.as_ref(), plan).await });
 
+    let totals = usage.totals();
+    if let (Some(prog), Some(sid)) = (progress.as_deref(), session_id) {
+        prog.emit_event_best_effort(
+            sid,
+            "generation_usage",
+            json!({
+                "requests": totals.requests,
+                "prompt_tokens": totals.prompt_tokens,
+                "completion_tokens": totals.completion_tokens,
+                "total_tokens": totals.total_tokens,
+                "cost": totals.cost,
+                "unpriced_requests": totals.unpriced_requests,
+                "estimated_requests": totals.estimated_requests,
+                "estimated_tokens": totals.estimated_tokens,
+                "budget_exceeded": usage.is_exceeded(),
+            }),
+        );
+    }
+    let mut result = outcome?;
+    result.budget_exceeded = !budget.is_unlimited() && usage.is_exceeded();
+    result.usage = Some(totals);
+    Ok(result)
+}
+
+fn summarize_result(
+    plan_id: &str,
+    result: &crate::context::generation::plan::GenerationResult,
+) -> Result<String, ApiError> {
+    if result.budget_exceeded {
+        return Err(ApiError::BudgetExceeded(format!(
+            "Generation paused: generated={}, failed={}.{} Resume with `meld context resume {}`.",
+            result.total_generated,
+            result.total_failed,
+            format_usage(result),
+            plan_id
+        )));
+    }
     if result.total_failed > 0 {
-        let failure_samples = format_failure_samples(&result, 3);
+        let failure_samples = format_failure_samples(result, 3);
         return Err(ApiError::GenerationFailed(format!(
             "Generation completed with failures. generated={}, failed={}.{}",
             result.total_generated, result.total_failed, failure_samples
         )));
     }
     Ok(format!(
-        "Generation completed: generated={}, failed={}",
-        result.total_generated, result.total_failed
+        "Generation completed: generated={}, failed={}{}",
+        result.total_generated,
+        result.total_failed,
+        format_usage(result)
     ))
 }
+
+fn format_usage(result: &crate::context::generation::plan::GenerationResult) -> String {
+    let Some(usage) = &result.usage else {
+        return String::new();
+    };
+    let mut out = String::new();
+    if usage.requests > 0 {
+        out.push_str(&format!(
+            " tokens={} (prompt={}, completion={})",
+            usage.total_tokens, usage.prompt_tokens, usage.completion_tokens
+        ));
+        if usage.unpriced_requests < usage.requests {
+            out.push_str(&format!(", cost=${:.4}", usage.cost));
+        }
+    }
+    if usage.estimated_requests > 0 {
+        out.push_str(&format!(
+            " estimated_tokens={} (included above; {} responses without provider usage)",
+            usage.estimated_tokens, usage.estimated_requests
+        ));
+    }
+    out
+}
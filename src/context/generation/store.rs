@@ -0,0 +1,398 @@
+//! Durable sled-backed store for generation plans and per-item status.
+//! Lets an interrupted `context generate` resume without re-planning.
+
+use std::collections::HashMap;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+use sled::{Db, Tree};
+
+use crate::context::generation::plan::{GenerationItem, GenerationPlan};
+use crate::error::StorageError;
+use crate::telemetry::now_millis;
+use crate::types::FrameID;
+
+const TREE_RUNS: &str = "gen_runs";
+const TREE_ITEMS: &str = "gen_run_items";
+
+/// Lifecycle of a persisted plan
+///
+/// A plan stays `Running` until its executor finishes, so a run that was killed
+/// (Ctrl-C, crash) is still reported as running and is resumable.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+impl RunStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunStatus::Running => "running",
+            RunStatus::Completed => "completed",
+            RunStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemState {
+    pub status: ItemStatus,
+    pub frame_id: Option<FrameID>,
+    pub error: Option<String>,
+    pub updated_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationRunRecord {
+    pub plan: GenerationPlan,
+    pub status: RunStatus,
+    pub created_at_ms: u64,
+    pub updated_at_ms: u64,
+    /// Number of times the plan was started (1 for the original run)
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+/// Plan record with item counts, as listed by `meld context runs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationRunSummary {
+    pub plan_id: String,
+    pub source: String,
+    pub target_path: String,
+    pub status: RunStatus,
+    pub created_at_ms: u64,
+    pub updated_at_ms: u64,
+    pub attempts: u32,
+    pub total_nodes: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub pending: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct GenerationRunStore {
+    runs: Tree,
+    items: Tree,
+}
+
+impl GenerationRunStore {
+    pub fn new(db: Db) -> Result<Self, StorageError> {
+        let runs = db.open_tree(TREE_RUNS).map_err(to_storage_io)?;
+        let items = db.open_tree(TREE_ITEMS).map_err(to_storage_io)?;
+        Ok(Self { runs, items })
+    }
+
+    /// Persist a new plan; items without a recorded state are pending
+    pub fn create_run(&self, plan: &GenerationPlan) -> Result<GenerationRunRecord, StorageError> {
+        let now = now_millis();
+        let record = GenerationRunRecord {
+            plan: plan.clone(),
+            status: RunStatus::Running,
+            created_at_ms: now,
+            updated_at_ms: now,
+            attempts: 1,
+            error: None,
+        };
+        self.put_run(&record)?;
+        self.runs.flush().map_err(to_storage_io)?;
+        Ok(record)
+    }
+
+    pub fn get_run(&self, plan_id: &str) -> Result<Option<GenerationRunRecord>, StorageError> {
+        let Some(raw) = self.runs.get(plan_id.as_bytes()).map_err(to_storage_io)? else {
+            return Ok(None);
+        };
+        let parsed = serde_json::from_slice(&raw).map_err(to_storage_data)?;
+        Ok(Some(parsed))
+    }
+
+    /// All runs, newest first
+    pub fn list_runs(&self) -> Result<Vec<GenerationRunRecord>, StorageError> {
+        let mut out = Vec::new();
+        for result in self.runs.iter() {
+            let (_, value) = result.map_err(to_storage_io)?;
+            let rec: GenerationRunRecord =
+                serde_json::from_slice(&value).map_err(to_storage_data)?;
+            out.push(rec);
+        }
+        out.sort_by(|a, b| {
+            b.created_at_ms
+                .cmp(&a.created_at_ms)
+                .then_with(|| b.plan.plan_id.cmp(&a.plan.plan_id))
+        });
+        Ok(out)
+    }
+
+    /// Newest run that is not completed
+    pub fn latest_resumable(&self) -> Result<Option<GenerationRunRecord>, StorageError> {
+        Ok(self
+            .list_runs()?
+            .into_iter()
+            .find(|run| run.status != RunStatus::Completed))
+    }
+
+    /// Mark a run as started again and record the session driving it
+    pub fn start_attempt(
+        &self,
+        plan_id: &str,
+        session_id: Option<&str>,
+    ) -> Result<GenerationRunRecord, StorageError> {
+        let mut record = self.require_run(plan_id)?;
+        record.status = RunStatus::Running;
+        record.attempts += 1;
+        record.error = None;
+        record.plan.session_id = session_id.map(String::from);
+        record.updated_at_ms = now_millis();
+        self.put_run(&record)?;
+        Ok(record)
+    }
+
+    pub fn finish_run(
+        &self,
+        plan_id: &str,
+        status: RunStatus,
+        error: Option<String>,
+    ) -> Result<(), StorageError> {
+        let mut record = self.require_run(plan_id)?;
+        record.status = status;
+        record.error = error;
+        record.updated_at_ms = now_millis();
+        self.put_run(&record)?;
+        self.runs.flush().map_err(to_storage_io)?;
+        Ok(())
+    }
+
+    pub fn record_item(
+        &self,
+        plan_id: &str,
+        item: &GenerationItem,
+        outcome: Result<FrameID, String>,
+    ) -> Result<(), StorageError> {
+        let state = match outcome {
+            Ok(frame_id) => ItemState {
+                status: ItemStatus::Completed,
+                frame_id: Some(frame_id),
+                error: None,
+                updated_at_ms: now_millis(),
+            },
+            Err(error) => ItemState {
+                status: ItemStatus::Failed,
+                frame_id: None,
+                error: Some(error),
+                updated_at_ms: now_millis(),
+            },
+        };
+        self.put_item(plan_id, item, &state)?;
+        self.items.flush().map_err(to_storage_io)?;
+        Ok(())
+    }
+
+    /// Item states for a plan, keyed by [`item_key`]
+    pub fn item_states(&self, plan_id: &str) -> Result<HashMap<String, ItemState>, StorageError> {
+        let prefix = format!("{plan_id}:");
+        let mut out = HashMap::new();
+        for result in self.items.scan_prefix(prefix.as_bytes()) {
+            let (key, value) = result.map_err(to_storage_io)?;
+            let state: ItemState = serde_json::from_slice(&value).map_err(to_storage_data)?;
+            let key = String::from_utf8_lossy(&key[prefix.len()..]).to_string();
+            out.insert(key, state);
+        }
+        Ok(out)
+    }
+
+    pub fn summarize(
+        &self,
+        record: &GenerationRunRecord,
+    ) -> Result<GenerationRunSummary, StorageError> {
+        let states = self.item_states(&record.plan.plan_id)?;
+        let mut summary = GenerationRunSummary {
+            plan_id: record.plan.plan_id.clone(),
+            source: record.plan.source.clone(),
+            target_path: record.plan.target_path.clone(),
+            status: record.status,
+            created_at_ms: record.created_at_ms,
+            updated_at_ms: record.updated_at_ms,
+            attempts: record.attempts,
+            total_nodes: record.plan.total_nodes,
+            completed: 0,
+            failed: 0,
+            pending: 0,
+            error: record.error.clone(),
+        };
+        for item in record.plan.levels.iter().flatten() {
+            match states.get(&item_key(item)).map(|s| s.status) {
+                Some(ItemStatus::Completed) => summary.completed += 1,
+                Some(ItemStatus::Failed) => summary.failed += 1,
+                Some(ItemStatus::Pending) | None => summary.pending += 1,
+            }
+        }
+        Ok(summary)
+    }
+
+    /// The plan restricted to items that have not completed; empty levels are dropped
+    pub fn remaining_plan(
+        &self,
+        record: &GenerationRunRecord,
+    ) -> Result<GenerationPlan, StorageError> {
+        let states = self.item_states(&record.plan.plan_id)?;
+        let levels: Vec<Vec<GenerationItem>> = record
+            .plan
+            .levels
+            .iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .filter(|item| {
+                        states.get(&item_key(item)).map(|s| s.status) != Some(ItemStatus::Completed)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .filter(|level| !level.is_empty())
+            .collect();
+        let mut plan = record.plan.clone();
+        plan.total_nodes = levels.iter().map(Vec::len).sum();
+        plan.total_levels = levels.len();
+        plan.levels = levels;
+        Ok(plan)
+    }
+
+    fn require_run(&self, plan_id: &str) -> Result<GenerationRunRecord, StorageError> {
+        self.get_run(plan_id)?.ok_or_else(|| {
+            StorageError::InvalidPath(format!("Generation plan not found: {}", plan_id))
+        })
+    }
+
+    fn put_run(&self, record: &GenerationRunRecord) -> Result<(), StorageError> {
+        let value = serde_json::to_vec(record).map_err(to_storage_data)?;
+        self.runs
+            .insert(record.plan.plan_id.as_bytes(), value)
+            .map_err(to_storage_io)?;
+        Ok(())
+    }
+
+    fn put_item(
+        &self,
+        plan_id: &str,
+        item: &GenerationItem,
+        state: &ItemState,
+    ) -> Result<(), StorageError> {
+        let key = format!("{}:{}", plan_id, item_key(item));
+        let value = serde_json::to_vec(state).map_err(to_storage_data)?;
+        self.items
+            .insert(key.as_bytes(), value)
+            .map_err(to_storage_io)?;
+        Ok(())
+    }
+}
+
+/// Stable per-plan item key: node and frame type
+pub fn item_key(item: &GenerationItem) -> String {
+    item.key()
+}
+
+fn to_storage_io(err: sled::Error) -> StorageError {
+    StorageError::IoError(io::Error::other(err.to_string()))
+}
+
+fn to_storage_data(err: serde_json::Error) -> StorageError {
+    StorageError::IoError(io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::generation::plan::{FailurePolicy, GenerationNodeType, PlanPriority};
+    use crate::types::Hash;
+
+    fn item(id: u8) -> GenerationItem {
+        GenerationItem {
+            node_id: Hash::from([id; 32]),
+            path: format!("/tmp/{id}.txt"),
+            node_type: GenerationNodeType::File,
+            agent_id: "writer".to_string(),
+            provider_name: "provider".to_string(),
+            frame_type: "context-writer".to_string(),
+            force: false,
+            template: false,
+            fallback_providers: Vec::new(),
+            consumes: Vec::new(),
+            depends_on: Vec::new(),
+            user_prompt: None,
+        }
+    }
+
+    fn plan(plan_id: &str) -> GenerationPlan {
+        GenerationPlan {
+            plan_id: plan_id.to_string(),
+            source: "test".to_string(),
+            session_id: None,
+            levels: vec![vec![item(1), item(2)], vec![item(3)]],
+            priority: PlanPriority::Urgent,
+            failure_policy: FailurePolicy::StopOnLevelFailure,
+            target_path: "/tmp".to_string(),
+            total_nodes: 3,
+            total_levels: 2,
+        }
+    }
+
+    fn store() -> (tempfile::TempDir, GenerationRunStore) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        (dir, GenerationRunStore::new(db).unwrap())
+    }
+
+    #[test]
+    fn remaining_plan_skips_completed_items() {
+        let (_dir, store) = store();
+        let record = store.create_run(&plan("plan-1")).unwrap();
+        store
+            .record_item("plan-1", &item(1), Ok(Hash::from([9u8; 32])))
+            .unwrap();
+        store
+            .record_item("plan-1", &item(2), Err("boom".to_string()))
+            .unwrap();
+
+        let remaining = store.remaining_plan(&record).unwrap();
+        assert_eq!(remaining.total_nodes, 2);
+        assert_eq!(remaining.total_levels, 2);
+        assert_eq!(remaining.levels[0][0].node_id, item(2).node_id);
+        remaining.validate().unwrap();
+
+        let summary = store.summarize(&record).unwrap();
+        assert_eq!(
+            (summary.completed, summary.failed, summary.pending),
+            (1, 1, 1)
+        );
+    }
+
+    #[test]
+    fn latest_resumable_ignores_completed_runs() {
+        let (_dir, store) = store();
+        store.create_run(&plan("plan-a")).unwrap();
+        store.create_run(&plan("plan-b")).unwrap();
+        store
+            .finish_run("plan-b", RunStatus::Completed, None)
+            .unwrap();
+
+        let latest = store.latest_resumable().unwrap().unwrap();
+        assert_eq!(latest.plan.plan_id, "plan-a");
+
+        let restarted = store.start_attempt("plan-a", Some("s2")).unwrap();
+        assert_eq!(restarted.attempts, 2);
+        assert_eq!(restarted.plan.session_id.as_deref(), Some("s2"));
+        assert!(store.start_attempt("missing", None).is_err());
+    }
+}
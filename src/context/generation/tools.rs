@@ -0,0 +1,296 @@
+//! Workspace tools for agentic generation.
+//!
+//! Tool-using agents may call `read_file`, `get_context`, and `list_dir` while a frame is
+//! generated. Every tool answers from the scanned tree through `ContextApi`: paths are
+//! resolved relative to the workspace root, and paths outside it (or not in the tree) are
+//! reported back to the model as errors rather than failing the frame.
+
+use crate::agent::profile::ToolPolicy;
+use crate::api::{ContextApi, ContextView};
+use crate::context::queue::FrameGenerationQueue;
+use crate::error::ApiError;
+use crate::provider::{ToolCall, ToolDefinition};
+use crate::store::{NodeRecord, NodeType};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+
+/// Tool output beyond this many bytes is truncated before it reaches the model
+pub const MAX_TOOL_RESULT_BYTES: usize = 32 * 1024;
+
+/// Frames `get_context` returns per node
+const CONTEXT_FRAMES: usize = 5;
+
+/// System prompt addition describing the tools
+pub const TOOL_INSTRUCTIONS: &str = "You can call tools to read other files in this \
+     workspace, their generated context, and directory listings before you answer. Paths \
+     are relative to the workspace root. Call tools only when the information is needed, \
+     then answer the task directly.";
+
+/// One tool call made while generating a frame, as recorded in provenance
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolTranscriptEntry {
+    /// Tool-calling round the call was made in, from 1
+    pub step: usize,
+    pub tool: String,
+    pub arguments: Value,
+    /// Bytes of output returned to the model
+    pub result_bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Tools bound to the workspace of the node being generated
+pub struct WorkspaceTools<'a> {
+    api: &'a ContextApi,
+    root: PathBuf,
+    enabled: Vec<String>,
+}
+
+impl<'a> WorkspaceTools<'a> {
+    /// Tools for generating `node_record`; the workspace root is its top ancestor
+    pub fn for_node(
+        api: &'a ContextApi,
+        node_record: &NodeRecord,
+        policy: &ToolPolicy,
+    ) -> Result<Self, ApiError> {
+        let mut root = node_record.clone();
+        while let Some(parent) = root.parent {
+            match api.node_store().get(&parent).map_err(ApiError::from)? {
+                Some(record) => root = record,
+                None => break,
+            }
+        }
+        Ok(Self {
+            api,
+            root: root.path,
+            enabled: policy.tools.clone(),
+        })
+    }
+
+    /// Definitions of the enabled tools, for the provider request
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        let path_parameter = |description: &str| {
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string", "description": description}
+                },
+                "required": ["path"],
+                "additionalProperties": false
+            })
+        };
+        self.enabled
+            .iter()
+            .map(|name| {
+                let (description, path) = match name.as_str() {
+                    "read_file" => (
+                        "Read the content of a file in the workspace.",
+                        "File path relative to the workspace root",
+                    ),
+                    "get_context" => (
+                        "Read the generated context frames of a file or directory.",
+                        "Path relative to the workspace root",
+                    ),
+                    _ => (
+                        "List the entries of a directory in the workspace.",
+                        "Directory path relative to the workspace root; \".\" for the root",
+                    ),
+                };
+                ToolDefinition {
+                    name: name.clone(),
+                    description: description.to_string(),
+                    parameters: path_parameter(path),
+                }
+            })
+            .collect()
+    }
+
+    /// Run one tool call. Failures are returned as text for the model, never as errors.
+    pub fn call(&self, call: &ToolCall, step: usize) -> (String, ToolTranscriptEntry) {
+        let result = match call.arguments.get("path").and_then(Value::as_str) {
+            _ if !self.enabled.contains(&call.name) => {
+                Err(format!("tool '{}' is not available", call.name))
+            }
+            None => Err("missing string argument 'path'".to_string()),
+            Some(path) => self
+                .resolve(path)
+                .and_then(|node| match call.name.as_str() {
+                    "read_file" => self.read_file(&node),
+                    "get_context" => self.get_context(&node),
+                    _ => self.list_dir(&node),
+                }),
+        };
+        let (output, error) = match result {
+            Ok(output) => (truncate_output(output), None),
+            Err(error) => (format!("error: {}", error), Some(error)),
+        };
+        let entry = ToolTranscriptEntry {
+            step,
+            tool: call.name.clone(),
+            arguments: call.arguments.clone(),
+            result_bytes: output.len(),
+            error,
+        };
+        (output, entry)
+    }
+
+    /// Node at a workspace-relative (or absolute, inside the workspace) path
+    fn resolve(&self, path: &str) -> Result<NodeRecord, String> {
+        let relative = workspace_relative(&self.root, Path::new(path.trim()))?;
+        self.api
+            .node_store()
+            .find_by_path(&self.root.join(&relative))
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("'{}' is not in the workspace tree", path))
+    }
+
+    fn display_path(&self, node: &NodeRecord) -> String {
+        match node.path.strip_prefix(&self.root) {
+            Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+            Ok(relative) => relative.display().to_string(),
+            Err(_) => node.path.display().to_string(),
+        }
+    }
+
+    fn read_file(&self, node: &NodeRecord) -> Result<String, String> {
+        let NodeType::File { content_hash, .. } = node.node_type else {
+            return Err(format!("'{}' is a directory", self.display_path(node)));
+        };
+        let bytes = FrameGenerationQueue::read_scanned_file_bytes(self.api, node, &content_hash)
+            .map_err(|e| e.to_string())?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    fn get_context(&self, node: &NodeRecord) -> Result<String, String> {
+        let view = ContextView::builder()
+            .max_frames(CONTEXT_FRAMES)
+            .recent()
+            .build();
+        let context = self
+            .api
+            .get_node(node.node_id, view)
+            .map_err(|e| e.to_string())?;
+        let frames: Vec<String> = context
+            .frames
+            .iter()
+            .filter(|frame| !frame.is_deleted())
+            .map(|frame| {
+                format!(
+                    "[{} by {}]\n{}",
+                    frame.frame_type,
+                    frame.agent_id().unwrap_or("unknown"),
+                    String::from_utf8_lossy(&frame.content)
+                )
+            })
+            .collect();
+        if frames.is_empty() {
+            return Ok(format!("No context for '{}' yet.", self.display_path(node)));
+        }
+        Ok(frames.join("\n\n"))
+    }
+
+    fn list_dir(&self, node: &NodeRecord) -> Result<String, String> {
+        if !matches!(node.node_type, NodeType::Directory) {
+            return Err(format!("'{}' is not a directory", self.display_path(node)));
+        }
+        let mut entries = Vec::new();
+        for child in &node.children {
+            let Some(record) = self
+                .api
+                .node_store()
+                .get(child)
+                .map_err(|e| e.to_string())?
+            else {
+                continue;
+            };
+            if record.tombstoned_at.is_some() {
+                continue;
+            }
+            let name = record
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            entries.push(match record.node_type {
+                NodeType::Directory => format!("{}/", name),
+                NodeType::File { size, .. } => format!("{} ({} bytes)", name, size),
+            });
+        }
+        entries.sort();
+        if entries.is_empty() {
+            return Ok(format!("'{}' is empty.", self.display_path(node)));
+        }
+        Ok(entries.join("\n"))
+    }
+}
+
+/// Normalize `path` to a path below `root`, rejecting any that leave it
+fn workspace_relative(root: &Path, path: &Path) -> Result<PathBuf, String> {
+    let path = if path.is_absolute() {
+        path.strip_prefix(root)
+            .map_err(|_| format!("'{}' is outside the workspace", path.display()))?
+    } else {
+        path
+    };
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !relative.pop() {
+                    return Err(format!("'{}' is outside the workspace", path.display()));
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(format!("'{}' is outside the workspace", path.display()));
+            }
+        }
+    }
+    Ok(relative)
+}
+
+fn truncate_output(mut output: String) -> String {
+    if output.len() <= MAX_TOOL_RESULT_BYTES {
+        return output;
+    }
+    let total = output.len();
+    let mut end = MAX_TOOL_RESULT_BYTES;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    output.truncate(end);
+    output.push_str(&format!("\n[truncated: {} of {} bytes shown]", end, total));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_resolve_inside_the_workspace_only() {
+        let root = Path::new("/work/repo");
+        let resolve = |path: &str| workspace_relative(root, Path::new(path));
+        assert_eq!(resolve("src/lib.rs").unwrap(), PathBuf::from("src/lib.rs"));
+        assert_eq!(
+            resolve("./src/../README.md").unwrap(),
+            PathBuf::from("README.md")
+        );
+        assert_eq!(resolve("/work/repo/src").unwrap(), PathBuf::from("src"));
+        assert_eq!(resolve(".").unwrap(), PathBuf::new());
+        assert!(resolve("../other/secret").is_err());
+        assert!(resolve("src/../../etc/passwd").is_err());
+        assert!(resolve("/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn long_output_is_truncated_on_a_char_boundary() {
+        let output = "é".repeat(MAX_TOOL_RESULT_BYTES);
+        let truncated = truncate_output(output);
+        assert!(truncated.contains("[truncated:"));
+        assert!(truncated.len() < MAX_TOOL_RESULT_BYTES + 64);
+        assert_eq!(truncate_output("short".to_string()), "short");
+    }
+}
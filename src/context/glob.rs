@@ -0,0 +1,77 @@
+//! Glob matching shared by frame filters and routing rules.
+
+/// Match a value against a pattern where `*` matches any run of characters and `?`
+/// matches one character
+pub fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if let Some((star_p, star_v)) = backtrack {
+            p = star_p + 1;
+            v = star_v + 1;
+            backtrack = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Match a `/`-separated path against a glob: `**` spans any number of segments
+/// (including none), `*` and `?` stay within one segment
+pub fn glob_match_path(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern
+        .trim_start_matches("./")
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_segments(rest, &path[skip..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path_rest)) => glob_match(segment, name) && match_segments(rest, path_rest),
+            None => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("gpt-4*", "gpt-4"));
+        assert!(glob_match("gpt-4*", "gpt-4o-mini"));
+        assert!(glob_match("*mini", "gpt-4o-mini"));
+        assert!(glob_match("gpt-?o*", "gpt-4o-mini"));
+        assert!(!glob_match("gpt-4*", "gpt-3.5"));
+        assert!(!glob_match("llama", "llama3"));
+    }
+
+    #[test]
+    fn test_glob_match_path_across_and_within_segments() {
+        assert!(glob_match_path("**/*.sql", "schema.sql"));
+        assert!(glob_match_path("**/*.sql", "db/migrations/001_init.sql"));
+        assert!(!glob_match_path("**/*.sql", "db/schema.sql.bak"));
+        assert!(glob_match_path("docs/**", "docs"));
+        assert!(glob_match_path("docs/**", "docs/guide/intro.md"));
+        assert!(!glob_match_path("docs/**", "src/docs.rs"));
+        assert!(glob_match_path("src/*/mod.rs", "src/api/mod.rs"));
+        assert!(!glob_match_path("src/*/mod.rs", "src/api/v1/mod.rs"));
+        assert!(glob_match_path("./src/lib.r?", "src/lib.rs"));
+    }
+}
@@ -1,14 +1,21 @@
 //! Context query: view policy, composition, and query service.
 //! Single owner of context read behavior; api delegates to this module.
 
+pub mod compose;
 pub mod composition;
 pub mod get;
+pub mod relevance;
 pub mod service;
 pub mod view_policy;
 pub mod view;
 
+pub use compose::{compose_for_cli, ComposedContext, ComposedFrame};
 pub use composition::{compose_frames, CompositionPolicy, CompositionSource};
-pub use get::get_node_for_cli;
+pub use get::{get_node_for_cli, parse_where_clause};
+pub use relevance::RelevanceModel;
 pub use service::get_node as get_node_query;
-pub use view_policy::{FrameFilter, OrderingPolicy, ViewPolicy, get_context_view};
+pub use view_policy::{
+    frame_passes_filters, get_context_view, FrameFilter, MetadataPredicate, OrderingPolicy,
+    ViewPolicy,
+};
 pub use view::{ContextView, ContextViewBuilder, NodeContext};
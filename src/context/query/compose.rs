@@ -0,0 +1,215 @@
+//! Context compose entry point for CLI: resolve node, parse sources, compose frames,
+//! and attach the owning node path to each frame.
+
+use super::composition::{CompositionPolicy, CompositionSource};
+use super::get::{parse_ordering, resolve_target_node_id};
+use super::relevance::RelevanceModel;
+use super::view_policy::FrameFilter;
+use crate::api::ContextApi;
+use crate::context::frame::{Basis, Frame};
+use crate::error::ApiError;
+use crate::types::NodeID;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Default ancestor depth when `ancestors` is given without a depth (walk to the root)
+pub const DEFAULT_ANCESTOR_DEPTH: usize = usize::MAX;
+/// Default descendant depth when `descendants` is given without a depth
+pub const DEFAULT_DESCENDANT_DEPTH: usize = 1;
+/// Default descendant node cap when `descendants` is given without a cap
+pub const DEFAULT_DESCENDANT_MAX_NODES: usize = 100;
+
+/// A composed frame together with the node it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComposedFrame {
+    /// Node the frame is attached to
+    pub node_id: NodeID,
+    /// Node path, or None when the frame has no node basis or the node is unknown
+    pub path: Option<PathBuf>,
+    /// The frame itself
+    pub frame: Frame,
+}
+
+/// Result of a compose request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComposedContext {
+    /// Target node the composition was computed for
+    pub node_id: NodeID,
+    /// Target node path
+    pub path: PathBuf,
+    /// Composed frames in policy order
+    pub frames: Vec<ComposedFrame>,
+}
+
+/// Parse a comma separated source list.
+///
+/// Accepted entries: `current`, `parent`, `siblings`, `ancestors[:depth]`,
+/// `descendants[:depth[:max_nodes]]`, and `node:<hex>` for an explicit related node.
+pub fn parse_composition_sources(spec: &str) -> Result<Vec<CompositionSource>, ApiError> {
+    let mut sources = Vec::new();
+    let mut related = Vec::new();
+
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let mut parts = entry.split(':');
+        let name = parts.next().unwrap_or_default();
+        let args: Vec<&str> = parts.collect();
+        let source = match (name, args.as_slice()) {
+            ("current", []) => CompositionSource::CurrentNode,
+            ("parent", []) => CompositionSource::ParentDirectory,
+            ("siblings", []) => CompositionSource::Siblings,
+            ("ancestors", []) => CompositionSource::Ancestors {
+                max_depth: DEFAULT_ANCESTOR_DEPTH,
+            },
+            ("ancestors", [depth]) => CompositionSource::Ancestors {
+                max_depth: parse_bound(entry, depth)?,
+            },
+            ("descendants", []) => CompositionSource::Descendants {
+                max_depth: DEFAULT_DESCENDANT_DEPTH,
+                max_nodes: DEFAULT_DESCENDANT_MAX_NODES,
+            },
+            ("descendants", [depth]) => CompositionSource::Descendants {
+                max_depth: parse_bound(entry, depth)?,
+                max_nodes: DEFAULT_DESCENDANT_MAX_NODES,
+            },
+            ("descendants", [depth, max_nodes]) => CompositionSource::Descendants {
+                max_depth: parse_bound(entry, depth)?,
+                max_nodes: parse_bound(entry, max_nodes)?,
+            },
+            ("node", [hex_id]) => {
+                related.push(super::get::parse_node_id(hex_id)?);
+                continue;
+            }
+            _ => {
+                return Err(ApiError::ConfigError(format!(
+                    "Invalid source: '{}'. Expected current, parent, siblings, ancestors[:depth], descendants[:depth[:max_nodes]], or node:<id>.",
+                    entry
+                )));
+            }
+        };
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+
+    if !related.is_empty() {
+        sources.push(CompositionSource::RelatedNodes(related));
+    }
+    if sources.is_empty() {
+        return Err(ApiError::ConfigError(
+            "At least one composition source is required.".to_string(),
+        ));
+    }
+    Ok(sources)
+}
+
+fn parse_bound(entry: &str, value: &str) -> Result<usize, ApiError> {
+    value.parse::<usize>().map_err(|_| {
+        ApiError::ConfigError(format!(
+            "Invalid source: '{}'. '{}' is not a non-negative integer.",
+            entry, value
+        ))
+    })
+}
+
+fn frame_node_id(frame: &Frame) -> Option<NodeID> {
+    match &frame.basis {
+        Basis::Node(node_id) => Some(*node_id),
+        Basis::Both { node, .. } => Some(*node),
+        Basis::Frame(_) => None,
+    }
+}
+
+/// Single compose entry point: resolve node_id, build CompositionPolicy, call api.compose.
+#[allow(clippy::too_many_arguments)]
+pub fn compose_for_cli(
+    api: &ContextApi,
+    workspace_root: &std::path::PathBuf,
+    node: Option<&str>,
+    path: Option<&Path>,
+    sources: &str,
+    frame_type: Option<&str>,
+    max_frames: usize,
+    ordering: &str,
+    relevance: &RelevanceModel,
+) -> Result<ComposedContext, ApiError> {
+    let node_id = resolve_target_node_id(api, workspace_root, node, path)?;
+    let mut filters = Vec::new();
+    if let Some(ft) = frame_type {
+        filters.push(FrameFilter::ByType(ft.to_string()));
+    }
+    let policy = CompositionPolicy {
+        max_frames,
+        sources: parse_composition_sources(sources)?,
+        ordering: parse_ordering(ordering, relevance)?,
+        filters,
+    };
+
+    let target = api
+        .node_store()
+        .get(&node_id)
+        .map_err(ApiError::from)?
+        .ok_or(ApiError::NodeNotFound(node_id))?;
+    let frames = api
+        .compose(node_id, policy)?
+        .into_iter()
+        .map(|frame| {
+            let owner = frame_node_id(&frame);
+            let path = match owner {
+                Some(owner_id) => api
+                    .node_store()
+                    .get(&owner_id)
+                    .map_err(ApiError::from)?
+                    .map(|record| record.path),
+                None => None,
+            };
+            Ok(ComposedFrame {
+                node_id: owner.unwrap_or(node_id),
+                path,
+                frame,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    Ok(ComposedContext {
+        node_id,
+        path: target.path,
+        frames,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_composition_sources() {
+        let sources =
+            parse_composition_sources("current, ancestors, descendants:2:5, siblings").unwrap();
+        assert_eq!(
+            sources,
+            vec![
+                CompositionSource::CurrentNode,
+                CompositionSource::Ancestors {
+                    max_depth: DEFAULT_ANCESTOR_DEPTH
+                },
+                CompositionSource::Descendants {
+                    max_depth: 2,
+                    max_nodes: 5
+                },
+                CompositionSource::Siblings,
+            ]
+        );
+        assert_eq!(
+            parse_composition_sources("ancestors:3").unwrap(),
+            vec![CompositionSource::Ancestors { max_depth: 3 }]
+        );
+    }
+
+    #[test]
+    fn test_parse_composition_sources_rejects_invalid() {
+        assert!(parse_composition_sources("").is_err());
+        assert!(parse_composition_sources("cousins").is_err());
+        assert!(parse_composition_sources("ancestors:-1").is_err());
+        assert!(parse_composition_sources("current:1").is_err());
+    }
+}
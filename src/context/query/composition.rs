@@ -4,13 +4,15 @@
 //! Composition happens at read-time, is policy-driven, and produces bounded,
 //! deterministic results. No composite state is persisted—composition is computed on-demand.
 
-use super::view_policy::{FrameFilter, OrderingPolicy};
+use super::relevance::reference_time;
+use super::view_policy::{frame_passes_filters, FrameFilter, OrderingPolicy};
 use crate::context::frame::{Frame, FrameStorage};
 use crate::error::ApiError;
 use crate::heads::HeadIndex;
 use crate::store::NodeRecordStore;
 use crate::types::NodeID;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Composition source for multi-frame composition
 ///
@@ -25,6 +27,10 @@ pub enum CompositionSource {
     Siblings,
     /// Related node frames (explicit list)
     RelatedNodes(Vec<NodeID>),
+    /// Enclosing directory frames, nearest first, up to `max_depth` levels
+    Ancestors { max_depth: usize },
+    /// Descendant frames in breadth-first order, bounded by depth and node count
+    Descendants { max_depth: usize, max_nodes: usize },
 }
 
 /// Composition policy for multi-frame composition
@@ -53,55 +59,45 @@ impl Default for CompositionPolicy {
     }
 }
 
-/// Frame scoring for relevance-based ordering
+/// Tree distance between two nodes
 ///
-/// Computes a relevance score for a frame. Higher scores indicate higher relevance.
-/// The scoring algorithm is deterministic.
-#[allow(dead_code)]
-fn compute_relevance_score(
-    frame: &Frame,
-    target_node_id: NodeID,
-    _context: &CompositionContext,
-) -> i64 {
-    // Simple relevance scoring algorithm (deterministic)
-    // Higher score = more relevant
-
-    let mut score = 0i64;
-
-    // Boost score if frame is directly associated with target node
-    match &frame.basis {
-        crate::context::frame::Basis::Node(node_id) => {
-            if *node_id == target_node_id {
-                score += 1000;
-            }
-        }
-        crate::context::frame::Basis::Frame(_) => {
-            // Frame-based basis gets medium score
-            score += 500;
-        }
-        crate::context::frame::Basis::Both { node, .. } => {
-            if *node == target_node_id {
-                score += 1000;
-            }
-        }
+/// Counts the edges on the path through their closest common ancestor. Nodes in disjoint
+/// trees (or with missing records) are treated as one step beyond the longer chain.
+fn tree_distance(
+    node_store: &dyn NodeRecordStore,
+    from: NodeID,
+    to: NodeID,
+) -> Result<usize, ApiError> {
+    if from == to {
+        return Ok(0);
     }
-
-    // Boost score based on frame type (some types are more relevant)
-    match frame.frame_type.as_str() {
-        "analysis" | "summary" => score += 100,
-        "documentation" => score += 50,
-        _ => {}
+    let from_chain = ancestor_chain(node_store, from)?;
+    let to_chain = ancestor_chain(node_store, to)?;
+    for (from_depth, ancestor) in from_chain.iter().enumerate() {
+        if let Some(to_depth) = to_chain.iter().position(|id| id == ancestor) {
+            return Ok(from_depth + to_depth);
+        }
     }
+    Ok(from_chain.len() + to_chain.len())
+}
 
-    // Boost score based on recency (more recent = higher score)
-    // Use timestamp as a component (newer timestamps are larger)
-    // We'll use a simple approach: score += timestamp seconds since epoch / 1000
-    // This gives a small boost to newer frames
-    if let Ok(duration) = frame.timestamp.duration_since(std::time::UNIX_EPOCH) {
-        score += (duration.as_secs() / 1000) as i64;
+/// Node followed by its ancestors up to the root
+fn ancestor_chain(
+    node_store: &dyn NodeRecordStore,
+    node_id: NodeID,
+) -> Result<Vec<NodeID>, ApiError> {
+    let mut chain = vec![node_id];
+    let mut current = node_id;
+    while let Some(record) = node_store.get(&current).map_err(ApiError::from)? {
+        match record.parent {
+            Some(parent_id) if !chain.contains(&parent_id) => {
+                chain.push(parent_id);
+                current = parent_id;
+            }
+            _ => break,
+        }
     }
-
-    score
+    Ok(chain)
 }
 
 /// Context for composition operations
@@ -109,8 +105,6 @@ fn compute_relevance_score(
 /// Contains information needed for composition decisions.
 #[derive(Debug, Clone)]
 struct CompositionContext {
-    #[allow(dead_code)]
-    target_node_id: NodeID,
     parent_node_id: Option<NodeID>,
     sibling_node_ids: Vec<NodeID>,
 }
@@ -122,7 +116,7 @@ fn collect_frames_from_source(
     source: &CompositionSource,
     target_node_id: NodeID,
     context: &CompositionContext,
-    _node_store: &dyn NodeRecordStore,
+    node_store: &dyn NodeRecordStore,
     frame_storage: &FrameStorage,
     head_index: &HeadIndex,
 ) -> Result<Vec<(NodeID, Frame)>, ApiError> {
@@ -168,11 +162,87 @@ fn collect_frames_from_source(
                 }
             }
         }
+        CompositionSource::Ancestors { max_depth } => {
+            let mut current = context.parent_node_id;
+            let mut depth = 0;
+            while let Some(ancestor_id) = current {
+                if depth >= *max_depth {
+                    break;
+                }
+                let record = node_store.get(&ancestor_id).map_err(ApiError::from)?;
+                // Tombstoned ancestors still count toward depth but contribute no frames
+                if record.as_ref().is_some_and(|r| r.tombstoned_at.is_none()) {
+                    let frame_ids = head_index.get_all_heads_for_node(&ancestor_id);
+                    for frame_id in frame_ids {
+                        if let Some(frame) = frame_storage.get(&frame_id).map_err(ApiError::from)? {
+                            frames.push((ancestor_id, frame));
+                        }
+                    }
+                }
+                depth += 1;
+                current = record.and_then(|record| record.parent);
+            }
+        }
+        CompositionSource::Descendants {
+            max_depth,
+            max_nodes,
+        } => {
+            let descendant_ids =
+                collect_descendants(node_store, target_node_id, *max_depth, *max_nodes)?;
+            for descendant_id in descendant_ids {
+                let frame_ids = head_index.get_all_heads_for_node(&descendant_id);
+                for frame_id in frame_ids {
+                    if let Some(frame) = frame_storage.get(&frame_id).map_err(ApiError::from)? {
+                        frames.push((descendant_id, frame));
+                    }
+                }
+            }
+        }
     }
 
     Ok(frames)
 }
 
+/// Collect active descendants breadth-first
+///
+/// Children are visited in record order, so the result is deterministic for a given tree.
+/// Tombstoned nodes and their subtrees are skipped.
+fn collect_descendants(
+    node_store: &dyn NodeRecordStore,
+    root_id: NodeID,
+    max_depth: usize,
+    max_nodes: usize,
+) -> Result<Vec<NodeID>, ApiError> {
+    let mut descendants = Vec::new();
+    let mut queue = std::collections::VecDeque::new();
+    queue.push_back((root_id, 0usize));
+
+    while let Some((node_id, depth)) = queue.pop_front() {
+        if depth >= max_depth {
+            continue;
+        }
+        let Some(record) = node_store.get(&node_id).map_err(ApiError::from)? else {
+            continue;
+        };
+        for child_id in record.children {
+            if descendants.len() >= max_nodes {
+                return Ok(descendants);
+            }
+            let active = node_store
+                .get(&child_id)
+                .map_err(ApiError::from)?
+                .map(|child| child.tombstoned_at.is_none())
+                .unwrap_or(false);
+            if active {
+                descendants.push(child_id);
+                queue.push_back((child_id, depth + 1));
+            }
+        }
+    }
+
+    Ok(descendants)
+}
+
 /// Compose frames from multiple sources
 ///
 /// Collects frames from multiple sources, applies filters, orders them according to policy,
@@ -226,7 +296,6 @@ pub fn compose_frames(
     };
 
     let context = CompositionContext {
-        target_node_id,
         parent_node_id,
         sibling_node_ids,
     };
@@ -259,65 +328,77 @@ pub fn compose_frames(
     // Step 2: Apply filters
     let filtered_frames: Vec<(NodeID, Frame)> = candidate_frames
         .into_iter()
-        .filter(|(_, frame)| {
-            policy.filters.iter().all(|filter| match filter {
-                FrameFilter::ByType(filter_type) => frame.frame_type == *filter_type,
-                FrameFilter::ByAgent(filter_agent) => frame.agent_id() == Some(filter_agent.as_str()),
-            })
-        })
+        .filter(|(_, frame)| frame_passes_filters(frame, &policy.filters))
         .collect();
 
     // Step 3: Score and order frames (policy-driven)
-    let mut scored_frames: Vec<(i64, NodeID, Frame)> = filtered_frames
-        .into_iter()
-        .map(|(node_id, frame)| {
-            let score = match policy.ordering {
-                OrderingPolicy::Recency => {
-                    // Use timestamp as score (newer = higher)
-                    frame
-                        .timestamp
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .map(|d| d.as_secs() as i64)
-                        .unwrap_or(0)
-                }
-                OrderingPolicy::Type => {
-                    // Use frame type hash as score (deterministic)
-                    use std::collections::hash_map::DefaultHasher;
-                    use std::hash::{Hash, Hasher};
-                    let mut hasher = DefaultHasher::new();
-                    frame.frame_type.hash(&mut hasher);
-                    hasher.finish() as i64
-                }
-                OrderingPolicy::Agent => {
-                    // Use agent ID hash as score (deterministic)
-                    use std::collections::hash_map::DefaultHasher;
-                    use std::hash::{Hash, Hasher};
-                    let mut hasher = DefaultHasher::new();
-                    frame.agent_id().unwrap_or("").hash(&mut hasher);
-                    hasher.finish() as i64
-                }
-            };
-            (score, node_id, frame)
-        })
-        .collect();
+    let mut scored_frames: Vec<(i64, NodeID, Frame)> = match &policy.ordering {
+        OrderingPolicy::Relevance(model) => {
+            let reference = reference_time(filtered_frames.iter().map(|(_, f)| f));
+            let mut distances: HashMap<NodeID, usize> = HashMap::new();
+            let mut scored = Vec::with_capacity(filtered_frames.len());
+            for (node_id, frame) in filtered_frames {
+                let distance = match distances.get(&node_id) {
+                    Some(distance) => *distance,
+                    None => {
+                        let distance = if node_id == target_node_id {
+                            0
+                        } else if Some(node_id) == context.parent_node_id {
+                            1
+                        } else if context.sibling_node_ids.contains(&node_id) {
+                            2
+                        } else {
+                            tree_distance(node_store, target_node_id, node_id)?
+                        };
+                        distances.insert(node_id, distance);
+                        distance
+                    }
+                };
+                scored.push((model.score(&frame, distance, reference), node_id, frame));
+            }
+            scored
+        }
+        OrderingPolicy::Recency => filtered_frames
+            .into_iter()
+            .map(|(node_id, frame)| {
+                // Use timestamp as score (newer = higher)
+                let score = frame
+                    .timestamp
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0);
+                (score, node_id, frame)
+            })
+            .collect(),
+        OrderingPolicy::Type | OrderingPolicy::Agent => filtered_frames
+            .into_iter()
+            .map(|(node_id, frame)| (0, node_id, frame))
+            .collect(),
+    };
 
-    // Sort by score (descending for Recency, ascending for Type/Agent to maintain lexicographic order)
-    match policy.ordering {
+    // Sort by score (descending for Recency and Relevance, lexicographic for Type/Agent)
+    match &policy.ordering {
         OrderingPolicy::Recency => {
             scored_frames.sort_by(|(score_a, _, _), (score_b, _, _)| score_b.cmp(score_a));
         }
-        OrderingPolicy::Type | OrderingPolicy::Agent => {
-            // For Type and Agent, we want lexicographic order, so we sort by the actual values
-            // after sorting by score. But since we're using hash, we'll just sort by score.
-            // Actually, let's sort by the actual values for Type and Agent
-            scored_frames.sort_by(|(_, _, frame_a), (_, _, frame_b)| match policy.ordering {
-                OrderingPolicy::Type => frame_a.frame_type.cmp(&frame_b.frame_type),
-                OrderingPolicy::Agent => {
-                    let agent_a = frame_a.agent_id().unwrap_or("");
-                    let agent_b = frame_b.agent_id().unwrap_or("");
-                    agent_a.cmp(agent_b)
-                }
-                _ => unreachable!(),
+        OrderingPolicy::Relevance(_) => {
+            // Ties broken by FrameID so equal scores still order deterministically
+            scored_frames.sort_by(|(score_a, _, frame_a), (score_b, _, frame_b)| {
+                score_b
+                    .cmp(score_a)
+                    .then_with(|| frame_a.frame_id.cmp(&frame_b.frame_id))
+            });
+        }
+        OrderingPolicy::Type => {
+            scored_frames.sort_by(|(_, _, frame_a), (_, _, frame_b)| {
+                frame_a.frame_type.cmp(&frame_b.frame_type)
+            });
+        }
+        OrderingPolicy::Agent => {
+            scored_frames.sort_by(|(_, _, frame_a), (_, _, frame_b)| {
+                let agent_a = frame_a.agent_id().unwrap_or("");
+                let agent_b = frame_b.agent_id().unwrap_or("");
+                agent_a.cmp(agent_b)
             });
         }
     }
@@ -337,10 +418,10 @@ mod tests {
     use super::*;
     use crate::context::frame::storage::FrameStorage;
     use crate::context::frame::{Basis, Frame};
+    use crate::context::query::relevance::RelevanceModel;
     use crate::heads::HeadIndex;
     use crate::store::{NodeRecord, NodeRecordStore, NodeType, SledNodeRecordStore};
     use crate::types::FrameID;
-    use std::collections::HashMap;
     use std::path::PathBuf;
     use std::sync::Arc;
     use tempfile::TempDir;
@@ -656,4 +737,207 @@ mod tests {
         assert!(frame_ids.contains(&child_frame.frame_id));
         assert!(frame_ids.contains(&parent_frame.frame_id));
     }
+
+    #[test]
+    fn test_compose_relevance_prefers_closer_nodes() {
+        let temp_dir = TempDir::new().unwrap();
+        let store_path = temp_dir.path().join("store");
+        let frame_storage_path = temp_dir.path().join("frames");
+
+        let node_store = Arc::new(SledNodeRecordStore::new(&store_path).unwrap());
+        let frame_storage = Arc::new(FrameStorage::new(&frame_storage_path).unwrap());
+        let mut head_index = HeadIndex::new();
+
+        let parent_id: NodeID = [1u8; 32];
+        let child_id: NodeID = [2u8; 32];
+        let sibling_id: NodeID = [3u8; 32];
+
+        node_store
+            .put(&create_test_node_record(
+                parent_id,
+                None,
+                vec![child_id, sibling_id],
+            ))
+            .unwrap();
+        node_store
+            .put(&create_test_node_record(child_id, Some(parent_id), vec![]))
+            .unwrap();
+        node_store
+            .put(&create_test_node_record(
+                sibling_id,
+                Some(parent_id),
+                vec![],
+            ))
+            .unwrap();
+
+        let mut expected = Vec::new();
+        for node_id in [child_id, parent_id, sibling_id] {
+            let frame = Frame::new(
+                Basis::Node(node_id),
+                b"content".to_vec(),
+                "test".to_string(),
+                "agent1".to_string(),
+                HashMap::new(),
+            )
+            .unwrap();
+            frame_storage.store(&frame).unwrap();
+            head_index
+                .update_head(&node_id, "test", &frame.frame_id)
+                .unwrap();
+            expected.push(frame.frame_id);
+        }
+
+        let policy = CompositionPolicy {
+            max_frames: 10,
+            sources: vec![
+                CompositionSource::Siblings,
+                CompositionSource::ParentDirectory,
+                CompositionSource::CurrentNode,
+            ],
+            ordering: OrderingPolicy::Relevance(RelevanceModel::default()),
+            filters: vec![],
+        };
+
+        let composed = compose_frames(
+            child_id,
+            &policy,
+            node_store.as_ref(),
+            &frame_storage,
+            &head_index,
+        )
+        .unwrap();
+
+        let frame_ids: Vec<FrameID> = composed.iter().map(|f| f.frame_id).collect();
+        assert_eq!(frame_ids, expected);
+    }
+
+    #[test]
+    fn test_tree_distance() {
+        let temp_dir = TempDir::new().unwrap();
+        let node_store = SledNodeRecordStore::new(temp_dir.path()).unwrap();
+
+        let root: NodeID = [1u8; 32];
+        let dir: NodeID = [2u8; 32];
+        let file: NodeID = [3u8; 32];
+        let other: NodeID = [4u8; 32];
+        node_store
+            .put(&create_test_node_record(root, None, vec![dir, other]))
+            .unwrap();
+        node_store
+            .put(&create_test_node_record(dir, Some(root), vec![file]))
+            .unwrap();
+        node_store
+            .put(&create_test_node_record(file, Some(dir), vec![]))
+            .unwrap();
+        node_store
+            .put(&create_test_node_record(other, Some(root), vec![]))
+            .unwrap();
+
+        assert_eq!(tree_distance(&node_store, file, file).unwrap(), 0);
+        assert_eq!(tree_distance(&node_store, file, root).unwrap(), 2);
+        assert_eq!(tree_distance(&node_store, file, other).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_compose_ancestors_and_descendants() {
+        let temp_dir = TempDir::new().unwrap();
+        let store_path = temp_dir.path().join("store");
+        let frame_storage_path = temp_dir.path().join("frames");
+
+        let node_store = Arc::new(SledNodeRecordStore::new(&store_path).unwrap());
+        let frame_storage = Arc::new(FrameStorage::new(&frame_storage_path).unwrap());
+        let mut head_index = HeadIndex::new();
+
+        // root -> dir -> mid -> {leaf_a, leaf_b}
+        let root: NodeID = [1u8; 32];
+        let dir: NodeID = [2u8; 32];
+        let mid: NodeID = [3u8; 32];
+        let leaf_a: NodeID = [4u8; 32];
+        let leaf_b: NodeID = [5u8; 32];
+        node_store
+            .put(&create_test_node_record(root, None, vec![dir]))
+            .unwrap();
+        node_store
+            .put(&create_test_node_record(dir, Some(root), vec![mid]))
+            .unwrap();
+        node_store
+            .put(&create_test_node_record(
+                mid,
+                Some(dir),
+                vec![leaf_a, leaf_b],
+            ))
+            .unwrap();
+        node_store
+            .put(&create_test_node_record(leaf_a, Some(mid), vec![]))
+            .unwrap();
+        node_store
+            .put(&create_test_node_record(leaf_b, Some(mid), vec![]))
+            .unwrap();
+
+        let mut frame_ids = HashMap::new();
+        for node_id in [root, dir, mid, leaf_a, leaf_b] {
+            let frame = Frame::new(
+                Basis::Node(node_id),
+                format!("content {}", node_id[0]).into_bytes(),
+                "test".to_string(),
+                "agent1".to_string(),
+                HashMap::new(),
+            )
+            .unwrap();
+            frame_storage.store(&frame).unwrap();
+            head_index
+                .update_head(&node_id, "test", &frame.frame_id)
+                .unwrap();
+            frame_ids.insert(node_id, frame.frame_id);
+        }
+
+        let compose = |sources: Vec<CompositionSource>| -> Vec<FrameID> {
+            let policy = CompositionPolicy {
+                max_frames: 10,
+                sources,
+                ordering: OrderingPolicy::Relevance(RelevanceModel::default()),
+                filters: vec![],
+            };
+            compose_frames(
+                mid,
+                &policy,
+                node_store.as_ref(),
+                &frame_storage,
+                &head_index,
+            )
+            .unwrap()
+            .iter()
+            .map(|f| f.frame_id)
+            .collect()
+        };
+
+        let ancestors = compose(vec![CompositionSource::Ancestors { max_depth: 10 }]);
+        assert_eq!(ancestors, vec![frame_ids[&dir], frame_ids[&root]]);
+
+        let nearest = compose(vec![CompositionSource::Ancestors { max_depth: 1 }]);
+        assert_eq!(nearest, vec![frame_ids[&dir]]);
+
+        let descendants = compose(vec![CompositionSource::Descendants {
+            max_depth: 1,
+            max_nodes: 1,
+        }]);
+        assert_eq!(descendants, vec![frame_ids[&leaf_a]]);
+
+        let zoomed = compose(vec![
+            CompositionSource::Ancestors { max_depth: 10 },
+            CompositionSource::CurrentNode,
+            CompositionSource::Descendants {
+                max_depth: 2,
+                max_nodes: 10,
+            },
+        ]);
+        assert_eq!(zoomed.len(), 5);
+        assert_eq!(zoomed[0], frame_ids[&mid]);
+
+        let mut tombstoned = create_test_node_record(dir, Some(root), vec![mid]);
+        tombstoned.tombstoned_at = Some(1);
+        node_store.put(&tombstoned).unwrap();
+        let ancestors = compose(vec![CompositionSource::Ancestors { max_depth: 10 }]);
+        assert_eq!(ancestors, vec![frame_ids[&root]]);
+    }
 }
@@ -1,13 +1,15 @@
 //! Context get entry point for CLI: resolve node, build view, return NodeContext.
 
 use crate::api::{ContextApi, ContextView, NodeContext};
+use crate::context::query::relevance::RelevanceModel;
 use crate::error::ApiError;
 use crate::types::NodeID;
-use crate::views::OrderingPolicy;
+use crate::views::{FrameFilter, MetadataPredicate, OrderingPolicy};
 use crate::workspace;
 use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-fn parse_node_id(s: &str) -> Result<NodeID, ApiError> {
+pub(crate) fn parse_node_id(s: &str) -> Result<NodeID, ApiError> {
     let s = s.strip_prefix("0x").unwrap_or(s);
     let bytes = hex::decode(s)
         .map_err(|e| ApiError::InvalidFrame(format!("Invalid hex string: {}", e)))?;
@@ -22,7 +24,122 @@ fn parse_node_id(s: &str) -> Result<NodeID, ApiError> {
     Ok(crate::types::Hash::from(hash))
 }
 
+/// Resolve the CLI target from exactly one of `--node` or `--path`.
+pub(crate) fn resolve_target_node_id(
+    api: &ContextApi,
+    workspace_root: &std::path::PathBuf,
+    node: Option<&str>,
+    path: Option<&Path>,
+) -> Result<NodeID, ApiError> {
+    match (node, path) {
+        (Some(node_str), None) => parse_node_id(node_str),
+        (None, Some(p)) => {
+            workspace::resolve_workspace_node_id(api, workspace_root, Some(p), None, false)
+        }
+        (Some(_), Some(_)) => Err(ApiError::ConfigError(
+            "Cannot specify both --node and --path. Use one or the other.".to_string(),
+        )),
+        (None, None) => Err(ApiError::ConfigError(
+            "Must specify either --node <node_id> or --path <path>.".to_string(),
+        )),
+    }
+}
+
+/// Parse the CLI `--ordering` value; relevance uses the configured scoring model.
+pub(crate) fn parse_ordering(
+    ordering: &str,
+    relevance: &RelevanceModel,
+) -> Result<OrderingPolicy, ApiError> {
+    match ordering {
+        "recency" => Ok(OrderingPolicy::Recency),
+        "deterministic" => Ok(OrderingPolicy::Type),
+        "relevance" => Ok(OrderingPolicy::Relevance(relevance.clone())),
+        _ => Err(ApiError::ConfigError(format!(
+            "Invalid ordering: '{}'. Must be 'recency', 'deterministic', or 'relevance'.",
+            ordering
+        ))),
+    }
+}
+
+/// Parse a CLI `--where` clause into a frame filter.
+///
+/// A clause is one or more terms joined by `|` (any may match); a term prefixed with `!` is
+/// negated. Terms are `key=value`, `key!=value`, or `key~=glob` over visible metadata keys.
+/// The keys `since` and `until` take an RFC 3339 timestamp or Unix seconds, and `frame_type`
+/// and `agent_id` map to the type and agent filters.
+pub fn parse_where_clause(clause: &str) -> Result<FrameFilter, ApiError> {
+    let mut alternatives = clause
+        .split('|')
+        .map(|term| parse_where_term(term.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if alternatives.len() == 1 {
+        Ok(alternatives.remove(0))
+    } else {
+        Ok(FrameFilter::Or(alternatives))
+    }
+}
+
+fn parse_where_term(term: &str) -> Result<FrameFilter, ApiError> {
+    if let Some(inner) = term.strip_prefix('!') {
+        return Ok(FrameFilter::Not(Box::new(parse_where_term(inner.trim())?)));
+    }
+    let invalid = || {
+        ApiError::ConfigError(format!(
+            "Invalid --where term: '{}'. Expected key=value, key!=value, or key~=pattern.",
+            term
+        ))
+    };
+    let eq = term.find('=').ok_or_else(invalid)?;
+    let (key, op) = match term[..eq].chars().last() {
+        Some('~') => (&term[..eq - 1], "~="),
+        Some('!') => (&term[..eq - 1], "!="),
+        _ => (&term[..eq], "="),
+    };
+    let key = key.trim();
+    let value = term[eq + 1..].trim();
+    if key.is_empty() || value.is_empty() {
+        return Err(invalid());
+    }
+
+    let filter = match (key, op) {
+        ("since", "=") => FrameFilter::Since(parse_timestamp(value)?),
+        ("until", "=") => FrameFilter::Until(parse_timestamp(value)?),
+        ("since" | "until", _) => return Err(invalid()),
+        ("frame_type", "~=") | ("agent_id", "~=") => return Err(invalid()),
+        ("frame_type", _) => FrameFilter::ByType(value.to_string()),
+        ("agent_id", _) => FrameFilter::ByAgent(value.to_string()),
+        (_, "~=") => FrameFilter::Metadata {
+            key: key.to_string(),
+            predicate: MetadataPredicate::Glob(value.to_string()),
+        },
+        _ => FrameFilter::Metadata {
+            key: key.to_string(),
+            predicate: MetadataPredicate::Equals(value.to_string()),
+        },
+    };
+    if op == "!=" {
+        Ok(FrameFilter::Not(Box::new(filter)))
+    } else {
+        Ok(filter)
+    }
+}
+
+fn parse_timestamp(value: &str) -> Result<SystemTime, ApiError> {
+    if let Ok(secs) = value.parse::<u64>() {
+        return Ok(UNIX_EPOCH + Duration::from_secs(secs));
+    }
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(SystemTime::from)
+        .map_err(|e| {
+            ApiError::ConfigError(format!(
+                "Invalid timestamp '{}': {}. Use RFC 3339 or Unix seconds.",
+                value, e
+            ))
+        })
+}
+
 /// Single get entry point: resolve node_id, build ContextView, call api.get_node.
+#[allow(clippy::too_many_arguments)]
 pub fn get_node_for_cli(
     api: &ContextApi,
     workspace_root: &std::path::PathBuf,
@@ -32,44 +149,18 @@ pub fn get_node_for_cli(
     frame_type: Option<&str>,
     max_frames: usize,
     ordering: &str,
-    _include_deleted: bool,
+    relevance: &RelevanceModel,
+    where_clauses: &[String],
+    include_deleted: bool,
 ) -> Result<NodeContext, ApiError> {
-    let node_id = match (node, path) {
-        (Some(node_str), None) => parse_node_id(node_str)?,
-        (None, Some(p)) => workspace::resolve_workspace_node_id(
-            api,
-            workspace_root,
-            Some(p),
-            None,
-            false,
-        )?,
-        (Some(_), Some(_)) => {
-            return Err(ApiError::ConfigError(
-                "Cannot specify both --node and --path. Use one or the other.".to_string(),
-            ));
-        }
-        (None, None) => {
-            return Err(ApiError::ConfigError(
-                "Must specify either --node <node_id> or --path <path>.".to_string(),
-            ));
-        }
-    };
-
-    let ordering_policy = match ordering {
-        "recency" => OrderingPolicy::Recency,
-        "deterministic" => OrderingPolicy::Type,
-        _ => {
-            return Err(ApiError::ConfigError(format!(
-                "Invalid ordering: '{}'. Must be 'recency' or 'deterministic'.",
-                ordering
-            )));
-        }
-    };
+    let node_id = resolve_target_node_id(api, workspace_root, node, path)?;
+    let ordering_policy = parse_ordering(ordering, relevance)?;
 
     let mut builder = ContextView::builder().max_frames(max_frames);
     match ordering_policy {
         OrderingPolicy::Recency => builder = builder.recent(),
         OrderingPolicy::Type => builder = builder.by_type_ordering(),
+        OrderingPolicy::Relevance(model) => builder = builder.by_relevance(model),
         _ => builder = builder.recent(),
     }
     if let Some(agent_id) = agent {
@@ -78,6 +169,78 @@ pub fn get_node_for_cli(
     if let Some(ft) = frame_type {
         builder = builder.by_type(ft);
     }
+    for clause in where_clauses {
+        builder = builder.filter(parse_where_clause(clause)?);
+    }
+    if include_deleted {
+        builder = builder.include_deleted();
+    }
     let view = builder.build();
     api.get_node(node_id, view)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(key: &str, predicate: MetadataPredicate) -> FrameFilter {
+        FrameFilter::Metadata {
+            key: key.to_string(),
+            predicate,
+        }
+    }
+
+    #[test]
+    fn test_parse_where_clause() {
+        assert_eq!(
+            parse_where_clause("provider=ollama").unwrap(),
+            metadata("provider", MetadataPredicate::Equals("ollama".to_string()))
+        );
+        assert_eq!(
+            parse_where_clause("model~=gpt-4*").unwrap(),
+            metadata("model", MetadataPredicate::Glob("gpt-4*".to_string()))
+        );
+        assert_eq!(
+            parse_where_clause("provider!=ollama").unwrap(),
+            FrameFilter::Not(Box::new(metadata(
+                "provider",
+                MetadataPredicate::Equals("ollama".to_string())
+            )))
+        );
+        assert_eq!(
+            parse_where_clause("provider=ollama | !model~=llama*").unwrap(),
+            FrameFilter::Or(vec![
+                metadata("provider", MetadataPredicate::Equals("ollama".to_string())),
+                FrameFilter::Not(Box::new(metadata(
+                    "model",
+                    MetadataPredicate::Glob("llama*".to_string())
+                ))),
+            ])
+        );
+        assert_eq!(
+            parse_where_clause("agent_id=writer").unwrap(),
+            FrameFilter::ByAgent("writer".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_where_timestamps() {
+        assert_eq!(
+            parse_where_clause("since=60").unwrap(),
+            FrameFilter::Since(UNIX_EPOCH + Duration::from_secs(60))
+        );
+        assert_eq!(
+            parse_where_clause("until=1970-01-01T00:01:00Z").unwrap(),
+            FrameFilter::Until(UNIX_EPOCH + Duration::from_secs(60))
+        );
+        assert!(parse_where_clause("since=yesterday").is_err());
+        assert!(parse_where_clause("since~=1").is_err());
+    }
+
+    #[test]
+    fn test_parse_where_rejects_malformed() {
+        assert!(parse_where_clause("provider").is_err());
+        assert!(parse_where_clause("=ollama").is_err());
+        assert!(parse_where_clause("provider=").is_err());
+    }
+}
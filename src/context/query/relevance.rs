@@ -0,0 +1,170 @@
+//! Relevance scoring model for frame ordering.
+//! Combines tree proximity, frame type weights, recency decay, and agent preference
+//! into a single deterministic integer score.
+
+use crate::context::frame::Frame;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+fn default_proximity_weight() -> i64 {
+    1000
+}
+
+fn default_frame_type_weights() -> BTreeMap<String, i64> {
+    let mut weights = BTreeMap::new();
+    weights.insert("analysis".to_string(), 100);
+    weights.insert("summary".to_string(), 100);
+    weights.insert("documentation".to_string(), 50);
+    weights
+}
+
+fn default_recency_weight() -> i64 {
+    200
+}
+
+fn default_recency_half_life_secs() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+fn default_agent_weight() -> i64 {
+    300
+}
+
+/// Configurable relevance scoring model
+///
+/// Loaded from the `[context.relevance]` config section. All weights are integers so the
+/// model stays comparable and hashable; higher scores indicate higher relevance.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RelevanceModel {
+    /// Weight for tree proximity; divided by `1 + distance` from the target node
+    #[serde(default = "default_proximity_weight")]
+    pub proximity_weight: i64,
+    /// Per frame type weights; types not listed score zero
+    #[serde(default = "default_frame_type_weights")]
+    pub frame_type_weights: BTreeMap<String, i64>,
+    /// Weight for the newest candidate frame; older frames decay toward zero
+    #[serde(default = "default_recency_weight")]
+    pub recency_weight: i64,
+    /// Age at which the recency contribution halves
+    #[serde(default = "default_recency_half_life_secs")]
+    pub recency_half_life_secs: u64,
+    /// Preferred agents, most preferred first
+    #[serde(default)]
+    pub preferred_agents: Vec<String>,
+    /// Weight for the most preferred agent; later entries scale down linearly
+    #[serde(default = "default_agent_weight")]
+    pub agent_weight: i64,
+}
+
+impl Default for RelevanceModel {
+    fn default() -> Self {
+        Self {
+            proximity_weight: default_proximity_weight(),
+            frame_type_weights: default_frame_type_weights(),
+            recency_weight: default_recency_weight(),
+            recency_half_life_secs: default_recency_half_life_secs(),
+            preferred_agents: Vec::new(),
+            agent_weight: default_agent_weight(),
+        }
+    }
+}
+
+impl RelevanceModel {
+    /// Score a frame
+    ///
+    /// `distance` is the number of tree edges between the frame's node and the target node.
+    /// Recency is measured against `reference_time` (the newest candidate timestamp) rather than
+    /// the wall clock so the same inputs always produce the same score.
+    pub fn score(&self, frame: &Frame, distance: usize, reference_time: SystemTime) -> i64 {
+        let mut score = self.proximity_weight / (1 + distance as i64);
+
+        score += self
+            .frame_type_weights
+            .get(&frame.frame_type)
+            .copied()
+            .unwrap_or(0);
+
+        if self.recency_weight != 0 {
+            let age_secs = reference_time
+                .duration_since(frame.timestamp)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let half_life = self.recency_half_life_secs.max(1) as f64;
+            let decay = 0.5f64.powf(age_secs as f64 / half_life);
+            score += (self.recency_weight as f64 * decay).round() as i64;
+        }
+
+        if let Some(agent_id) = frame.agent_id() {
+            if let Some(rank) = self.preferred_agents.iter().position(|a| a == agent_id) {
+                let n = self.preferred_agents.len() as i64;
+                score += self.agent_weight * (n - rank as i64) / n;
+            }
+        }
+
+        score
+    }
+}
+
+/// Newest timestamp among frames, used as the recency reference point
+pub fn reference_time<'a>(frames: impl IntoIterator<Item = &'a Frame>) -> SystemTime {
+    frames
+        .into_iter()
+        .map(|f| f.timestamp)
+        .max()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::frame::Basis;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn frame(frame_type: &str, agent_id: &str) -> Frame {
+        Frame::new(
+            Basis::Node([1u8; 32]),
+            b"content".to_vec(),
+            frame_type.to_string(),
+            agent_id.to_string(),
+            HashMap::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_closer_nodes_score_higher() {
+        let model = RelevanceModel::default();
+        let f = frame("other", "agent");
+        let now = f.timestamp;
+        assert!(model.score(&f, 0, now) > model.score(&f, 1, now));
+        assert!(model.score(&f, 1, now) > model.score(&f, 3, now));
+    }
+
+    #[test]
+    fn test_recency_decays_with_age() {
+        let model = RelevanceModel::default();
+        let mut old = frame("other", "agent");
+        let new = frame("other", "agent");
+        old.timestamp = new.timestamp - Duration::from_secs(model.recency_half_life_secs);
+        let reference = reference_time([&old, &new]);
+        let diff = model.score(&new, 0, reference) - model.score(&old, 0, reference);
+        assert_eq!(diff, model.recency_weight / 2);
+    }
+
+    #[test]
+    fn test_type_weights_and_agent_preference() {
+        let model = RelevanceModel {
+            preferred_agents: vec!["first".to_string(), "second".to_string()],
+            ..RelevanceModel::default()
+        };
+        let a = frame("analysis", "first");
+        let b = frame("other", "second");
+        let c = frame("other", "nobody");
+        let now = a.timestamp.max(b.timestamp).max(c.timestamp);
+        let base = model.score(&c, 0, now);
+        assert_eq!(model.score(&b, 0, now) - base, model.agent_weight / 2);
+        assert_eq!(model.score(&a, 0, now) - base, model.agent_weight + 100);
+    }
+}
@@ -1,11 +1,13 @@
 //! Public view types for context query: ContextView, ContextViewBuilder, NodeContext.
 //! Owned by context domain; api re-exports for compatibility.
 
-use super::view_policy::{FrameFilter, OrderingPolicy, ViewPolicy};
+use super::relevance::RelevanceModel;
+use super::view_policy::{FrameFilter, MetadataPredicate, OrderingPolicy, ViewPolicy};
 use crate::context::frame::Frame;
 use crate::store::NodeRecord;
 use crate::types::NodeID;
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
 
 /// Context view policy for frame selection
 ///
@@ -98,6 +100,12 @@ impl ContextViewBuilder {
         self
     }
 
+    /// Order by relevance score using the given scoring model
+    pub fn by_relevance(mut self, model: RelevanceModel) -> Self {
+        self.ordering = Some(OrderingPolicy::Relevance(model));
+        self
+    }
+
     /// Filter by frame type
     pub fn by_type(mut self, frame_type: impl Into<String>) -> Self {
         self.filters.push(FrameFilter::ByType(frame_type.into()));
@@ -110,6 +118,39 @@ impl ContextViewBuilder {
         self
     }
 
+    /// Filter by a visible metadata key
+    pub fn where_metadata(mut self, key: impl Into<String>, predicate: MetadataPredicate) -> Self {
+        self.filters.push(FrameFilter::Metadata {
+            key: key.into(),
+            predicate,
+        });
+        self
+    }
+
+    /// Keep frames created at or after `since`
+    pub fn since(mut self, since: SystemTime) -> Self {
+        self.filters.push(FrameFilter::Since(since));
+        self
+    }
+
+    /// Keep frames created at or before `until`
+    pub fn until(mut self, until: SystemTime) -> Self {
+        self.filters.push(FrameFilter::Until(until));
+        self
+    }
+
+    /// Include frames marked deleted
+    pub fn include_deleted(mut self) -> Self {
+        self.filters.push(FrameFilter::IncludeDeleted);
+        self
+    }
+
+    /// Add an arbitrary filter, including boolean combinations
+    pub fn filter(mut self, filter: FrameFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
     /// Build the ContextView
     ///
     /// Uses default values for any fields not explicitly set:
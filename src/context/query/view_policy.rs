@@ -1,10 +1,14 @@
 //! Context view policy: ordering and filtering for frame selection.
 //! Ensures deterministic, bounded context retrieval.
 
+use super::relevance::{reference_time, RelevanceModel};
 use crate::context::frame::{Frame, FrameMerkleSet, FrameStorage};
+use crate::context::glob::glob_match;
 use crate::error::StorageError;
+use crate::metadata::frame_types::project_visible_metadata;
 use crate::types::FrameID;
 use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
 
 /// Ordering policy for frame selection
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -15,15 +19,91 @@ pub enum OrderingPolicy {
     Type,
     /// Order by agent ID (lexicographic)
     Agent,
+    /// Order by relevance score (highest first, ties broken by FrameID)
+    Relevance(RelevanceModel),
+}
+
+/// Predicate on a single visible metadata value
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MetadataPredicate {
+    /// Value equals the given string
+    Equals(String),
+    /// Value matches a glob pattern (`*` any run, `?` any single character)
+    Glob(String),
+    /// Key is present with any value
+    Exists,
+}
+
+impl MetadataPredicate {
+    /// Check a metadata value (None when the key is absent)
+    pub fn matches(&self, value: Option<&str>) -> bool {
+        match (self, value) {
+            (MetadataPredicate::Equals(expected), Some(value)) => value == expected,
+            (MetadataPredicate::Glob(pattern), Some(value)) => glob_match(pattern, value),
+            (MetadataPredicate::Exists, Some(_)) => true,
+            (_, None) => false,
+        }
+    }
 }
 
 /// Frame filter
+///
+/// Frames marked deleted are excluded unless `IncludeDeleted` appears in the top-level
+/// filter list. Inside boolean combinators `IncludeDeleted` is neutral (always matches).
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum FrameFilter {
     /// Filter frames by type
     ByType(String),
     /// Filter frames by agent ID
     ByAgent(String),
+    /// Filter frames by a visible metadata key (internal keys such as agent_id are not visible)
+    Metadata {
+        key: String,
+        predicate: MetadataPredicate,
+    },
+    /// Keep frames created at or after this time
+    Since(SystemTime),
+    /// Keep frames created at or before this time
+    Until(SystemTime),
+    /// Keep frames marked deleted
+    IncludeDeleted,
+    /// All inner filters match
+    And(Vec<FrameFilter>),
+    /// At least one inner filter matches
+    Or(Vec<FrameFilter>),
+    /// Inner filter does not match
+    Not(Box<FrameFilter>),
+}
+
+impl FrameFilter {
+    /// Check whether a frame satisfies this filter
+    pub fn matches(&self, frame: &Frame) -> bool {
+        match self {
+            FrameFilter::ByType(filter_type) => frame.frame_type == *filter_type,
+            FrameFilter::ByAgent(filter_agent) => frame.agent_id() == Some(filter_agent.as_str()),
+            FrameFilter::Metadata { key, predicate } => {
+                let visible = project_visible_metadata(&frame.metadata);
+                predicate.matches(visible.get(key).map(|v| v.as_str()))
+            }
+            FrameFilter::Since(since) => frame.timestamp >= *since,
+            FrameFilter::Until(until) => frame.timestamp <= *until,
+            FrameFilter::IncludeDeleted => true,
+            FrameFilter::And(filters) => filters.iter().all(|f| f.matches(frame)),
+            FrameFilter::Or(filters) => filters.iter().any(|f| f.matches(frame)),
+            FrameFilter::Not(filter) => !filter.matches(frame),
+        }
+    }
+}
+
+/// Apply a filter list to a frame
+///
+/// All filters must match, and deleted frames are dropped unless the list contains
+/// `FrameFilter::IncludeDeleted`.
+pub fn frame_passes_filters(frame: &Frame, filters: &[FrameFilter]) -> bool {
+    if frame.is_deleted() && !filters.contains(&FrameFilter::IncludeDeleted) {
+        return false;
+    }
+    filters.iter().all(|filter| filter.matches(frame))
 }
 
 /// Context view policy
@@ -54,16 +134,11 @@ pub fn get_context_view(
 
     let filtered_frames: Vec<(FrameID, Frame)> = frames_with_metadata
         .into_iter()
-        .filter(|(_, frame)| {
-            policy.filters.iter().all(|filter| match filter {
-                FrameFilter::ByType(filter_type) => frame.frame_type == *filter_type,
-                FrameFilter::ByAgent(filter_agent) => frame.agent_id() == Some(filter_agent.as_str()),
-            })
-        })
+        .filter(|(_, frame)| frame_passes_filters(frame, &policy.filters))
         .collect();
 
     let mut sorted_frames = filtered_frames;
-    match policy.ordering {
+    match &policy.ordering {
         OrderingPolicy::Recency => {
             sorted_frames.sort_by(|(_, a), (_, b)| b.timestamp.cmp(&a.timestamp));
         }
@@ -77,6 +152,16 @@ pub fn get_context_view(
                 agent_a.cmp(agent_b)
             });
         }
+        OrderingPolicy::Relevance(model) => {
+            // All frames belong to the same node, so proximity is constant
+            let reference = reference_time(sorted_frames.iter().map(|(_, f)| f));
+            sorted_frames.sort_by_cached_key(|(frame_id, frame)| {
+                (
+                    std::cmp::Reverse(model.score(frame, 0, reference)),
+                    *frame_id,
+                )
+            });
+        }
     }
 
     sorted_frames.truncate(policy.max_frames);
@@ -92,15 +177,19 @@ mod tests {
     use std::collections::HashMap;
     use tempfile::TempDir;
 
-    fn create_test_frame(
+    fn create_test_frame(frame_id_base: u8, frame_type: &str, agent_id: Option<&str>) -> Frame {
+        create_test_frame_with_metadata(frame_id_base, frame_type, agent_id, HashMap::new())
+    }
+
+    fn create_test_frame_with_metadata(
         frame_id_base: u8,
         frame_type: &str,
         agent_id: Option<&str>,
+        metadata: HashMap<String, String>,
     ) -> Frame {
         let node_id: NodeID = [1u8; 32];
         let basis = Basis::Node(node_id);
         let content = format!("content_{}", frame_id_base).into_bytes();
-        let metadata = HashMap::new();
         let agent_id = agent_id.unwrap_or("test-agent").to_string();
         Frame::new(basis, content, frame_type.to_string(), agent_id, metadata).unwrap()
     }
@@ -182,6 +271,40 @@ mod tests {
         assert!(frame3_idx < frame1_idx);
     }
 
+    #[test]
+    fn test_ordering_by_relevance() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FrameStorage::new(temp_dir.path()).unwrap();
+        let frame1 = create_test_frame(1, "other", Some("agent1"));
+        let frame2 = create_test_frame(2, "analysis", Some("agent1"));
+        let frame3 = create_test_frame(3, "other", Some("agent2"));
+        storage.store(&frame1).unwrap();
+        storage.store(&frame2).unwrap();
+        storage.store(&frame3).unwrap();
+        let mut frame_set = FrameMerkleSet::new();
+        frame_set.add_frame(frame1.frame_id).unwrap();
+        frame_set.add_frame(frame2.frame_id).unwrap();
+        frame_set.add_frame(frame3.frame_id).unwrap();
+        let model = RelevanceModel {
+            preferred_agents: vec!["agent2".to_string()],
+            ..RelevanceModel::default()
+        };
+        let policy = ViewPolicy {
+            max_frames: 100,
+            ordering: OrderingPolicy::Relevance(model),
+            filters: vec![],
+        };
+        let view = get_context_view(&frame_set, &storage, &policy).unwrap();
+        assert_eq!(
+            view,
+            vec![frame3.frame_id, frame2.frame_id, frame1.frame_id]
+        );
+        assert_eq!(
+            view,
+            get_context_view(&frame_set, &storage, &policy).unwrap()
+        );
+    }
+
     #[test]
     fn test_deterministic_selection() {
         let temp_dir = TempDir::new().unwrap();
@@ -245,4 +368,75 @@ mod tests {
         assert!(view.contains(&frame3.frame_id));
         assert!(!view.contains(&frame2.frame_id));
     }
+
+    fn metadata(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_metadata_predicates() {
+        let ollama = create_test_frame_with_metadata(
+            1,
+            "test",
+            None,
+            metadata(&[("provider", "ollama"), ("model", "llama3")]),
+        );
+        let openai = create_test_frame_with_metadata(
+            2,
+            "test",
+            None,
+            metadata(&[("provider", "openai"), ("model", "gpt-4o-mini")]),
+        );
+
+        let by_provider = FrameFilter::Metadata {
+            key: "provider".to_string(),
+            predicate: MetadataPredicate::Equals("ollama".to_string()),
+        };
+        assert!(by_provider.matches(&ollama));
+        assert!(!by_provider.matches(&openai));
+
+        let by_model = FrameFilter::Metadata {
+            key: "model".to_string(),
+            predicate: MetadataPredicate::Glob("gpt-4*".to_string()),
+        };
+        assert!(by_model.matches(&openai));
+        assert!(!by_model.matches(&ollama));
+
+        // agent_id is internal and not visible to metadata predicates
+        let hidden = FrameFilter::Metadata {
+            key: "agent_id".to_string(),
+            predicate: MetadataPredicate::Exists,
+        };
+        assert!(!hidden.matches(&ollama));
+
+        let either = FrameFilter::Or(vec![by_provider.clone(), by_model.clone()]);
+        assert!(either.matches(&ollama) && either.matches(&openai));
+        let neither = FrameFilter::Not(Box::new(either));
+        assert!(!neither.matches(&ollama));
+        let both = FrameFilter::And(vec![by_provider, by_model]);
+        assert!(!both.matches(&ollama) && !both.matches(&openai));
+    }
+
+    #[test]
+    fn test_filter_time_range_and_deleted() {
+        let frame = create_test_frame(1, "test", None);
+        let earlier = frame.timestamp - std::time::Duration::from_secs(60);
+        let later = frame.timestamp + std::time::Duration::from_secs(60);
+        assert!(FrameFilter::Since(earlier).matches(&frame));
+        assert!(!FrameFilter::Since(later).matches(&frame));
+        assert!(FrameFilter::Until(later).matches(&frame));
+        assert!(!FrameFilter::Until(earlier).matches(&frame));
+
+        let deleted =
+            create_test_frame_with_metadata(2, "test", None, metadata(&[("deleted", "true")]));
+        assert!(!frame_passes_filters(&deleted, &[]));
+        assert!(frame_passes_filters(
+            &deleted,
+            &[FrameFilter::IncludeDeleted]
+        ));
+        assert!(frame_passes_filters(&frame, &[]));
+    }
 }
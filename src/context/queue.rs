@@ -5,29 +5,67 @@
 
 use crate::api::{ContextApi, ContextView};
 use crate::agent::profile::prompt_contract::PromptContract;
+use crate::agent::profile::{ChunkingPolicy, OutputSchema, ToolPolicy};
 use crate::context::frame::{Basis, Frame};
+use crate::context::generation::budget::UsageLedger;
+use crate::context::generation::estimate::estimate_tokens;
+use crate::context::generation::plan::ConsumedFrames;
+use crate::context::generation::tools::{WorkspaceTools, TOOL_INSTRUCTIONS};
+use crate::context::generation::chunking::{
+    chunk_frame_type, group_parts, render_chunk_prompt, render_merge_prompt,
+    render_synthesis_prompt, rendered_parts_len, split_source, PartSummary,
+};
 use crate::error::ApiError;
 use crate::metadata::frame_types::FrameMetadata;
-use crate::metadata::frame_write_contract::{build_generated_metadata, validate_frame_metadata};
-use crate::provider::ChatMessage;
+use crate::metadata::frame_write_contract::{
+    build_generated_metadata, build_template_metadata, validate_frame_metadata, CONTENT_TYPE_JSON,
+    KEY_CHUNK_COUNT, KEY_CHUNK_INDEX, KEY_CHUNK_LINES, KEY_COMPLETION_TOKENS, KEY_CONTENT_TYPE,
+    KEY_FALLBACK_FROM, KEY_FINISH_REASON, KEY_LATENCY_MS, KEY_OUTPUT_REPAIRS, KEY_OUTPUT_SCHEMA_ID,
+    KEY_PROMPT_TOKENS, KEY_RESPONSE_CACHED, KEY_TOOL_STEPS, KEY_TOOL_TRANSCRIPT, KEY_TOTAL_TOKENS,
+    KEY_TRUNCATED, KEY_USAGE_ESTIMATED,
+};
+use crate::provider::cache::{response_cache_key, ResponseCache};
+use crate::provider::{
+    workspace_relative_messages, ChatMessage, CompletionOptions, CompletionResponse, MessageRole,
+    ModelPricing, ModelProviderClient, ResponseFormat, StreamChunk, TokenUsage,
+};
 use crate::store::NodeRecord;
 use crate::telemetry::{
-    ProgressRuntime, ProviderLifecycleEventData, QueueEventData, QueueStatsEventData,
+    ProgressRuntime, ProviderChunkEventData, ProviderLifecycleEventData, QueueEventData,
+    QueueStatsEventData,
 };
-use crate::types::{FrameID, NodeID};
+use crate::tree::classify::{KEY_FILE_CLASS, KEY_FILE_CLASS_REASON};
+use crate::tree::hasher::compute_content_hash;
+use crate::types::{FrameID, Hash, NodeID};
+use futures::StreamExt;
 use hex;
 use parking_lot::RwLock;
 use serde_json::json;
 use std::collections::{BinaryHeap, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{oneshot, Mutex, Notify, Semaphore};
+use tokio::sync::{oneshot, Mutex, Notify};
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
+mod rate_limit;
+
+use rate_limit::{backoff_delay, ProviderLimits, ProviderRateLimiter};
+
 type GeneratedMetadataBuilder =
     dyn Fn(&str, &str, &str, &str, &str) -> FrameMetadata + Send + Sync;
 
+/// Receives streamed response text as it arrives
+pub type StreamSink = dyn Fn(&str) + Send + Sync;
+
+/// Written to the stream sink before a retry re-streams a response that failed midway
+pub const STREAM_RETRY_MARKER: &str = "\n[stream interrupted; retrying from the start]\n";
+
+/// Streamed text is batched into `provider_response_chunk` events of at least this many bytes
+const STREAM_EVENT_MIN_BYTES: usize = 64;
+
 /// Priority level for generation requests
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Priority {
@@ -64,6 +102,16 @@ pub struct QueueEventContext {
 pub struct GenerationRequestOptions {
     pub force: bool,
     pub plan_id: Option<String>,
+    /// Write a template frame for a classified file instead of calling the provider
+    pub template: bool,
+    /// Providers to fail over to, in order, once retries on the current provider are exhausted
+    pub fallback_providers: Vec<String>,
+    /// Providers this request already failed over from
+    pub failed_providers: Vec<String>,
+    /// Other agents' child frames to include in a directory prompt
+    pub consumes: Vec<ConsumedFrames>,
+    /// User prompt template replacing the agent's file and directory prompts
+    pub user_prompt: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -188,15 +236,18 @@ impl PartialOrd for GenerationRequest {
 /// Configuration for the generation queue
 #[derive(Debug, Clone)]
 pub struct GenerationConfig {
-    /// Maximum concurrent generations per agent
+    /// Maximum concurrent requests per provider, unless the provider config sets
+    /// `rate_limits.max_concurrency`; concurrency adapts below this on 429 responses
     pub max_concurrent_per_agent: usize,
     /// Batch size for processing requests
     pub batch_size: usize,
     /// Maximum retry attempts per request
     pub max_retry_attempts: usize,
-    /// Delay between retries (milliseconds)
+    /// Base delay between retries (milliseconds); doubles per attempt, with jitter
     pub retry_delay_ms: u64,
-    /// Rate limit: minimum delay between requests per agent (milliseconds)
+    /// Upper bound on the retry delay (milliseconds)
+    pub max_retry_delay_ms: u64,
+    /// Rate limit: minimum delay between requests per provider (milliseconds)
     pub rate_limit_ms: Option<u64>,
     /// Maximum queue size (prevents memory exhaustion)
     pub max_queue_size: usize,
@@ -211,7 +262,8 @@ impl Default for GenerationConfig {
             batch_size: 50,
             max_retry_attempts: 3,
             retry_delay_ms: 1000,
-            rate_limit_ms: Some(100), // 100ms between requests per agent
+            max_retry_delay_ms: 30_000,
+            rate_limit_ms: Some(100), // 100ms between requests per provider
             max_queue_size: 10000,
             workers_per_agent: 2,
         }
@@ -231,62 +283,6 @@ pub struct QueueStats {
     pub failed: usize,
 }
 
-/// Per-agent rate limiter
-struct AgentRateLimiter {
-    semaphore: Arc<Semaphore>,
-    last_request: Arc<RwLock<HashMap<String, Instant>>>,
-    min_delay: Option<Duration>,
-}
-
-impl AgentRateLimiter {
-    fn new(max_concurrent: usize, min_delay_ms: Option<u64>) -> Self {
-        Self {
-            semaphore: Arc::new(Semaphore::new(max_concurrent)),
-            last_request: Arc::new(RwLock::new(HashMap::new())),
-            min_delay: min_delay_ms.map(Duration::from_millis),
-        }
-    }
-
-    async fn acquire(&self, agent_id: &str) -> Result<tokio::sync::SemaphorePermit<'_>, ApiError> {
-        // Acquire semaphore (concurrency limit)
-        let permit = self
-            .semaphore
-            .acquire()
-            .await
-            .map_err(|_| ApiError::ProviderRateLimit("Semaphore closed".to_string()))?;
-
-        // Check rate limit delay
-        if let Some(min_delay) = self.min_delay {
-            let sleep_duration = {
-                let last = self.last_request.read();
-                if let Some(last_time) = last.get(agent_id) {
-                    let elapsed = last_time.elapsed();
-                    if elapsed < min_delay {
-                        Some(min_delay - elapsed)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            };
-
-            // Sleep if needed (after dropping the guard)
-            if let Some(duration) = sleep_duration {
-                sleep(duration).await;
-            }
-
-            // Update last request time
-            {
-                let mut last = self.last_request.write();
-                last.insert(agent_id.to_string(), Instant::now());
-            }
-        }
-
-        Ok(permit)
-    }
-}
-
 /// Frame generation queue
 pub struct FrameGenerationQueue {
     /// Pending requests (priority queue using BinaryHeap)
@@ -299,8 +295,8 @@ pub struct FrameGenerationQueue {
     config: GenerationConfig,
     /// API for frame operations
     api: Arc<ContextApi>,
-    /// Rate limiters per agent
-    rate_limiters: Arc<RwLock<HashMap<String, AgentRateLimiter>>>,
+    /// Adaptive rate limiters per provider
+    rate_limiters: Arc<RwLock<HashMap<String, Arc<ProviderRateLimiter>>>>,
     /// Running state
     running: Arc<RwLock<bool>>,
     /// Statistics
@@ -311,11 +307,16 @@ pub struct FrameGenerationQueue {
     dedupe_index: Arc<Mutex<HashMap<RequestIdentity, DedupeEntry>>>,
     /// Builder for generated frame metadata.
     metadata_builder: Arc<GeneratedMetadataBuilder>,
+    /// Optional usage ledger: records provider token usage and enforces budget caps
+    usage: Option<Arc<UsageLedger>>,
+    /// Optional provider response cache consulted before every provider call
+    response_cache: Option<Arc<ResponseCache>>,
+    /// When set, frame responses are streamed into this sink and emitted as
+    /// `provider_response_chunk` events
+    stream_sink: Option<Arc<StreamSink>>,
 }
 
 impl FrameGenerationQueue {
-    const FILE_CONTEXT_MAX_BYTES: usize = 128 * 1024;
-
     /// Create a new generation queue
     pub fn new(api: Arc<ContextApi>, config: GenerationConfig) -> Self {
         Self::with_event_context(api, config, None)
@@ -344,6 +345,7 @@ impl FrameGenerationQueue {
     where
         F: Fn(&str, &str, &str, &str, &str) -> FrameMetadata + Send + Sync + 'static,
     {
+        let response_cache = api.response_cache().cloned();
         Self {
             queue: Arc::new(Mutex::new(BinaryHeap::new())),
             notify: Arc::new(Notify::new()),
@@ -356,9 +358,33 @@ impl FrameGenerationQueue {
             event_context,
             dedupe_index: Arc::new(Mutex::new(HashMap::new())),
             metadata_builder: Arc::new(metadata_builder),
+            usage: None,
+            response_cache,
+            stream_sink: None,
         }
     }
 
+    /// Record provider token usage in `ledger` and stop calling providers once its budget
+    /// is exhausted.
+    pub fn with_usage_ledger(mut self, ledger: Arc<UsageLedger>) -> Self {
+        self.usage = Some(ledger);
+        self
+    }
+
+    /// Always call providers, bypassing (and not filling) the API's response cache.
+    pub fn without_response_cache(mut self) -> Self {
+        self.response_cache = None;
+        self
+    }
+
+    /// Stream frame responses: text goes to `sink` as it arrives and, with an event
+    /// context, out as `provider_response_chunk` events. Usage the stream does not
+    /// report is estimated and marked as such.
+    pub fn with_streaming(mut self, sink: Arc<StreamSink>) -> Self {
+        self.stream_sink = Some(sink);
+        self
+    }
+
     /// Enqueue a generation request (async - returns immediately)
     pub async fn enqueue(
         &self,
@@ -765,6 +791,9 @@ impl FrameGenerationQueue {
             let event_context = self.event_context.clone();
             let dedupe_index = Arc::clone(&self.dedupe_index);
             let metadata_builder = Arc::clone(&self.metadata_builder);
+            let usage = self.usage.clone();
+            let response_cache = self.response_cache.clone();
+            let stream_sink = self.stream_sink.clone();
 
             let handle = tokio::spawn(async move {
                 Self::worker_loop(
@@ -779,6 +808,9 @@ impl FrameGenerationQueue {
                     event_context,
                     dedupe_index,
                     metadata_builder,
+                    usage,
+                    response_cache,
+                    stream_sink,
                 )
                 .await;
             });
@@ -860,18 +892,22 @@ impl FrameGenerationQueue {
     }
 
     /// Worker loop for processing requests
+    #[allow(clippy::too_many_arguments)]
     async fn worker_loop(
         worker_id: usize,
         queue: Arc<Mutex<BinaryHeap<GenerationRequest>>>,
         notify: Arc<Notify>,
         api: Arc<ContextApi>,
         config: GenerationConfig,
-        rate_limiters: Arc<RwLock<HashMap<String, AgentRateLimiter>>>,
+        rate_limiters: Arc<RwLock<HashMap<String, Arc<ProviderRateLimiter>>>>,
         running: Arc<RwLock<bool>>,
         stats: Arc<RwLock<QueueStats>>,
         event_context: Option<QueueEventContext>,
         dedupe_index: Arc<Mutex<HashMap<RequestIdentity, DedupeEntry>>>,
         metadata_builder: Arc<GeneratedMetadataBuilder>,
+        usage: Option<Arc<UsageLedger>>,
+        response_cache: Option<Arc<ResponseCache>>,
+        stream_sink: Option<Arc<StreamSink>>,
     ) {
         debug!(worker_id, "Worker started");
 
@@ -920,61 +956,35 @@ impl FrameGenerationQueue {
                 },
             );
 
-            // Get or create rate limiter for this agent
-            // We need to clone the Arc references, not the limiter itself
-            let (semaphore, last_request, min_delay) = {
-                let mut limiters = rate_limiters.write();
-                let limiter = limiters.entry(request.agent_id.clone()).or_insert_with(|| {
-                    AgentRateLimiter::new(config.max_concurrent_per_agent, config.rate_limit_ms)
-                });
-                (
-                    Arc::clone(&limiter.semaphore),
-                    Arc::clone(&limiter.last_request),
-                    limiter.min_delay,
-                )
-            };
-
-            // Create a temporary rate limiter for this request
-            let rate_limiter = AgentRateLimiter {
-                semaphore,
-                last_request,
-                min_delay,
-            };
+            let rate_limiter =
+                Self::provider_rate_limiter(&api, &config, &rate_limiters, &request.provider_name);
             let request_identity = RequestIdentity::from_request(&request);
 
-            // Acquire rate limiter permit
-            let _permit = match rate_limiter.acquire(&request.agent_id).await {
-                Ok(permit) => permit,
-                Err(e) => {
-                    error!(
-                        worker_id,
-                        agent_id = %request.agent_id,
-                        error = %e,
-                        "Failed to acquire rate limiter permit"
-                    );
-                    // Re-queue request (maintains priority order automatically)
-                    let mut queue_guard = queue.lock().await;
-                    queue_guard.push(request.clone());
-                    {
-                        let mut stats = stats.write();
-                        stats.processing = stats.processing.saturating_sub(1);
-                        stats.pending += 1;
-                    }
-                    continue;
-                }
-            };
-
-            // Process request
+            // Process request, noting whether any text reached the stream sink
+            let streamed = Arc::new(AtomicBool::new(false));
+            let tracking_sink = stream_sink.clone().map(|sink| {
+                let streamed = Arc::clone(&streamed);
+                Arc::new(move |text: &str| {
+                    streamed.store(true, Ordering::Relaxed);
+                    sink(text);
+                }) as Arc<StreamSink>
+            });
             let result = Self::process_request(
                 &request,
                 &api,
                 &config,
                 event_context.clone(),
                 metadata_builder.as_ref(),
+                usage.as_deref(),
+                response_cache.as_deref(),
+                &rate_limiter,
+                tracking_sink.as_deref(),
             )
             .await;
 
-            // Determine if we should retry (before sending result to completion channel)
+            // Determine if we should retry or fail over (before sending result to completion
+            // channel)
+            let mut failover = false;
             let should_retry = {
                 let mut stats_guard = stats.write();
                 stats_guard.processing = stats_guard.processing.saturating_sub(1);
@@ -987,7 +997,8 @@ impl FrameGenerationQueue {
                         // Check if we should retry
                         let retry = request.retry_count < config.max_retry_attempts
                             && Self::is_retryable_error(err);
-                        if retry {
+                        failover = !retry && Self::can_fail_over(&request, err);
+                        if retry || failover {
                             // Will update stats after re-queuing
                         } else {
                             stats_guard.failed += 1;
@@ -1000,7 +1011,7 @@ impl FrameGenerationQueue {
                                 "Generation request failed permanently"
                             );
                         }
-                        retry
+                        retry || failover
                     }
                 }
             };
@@ -1020,8 +1031,42 @@ impl FrameGenerationQueue {
                 }
             }
 
+            // The next attempt streams from the start; mark where the failed one stopped
+            if should_retry && streamed.load(Ordering::Relaxed) {
+                if let Some(sink) = stream_sink.as_deref() {
+                    sink(STREAM_RETRY_MARKER);
+                }
+            }
+
             // Re-queue if needed (after dropping stats guard)
-            if should_retry {
+            if should_retry && failover {
+                // Retries on this provider are exhausted: move to the next provider in the chain
+                let next_provider = request.options.fallback_providers.remove(0);
+                let failed_provider = std::mem::replace(&mut request.provider_name, next_provider);
+                warn!(
+                    worker_id,
+                    node_id = %hex::encode(request.node_id),
+                    agent_id = %request.agent_id,
+                    failed_provider = %failed_provider,
+                    provider_name = %request.provider_name,
+                    "Provider failed after retries; failing over"
+                );
+                Self::emit_provider_event_static(
+                    event_context.clone(),
+                    "provider_failover",
+                    ProviderLifecycleEventData {
+                        node_id: hex::encode(request.node_id),
+                        agent_id: request.agent_id.clone(),
+                        provider_name: request.provider_name.clone(),
+                        frame_type: request.frame_type.clone(),
+                        duration_ms: None,
+                        error: result.as_ref().err().map(ToString::to_string),
+                        retry_count: Some(0),
+                    },
+                );
+                request.options.failed_providers.push(failed_provider);
+                request.retry_count = 0;
+            } else if should_retry {
                 Self::emit_provider_event_static(
                     event_context.clone(),
                     "provider_request_retrying",
@@ -1035,10 +1080,17 @@ impl FrameGenerationQueue {
                         retry_count: Some(request.retry_count + 1),
                     },
                 );
+                // Exponential backoff with jitter; a provider-requested wait is enforced
+                // by the provider's rate limiter
+                let delay = backoff_delay(
+                    Duration::from_millis(config.retry_delay_ms),
+                    Duration::from_millis(config.max_retry_delay_ms),
+                    request.retry_count,
+                );
                 request.retry_count += 1;
-                // Add retry delay before re-queuing
-                sleep(Duration::from_millis(config.retry_delay_ms)).await;
-
+                sleep(delay).await;
+            }
+            if should_retry {
                 let mut queue_guard = queue.lock().await;
                 queue_guard.push(request.clone());
                 drop(queue_guard);
@@ -1057,14 +1109,47 @@ impl FrameGenerationQueue {
         debug!(worker_id, "Worker stopped");
     }
 
+    /// Shared limiter for a provider, created from its config on first use
+    fn provider_rate_limiter(
+        api: &ContextApi,
+        config: &GenerationConfig,
+        rate_limiters: &RwLock<HashMap<String, Arc<ProviderRateLimiter>>>,
+        provider_name: &str,
+    ) -> Arc<ProviderRateLimiter> {
+        if let Some(limiter) = rate_limiters.read().get(provider_name) {
+            return Arc::clone(limiter);
+        }
+        let configured = api
+            .provider_registry()
+            .read()
+            .get(provider_name)
+            .and_then(|provider| provider.rate_limits);
+        let limits = ProviderLimits::resolve(
+            configured.as_ref(),
+            config.max_concurrent_per_agent,
+            config.rate_limit_ms,
+        );
+        let mut limiters = rate_limiters.write();
+        Arc::clone(
+            limiters
+                .entry(provider_name.to_string())
+                .or_insert_with(|| Arc::new(ProviderRateLimiter::new(limits))),
+        )
+    }
+
     /// Process a single generation request
     /// This is the ONLY place where providers are called
+    #[allow(clippy::too_many_arguments)]
     async fn process_request(
         request: &GenerationRequest,
         api: &ContextApi,
         _config: &GenerationConfig,
         event_context: Option<QueueEventContext>,
         metadata_builder: &GeneratedMetadataBuilder,
+        usage: Option<&UsageLedger>,
+        response_cache: Option<&ResponseCache>,
+        rate_limiter: &ProviderRateLimiter,
+        stream_sink: Option<&StreamSink>,
     ) -> Result<FrameID, ApiError> {
         debug!(
             request_id = ?request.request_id,
@@ -1084,13 +1169,16 @@ impl FrameGenerationQueue {
         let agent = api.get_agent(&request.agent_id)?;
 
         // Get provider config and type from registry (drop guard before await)
-        let (provider_config, provider_type_str) = {
+        let (provider_config, provider_type_str, workspace_root) = {
             let provider_registry = api.provider_registry().read();
             let config = provider_registry.get_or_error(&request.provider_name)?;
             let provider_type_str =
                 crate::provider::profile::provider_type_slug(config.provider_type);
-            (config.clone(), provider_type_str)
+            let workspace_root = provider_registry.workspace_root().map(Path::to_path_buf);
+            (config.clone(), provider_type_str, workspace_root)
         };
+        let response_cache =
+            response_cache.map(|cache| (cache, provider_type_str, workspace_root.as_deref()));
 
         // Get node record
         let node_record = api
@@ -1099,8 +1187,30 @@ impl FrameGenerationQueue {
             .map_err(ApiError::from)?
             .ok_or_else(|| ApiError::NodeNotFound(request.node_id))?;
 
+        // Classified files planned as templates get a descriptive frame without provider IO.
+        if request.options.template {
+            let file_class = node_record
+                .metadata
+                .get(KEY_FILE_CLASS)
+                .map(String::as_str)
+                .unwrap_or("text");
+            let metadata = build_template_metadata(&request.agent_id, file_class);
+            validate_frame_metadata(&metadata, &request.agent_id)?;
+            let frame = Frame::new(
+                Basis::Node(request.node_id),
+                render_template_frame(&node_record).into_bytes(),
+                request.frame_type.clone(),
+                request.agent_id.clone(),
+                metadata,
+            )?;
+            return api.put_frame(request.node_id, frame, request.agent_id.clone());
+        }
+
         // Resolve agent prompt contract once through the explicit adapter.
-        let prompt_contract = PromptContract::from_agent(&agent)?;
+        let mut prompt_contract = PromptContract::from_agent(&agent)?;
+        if let Some(template) = &request.options.user_prompt {
+            prompt_contract = prompt_contract.with_user_prompt(template);
+        }
         let (system_prompt, user_prompt) = Self::generate_prompts(&prompt_contract, &node_record);
 
         // Create provider client (need to get registry again, but drop before await)
@@ -1110,62 +1220,558 @@ impl FrameGenerationQueue {
         };
 
         // Build and validate metadata through the shared write contract before provider IO.
-        let generated_metadata = metadata_builder(
+        let mut generated_metadata = metadata_builder(
             &request.agent_id,
             &request.provider_name,
             client.model_name(),
             &provider_type_str,
             &user_prompt,
         );
+        if !request.options.failed_providers.is_empty() {
+            generated_metadata.insert(
+                KEY_FALLBACK_FROM.to_string(),
+                request.options.failed_providers.join(","),
+            );
+        }
         validate_frame_metadata(&generated_metadata, &request.agent_id)?;
 
-        // Build prompt context based on node kind.
-        // File nodes are grounded on live file bytes, while directory nodes
-        // are grounded on child context frames.
-        let prompt_context = match node_record.node_type {
-            crate::store::NodeType::File { .. } => {
-                Some(Self::collect_file_source_context(&node_record)?)
+        // Resolve completion options: provider defaults > agent preferences (if any)
+        let completion_options = provider_config.default_options.clone();
+
+        // Agents with an output schema get JSON frames: the final response is requested in
+        // the provider's structured-output mode where it has one, then validated and repaired.
+        let output_schema = OutputSchema::from_agent(&agent)?;
+        let mut final_options = completion_options.clone();
+        let mut final_system_prompt = system_prompt.clone();
+        if let Some(schema) = &output_schema {
+            final_options.response_format = Some(ResponseFormat::JsonSchema {
+                name: schema.name(),
+                schema: schema.schema.clone(),
+            });
+            final_system_prompt = format!("{}\n\n{}", system_prompt, schema.instructions());
+        }
+        // Tool-using agents are offered workspace tools on their final call when the
+        // provider can call them; the tool loop below runs the calls until it answers.
+        let tool_policy = ToolPolicy::from_agent(&agent)?;
+        let workspace_tools = match &tool_policy {
+            Some(_) if !client.supports_tools() => {
+                warn!(
+                    agent_id = %request.agent_id,
+                    provider_name = %request.provider_name,
+                    "Provider does not support tool calls; generating without tools"
+                );
+                None
             }
-            crate::store::NodeType::Directory => {
-                let child_context_text =
-                    Self::collect_directory_child_context_text(api, &node_record, request)?;
-                if child_context_text.is_empty() {
-                    let node_context_text = Self::collect_scoped_node_frame_context(api, request)?;
-                    if node_context_text.is_empty() {
-                        None
-                    } else {
-                        Some(node_context_text)
+            Some(policy) => Some((policy, WorkspaceTools::for_node(api, &node_record, policy)?)),
+            None => None,
+        };
+        if let Some((_, tools)) = &workspace_tools {
+            final_options.tools = tools.definitions();
+            final_system_prompt = format!("{}\n\n{}", final_system_prompt, TOOL_INSTRUCTIONS);
+        }
+        // Tool calls only arrive with complete responses, so tool-using frames print whole.
+        let final_stream_sink = stream_sink.filter(|_| workspace_tools.is_none());
+        // Record runs must reach the recorder; replay and template providers are already
+        // offline and deterministic.
+        let response_cache = response_cache.filter(|_| {
+            !matches!(
+                provider_config.provider_type,
+                crate::provider::ProviderType::Replay | crate::provider::ProviderType::Template
+            )
+        });
+
+        // Agent preferences from metadata (optional hints, not requirements)
+        // For now, we just use provider defaults. Agent preferences can be added later if needed.
+
+        // Files above the agent's chunk threshold are generated map-reduce style:
+        // one frame per chunk, then a file frame synthesized from the chunk summaries.
+        let chunk_policy = ChunkingPolicy::from_agent(&agent)?;
+        let file_bytes = match node_record.node_type {
+            crate::store::NodeType::File { content_hash, size }
+                if chunk_policy.should_chunk(size as usize) =>
+            {
+                let bytes = Self::read_scanned_file_bytes(api, &node_record, &content_hash)?;
+                chunk_policy.should_chunk(bytes.len()).then_some(bytes)
+            }
+            _ => None,
+        };
+
+        // Whether every provider response behind the frame came from the response cache
+        let mut all_cached = true;
+        let (mut response, mut duration, basis, mut messages) = if let Some(bytes) = file_bytes {
+            let text = String::from_utf8_lossy(&bytes);
+            let chunks = split_source(&text, &node_record.path, chunk_policy.chunk_max_bytes);
+            let mut summaries = Vec::with_capacity(chunks.len());
+            let mut previous: Option<FrameID> = None;
+            let mut usage_total = TokenUsage {
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                total_tokens: 0,
+                estimated: false,
+            };
+            let mut duration_total = Duration::ZERO;
+            for chunk in &chunks {
+                let messages = vec![
+                    ChatMessage {
+                        role: crate::provider::MessageRole::System,
+                        content: system_prompt.clone(),
+                        tool_calls: Vec::new(),
+                        tool_call_id: None,
+                    },
+                    ChatMessage {
+                        role: crate::provider::MessageRole::User,
+                        content: render_chunk_prompt(
+                            &user_prompt,
+                            &node_record.path,
+                            chunk,
+                            chunks.len(),
+                        ),
+                        tool_calls: Vec::new(),
+                        tool_call_id: None,
+                    },
+                ];
+                let (chunk_response, chunk_duration, chunk_cached) = Self::complete_with_events(
+                    client.as_ref(),
+                    messages,
+                    completion_options.clone(),
+                    request,
+                    event_context.clone(),
+                    usage,
+                    provider_config.pricing.as_ref(),
+                    rate_limiter,
+                    response_cache,
+                    None,
+                )
+                .await?;
+                all_cached &= chunk_cached;
+                add_usage(&mut usage_total, &chunk_response.usage);
+                duration_total += chunk_duration;
+
+                let mut chunk_metadata = generated_metadata.clone();
+                insert_response_provenance(
+                    &mut chunk_metadata,
+                    &chunk_response.usage,
+                    chunk_duration.as_millis(),
+                    chunk_response.finish_reason.as_deref(),
+                );
+                chunk_metadata.insert(KEY_CHUNK_INDEX.to_string(), chunk.index.to_string());
+                chunk_metadata.insert(KEY_CHUNK_COUNT.to_string(), chunks.len().to_string());
+                chunk_metadata.insert(
+                    KEY_CHUNK_LINES.to_string(),
+                    format!("{}-{}", chunk.start_line, chunk.end_line),
+                );
+                let chunk_basis = match previous {
+                    Some(frame_id) => Basis::Frame(frame_id),
+                    None => Basis::Node(request.node_id),
+                };
+                let chunk_frame = Frame::new(
+                    chunk_basis,
+                    chunk_response.content.clone().into_bytes(),
+                    chunk_frame_type(&request.frame_type),
+                    request.agent_id.clone(),
+                    chunk_metadata,
+                )?;
+                previous =
+                    Some(api.put_frame(request.node_id, chunk_frame, request.agent_id.clone())?);
+                summaries.push(PartSummary::from_chunk(chunk, chunk_response.content));
+            }
+
+            // Merge neighbouring summaries until they fit in one synthesis prompt; stop once
+            // no two neighbours fit together and let the synthesis prompt cut them instead
+            let max_bytes = chunk_policy.chunk_max_bytes;
+            while rendered_parts_len(&summaries) > max_bytes {
+                let groups = group_parts(&summaries, max_bytes);
+                if groups.len() == summaries.len() {
+                    break;
+                }
+                let mut merged = Vec::with_capacity(groups.len());
+                for group in groups {
+                    let parts = &summaries[group];
+                    if parts.len() == 1 {
+                        merged.push(parts[0].clone());
+                        continue;
                     }
-                } else {
-                    Some(child_context_text)
+                    let messages = vec![
+                        ChatMessage {
+                            role: crate::provider::MessageRole::System,
+                            content: system_prompt.clone(),
+                            tool_calls: Vec::new(),
+                            tool_call_id: None,
+                        },
+                        ChatMessage {
+                            role: crate::provider::MessageRole::User,
+                            content: render_merge_prompt(&user_prompt, &node_record.path, parts),
+                            tool_calls: Vec::new(),
+                            tool_call_id: None,
+                        },
+                    ];
+                    let (merge_response, merge_duration, merge_cached) =
+                        Self::complete_with_events(
+                            client.as_ref(),
+                            messages,
+                            completion_options.clone(),
+                            request,
+                            event_context.clone(),
+                            usage,
+                            provider_config.pricing.as_ref(),
+                            rate_limiter,
+                            response_cache,
+                            None,
+                        )
+                        .await?;
+                    all_cached &= merge_cached;
+                    add_usage(&mut usage_total, &merge_response.usage);
+                    duration_total += merge_duration;
+                    merged.push(PartSummary {
+                        start_line: parts[0].start_line,
+                        end_line: parts[parts.len() - 1].end_line,
+                        text: merge_response.content,
+                    });
                 }
+                summaries = merged;
             }
+
+            let messages = vec![
+                ChatMessage {
+                    role: crate::provider::MessageRole::System,
+                    content: final_system_prompt,
+                    tool_calls: Vec::new(),
+                    tool_call_id: None,
+                },
+                ChatMessage {
+                    role: crate::provider::MessageRole::User,
+                    content: render_synthesis_prompt(
+                        &user_prompt,
+                        &node_record.path,
+                        &summaries,
+                        max_bytes,
+                    ),
+                    tool_calls: Vec::new(),
+                    tool_call_id: None,
+                },
+            ];
+            let (mut response, synthesis_duration, synthesis_cached) = Self::complete_with_events(
+                client.as_ref(),
+                messages.clone(),
+                final_options.clone(),
+                request,
+                event_context.clone(),
+                usage,
+                provider_config.pricing.as_ref(),
+                rate_limiter,
+                response_cache,
+                final_stream_sink,
+            )
+            .await?;
+            all_cached &= synthesis_cached;
+            add_usage(&mut usage_total, &response.usage);
+            response.usage = usage_total;
+            generated_metadata.insert(KEY_CHUNK_COUNT.to_string(), chunks.len().to_string());
+            let basis = match previous {
+                Some(frame) => Basis::Both {
+                    node: request.node_id,
+                    frame,
+                },
+                None => Basis::Node(request.node_id),
+            };
+            (
+                response,
+                duration_total + synthesis_duration,
+                basis,
+                messages,
+            )
+        } else {
+            let messages = Self::build_prompt_messages(
+                api,
+                &node_record,
+                final_system_prompt,
+                &user_prompt,
+                &request.agent_id,
+                &request.frame_type,
+                &request.options.consumes,
+            )?;
+            let (response, duration, cached) = Self::complete_with_events(
+                client.as_ref(),
+                messages.clone(),
+                final_options.clone(),
+                request,
+                event_context.clone(),
+                usage,
+                provider_config.pricing.as_ref(),
+                rate_limiter,
+                response_cache,
+                final_stream_sink,
+            )
+            .await?;
+            all_cached = cached;
+            (response, duration, Basis::Node(request.node_id), messages)
         };
 
-        // Build messages for LLM
-        let mut messages = vec![ChatMessage {
-            role: crate::provider::MessageRole::System,
-            content: system_prompt,
-        }];
+        // Tool-using agents: run the calls the model asks for and send the results back
+        // until it answers. At the step or token limit the model is told to answer with
+        // what it has; calling tools after that fails the frame.
+        let mut tool_steps = 0;
+        let mut transcript = Vec::new();
+        if let Some((policy, tools)) = &workspace_tools {
+            let mut wrap_up_sent = false;
+            while !response.tool_calls.is_empty() {
+                if wrap_up_sent {
+                    return Err(ApiError::ToolLimitExceeded(format!(
+                        "agent '{}' kept calling tools after {} step(s) and {} tokens",
+                        request.agent_id, tool_steps, response.usage.total_tokens
+                    )));
+                }
+                tool_steps += 1;
+                let calls = std::mem::take(&mut response.tool_calls);
+                messages.push(ChatMessage {
+                    role: MessageRole::Assistant,
+                    content: std::mem::take(&mut response.content),
+                    tool_calls: calls.clone(),
+                    tool_call_id: None,
+                });
+                for call in &calls {
+                    let (output, entry) = tools.call(call, tool_steps);
+                    debug!(
+                        request_id = ?request.request_id,
+                        node_id = %hex::encode(request.node_id),
+                        tool = %call.name,
+                        step = tool_steps,
+                        error = ?entry.error,
+                        "Tool call completed"
+                    );
+                    transcript.push(entry);
+                    messages.push(ChatMessage {
+                        role: MessageRole::Tool,
+                        content: output,
+                        tool_calls: Vec::new(),
+                        tool_call_id: Some(call.id.clone()),
+                    });
+                }
+                if tool_steps >= policy.max_steps
+                    || u64::from(response.usage.total_tokens) >= policy.max_tokens
+                {
+                    wrap_up_sent = true;
+                    messages.push(ChatMessage {
+                        role: MessageRole::User,
+                        content: "The tool-calling limit for this task is reached. Answer \
+                                  now using the information gathered so far."
+                            .to_string(),
+                        tool_calls: Vec::new(),
+                        tool_call_id: None,
+                    });
+                }
+                let (next, next_duration, next_cached) = Self::complete_with_events(
+                    client.as_ref(),
+                    messages.clone(),
+                    final_options.clone(),
+                    request,
+                    event_context.clone(),
+                    usage,
+                    provider_config.pricing.as_ref(),
+                    rate_limiter,
+                    response_cache,
+                    None,
+                )
+                .await?;
+                all_cached &= next_cached;
+                duration += next_duration;
+                let mut usage_total = response.usage.clone();
+                add_usage(&mut usage_total, &next.usage);
+                response = CompletionResponse {
+                    usage: usage_total,
+                    ..next
+                };
+            }
+            if let Some(sink) = stream_sink {
+                sink(&response.content);
+            }
+        }
 
-        // Add context from existing frames
-        if let Some(context_text) = prompt_context {
-            messages.push(ChatMessage {
-                role: crate::provider::MessageRole::User,
-                content: format!("Context:\n{}\n\nTask: {}", context_text, user_prompt),
-            });
-        } else {
-            messages.push(ChatMessage {
-                role: crate::provider::MessageRole::User,
-                content: user_prompt.clone(),
-            });
+        // Structured frames: validate, and on failure show the model its errors and ask
+        // again, up to the agent's repair count.
+        if let Some(schema) = &output_schema {
+            let mut repairs = 0;
+            let value = loop {
+                let errors = match schema.parse_response(&response.content) {
+                    Ok(value) => break value,
+                    Err(errors) => errors,
+                };
+                if repairs == schema.repairs {
+                    return Err(ApiError::StructuredOutputInvalid(format!(
+                        "schema {} after {} repair attempt(s): {}",
+                        schema.id,
+                        repairs,
+                        errors.join("; ")
+                    )));
+                }
+                repairs += 1;
+                debug!(
+                    request_id = ?request.request_id,
+                    node_id = %hex::encode(request.node_id),
+                    repair = repairs,
+                    errors = %errors.join("; "),
+                    "Response does not match output schema; requesting repair"
+                );
+                messages.push(ChatMessage {
+                    role: MessageRole::Assistant,
+                    content: std::mem::take(&mut response.content),
+                    tool_calls: Vec::new(),
+                    tool_call_id: None,
+                });
+                messages.push(ChatMessage {
+                    role: MessageRole::User,
+                    content: format!(
+                        "Your response does not match the required JSON schema:\n- {}\n\n\
+                         Respond again with only the corrected JSON object.",
+                        errors.join("\n- ")
+                    ),
+                    tool_calls: Vec::new(),
+                    tool_call_id: None,
+                });
+                let (repaired, repair_duration, repair_cached) = Self::complete_with_events(
+                    client.as_ref(),
+                    messages.clone(),
+                    final_options.clone(),
+                    request,
+                    event_context.clone(),
+                    usage,
+                    provider_config.pricing.as_ref(),
+                    rate_limiter,
+                    response_cache,
+                    None,
+                )
+                .await?;
+                all_cached &= repair_cached;
+                duration += repair_duration;
+                let mut usage_total = response.usage.clone();
+                add_usage(&mut usage_total, &repaired.usage);
+                response = CompletionResponse {
+                    usage: usage_total,
+                    ..repaired
+                };
+            };
+            response.content = serde_json::to_string_pretty(&value).expect("JSON values serialize");
+            generated_metadata.insert(KEY_CONTENT_TYPE.to_string(), CONTENT_TYPE_JSON.to_string());
+            generated_metadata.insert(KEY_OUTPUT_SCHEMA_ID.to_string(), schema.id.clone());
+            if repairs > 0 {
+                generated_metadata.insert(KEY_OUTPUT_REPAIRS.to_string(), repairs.to_string());
+            }
+        }
+        if tool_steps > 0 {
+            generated_metadata.insert(KEY_TOOL_STEPS.to_string(), tool_steps.to_string());
+            generated_metadata.insert(
+                KEY_TOOL_TRANSCRIPT.to_string(),
+                serde_json::to_string(&transcript).expect("tool transcripts serialize to JSON"),
+            );
         }
 
-        // Resolve completion options: provider defaults > agent preferences (if any)
-        let completion_options = provider_config.default_options.clone();
+        insert_response_provenance(
+            &mut generated_metadata,
+            &response.usage,
+            duration.as_millis(),
+            response.finish_reason.as_deref(),
+        );
+        if all_cached {
+            generated_metadata.insert(KEY_RESPONSE_CACHED.to_string(), "true".to_string());
+        }
 
-        // Agent preferences from metadata (optional hints, not requirements)
-        // For now, we just use provider defaults. Agent preferences can be added later if needed.
+        // Create frame with generated content
+        let content = response.content.into_bytes();
+
+        let frame = Frame::new(
+            basis,
+            content,
+            request.frame_type.clone(),
+            request.agent_id.clone(),
+            generated_metadata,
+        )?;
+
+        // Store frame using put_frame
+        let frame_id = api.put_frame(request.node_id, frame, request.agent_id.clone())?;
+
+        info!(
+            request_id = ?request.request_id,
+            node_id = %hex::encode(request.node_id),
+            agent_id = %request.agent_id,
+            frame_id = %hex::encode(frame_id),
+            duration_ms = duration.as_millis(),
+            "Frame generation completed"
+        );
+
+        Ok(frame_id)
+    }
+
+    /// Call the provider once, with budget checks, rate limiting, usage accounting, and
+    /// lifecycle events. With a response cache (and the provider type and workspace root for
+    /// its key), a cached response is returned without provider IO and the flag in the result
+    /// is set; forced requests only write to the cache.
+    /// With a stream sink the response is streamed into it; cached responses arrive whole.
+    #[allow(clippy::too_many_arguments)]
+    async fn complete_with_events(
+        client: &dyn ModelProviderClient,
+        messages: Vec<ChatMessage>,
+        completion_options: CompletionOptions,
+        request: &GenerationRequest,
+        event_context: Option<QueueEventContext>,
+        usage: Option<&UsageLedger>,
+        pricing: Option<&ModelPricing>,
+        rate_limiter: &ProviderRateLimiter,
+        response_cache: Option<(&ResponseCache, &str, Option<&Path>)>,
+        stream_sink: Option<&StreamSink>,
+    ) -> Result<(CompletionResponse, Duration, bool), ApiError> {
+        let cache_entry = response_cache.map(|(cache, provider_type, workspace_root)| {
+            let key = response_cache_key(
+                provider_type,
+                client.model_name(),
+                &workspace_relative_messages(&messages, workspace_root),
+                &completion_options,
+            );
+            (cache, key)
+        });
+        let cache_read = cache_entry.as_ref().filter(|_| !request.options.force);
+        if let Some((cache, key)) = cache_read {
+            match cache.get(key) {
+                Ok(Some(response)) => {
+                    debug!(
+                        request_id = ?request.request_id,
+                        node_id = %hex::encode(request.node_id),
+                        provider_name = %request.provider_name,
+                        "Provider response served from cache"
+                    );
+                    Self::emit_provider_event_static(
+                        event_context,
+                        "provider_response_cached",
+                        ProviderLifecycleEventData {
+                            node_id: hex::encode(request.node_id),
+                            agent_id: request.agent_id.clone(),
+                            provider_name: request.provider_name.clone(),
+                            frame_type: request.frame_type.clone(),
+                            duration_ms: Some(0),
+                            error: None,
+                            retry_count: Some(request.retry_count),
+                        },
+                    );
+                    if let Some(sink) = stream_sink {
+                        sink(&response.content);
+                    }
+                    return Ok((response, Duration::ZERO, true));
+                }
+                Ok(None) => {}
+                Err(e) => warn!(error = %e, "Failed to read provider response cache"),
+            }
+        }
+
+        if let Some(usage) = usage {
+            usage.check()?;
+        }
+
+        let prompt_tokens = messages
+            .iter()
+            .map(|m| estimate_tokens(&m.content) as u64)
+            .sum::<u64>();
+        let estimated_tokens =
+            prompt_tokens + completion_options.max_tokens.map(u64::from).unwrap_or(0);
+        let permit = rate_limiter.acquire(estimated_tokens).await;
 
         // Generate completion - THIS IS THE ONLY PLACE PROVIDERS ARE CALLED
         let start = Instant::now();
@@ -1192,9 +1798,33 @@ impl FrameGenerationQueue {
                 retry_count: Some(request.retry_count),
             },
         );
-        let response = match client.complete(messages, completion_options).await {
+        let result = match stream_sink {
+            Some(sink) => {
+                Self::stream_completion(
+                    client,
+                    messages,
+                    completion_options,
+                    request,
+                    event_context.clone(),
+                    sink,
+                    prompt_tokens,
+                )
+                .await
+            }
+            None => client.complete(messages, completion_options).await,
+        };
+        let response = match result {
             Ok(r) => Ok(r),
             Err(e) => {
+                if let ApiError::ProviderRateLimit { retry_after, .. } = &e {
+                    permit.rate_limited(*retry_after);
+                    warn!(
+                        provider_name = %request.provider_name,
+                        retry_after_ms = retry_after.map(|d| d.as_millis() as u64),
+                        concurrency = rate_limiter.concurrency(),
+                        "Provider rate limited; reducing concurrency"
+                    );
+                }
                 Self::emit_provider_event_static(
                     event_context.clone(),
                     "provider_request_failed",
@@ -1234,6 +1864,11 @@ impl FrameGenerationQueue {
         }?;
 
         let duration = start.elapsed();
+        permit.succeeded(u64::from(response.usage.total_tokens));
+        drop(permit);
+        if let Some(usage) = usage {
+            usage.record(&response.usage, pricing);
+        }
         info!(
             request_id = ?request.request_id,
             node_id = %hex::encode(request.node_id),
@@ -1258,74 +1893,232 @@ impl FrameGenerationQueue {
                 retry_count: Some(request.retry_count),
             },
         );
+        if let Some((cache, key)) = &cache_entry {
+            if let Err(e) = cache.put(key, &response) {
+                warn!(error = %e, "Failed to write provider response cache");
+            }
+        }
 
-        // Create frame with generated content
-        let basis = Basis::Node(request.node_id);
-        let content = response.content.into_bytes();
+        Ok((response, duration, false))
+    }
 
-        let frame = Frame::new(
-            basis,
+    /// Assemble a completion from the provider's stream, passing text to `sink` and
+    /// emitting `provider_response_chunk` events as it arrives. Usage the provider does not
+    /// report is estimated from the prompt and the streamed text, and marked as estimated.
+    async fn stream_completion(
+        client: &dyn ModelProviderClient,
+        messages: Vec<ChatMessage>,
+        completion_options: CompletionOptions,
+        request: &GenerationRequest,
+        event_context: Option<QueueEventContext>,
+        sink: &StreamSink,
+        prompt_tokens: u64,
+    ) -> Result<CompletionResponse, ApiError> {
+        let emit_chunk = |index: usize, text: &str| {
+            if let Some(ctx) = &event_context {
+                let payload = ProviderChunkEventData {
+                    node_id: hex::encode(request.node_id),
+                    agent_id: request.agent_id.clone(),
+                    provider_name: request.provider_name.clone(),
+                    frame_type: request.frame_type.clone(),
+                    index,
+                    text: text.to_string(),
+                };
+                ctx.progress.emit_event_best_effort(
+                    &ctx.session_id,
+                    "provider_response_chunk",
+                    json!(payload),
+                );
+            }
+        };
+
+        let mut stream = client.stream(messages, completion_options).await?;
+        let mut content = String::new();
+        let mut pending = String::new();
+        let mut chunks = 0;
+        let (mut reported_prompt, mut reported_completion) = (None, None);
+        let mut finish_reason = None;
+        while let Some(chunk) = stream.next().await {
+            let text = match chunk? {
+                StreamChunk::Text(text) => text,
+                StreamChunk::Usage {
+                    prompt_tokens,
+                    completion_tokens,
+                } => {
+                    reported_prompt = prompt_tokens.or(reported_prompt);
+                    reported_completion = completion_tokens.or(reported_completion);
+                    continue;
+                }
+                StreamChunk::FinishReason(reason) => {
+                    finish_reason = Some(reason);
+                    continue;
+                }
+            };
+            sink(&text);
+            content.push_str(&text);
+            pending.push_str(&text);
+            if pending.len() >= STREAM_EVENT_MIN_BYTES {
+                emit_chunk(chunks, &pending);
+                chunks += 1;
+                pending.clear();
+            }
+        }
+        if !pending.is_empty() {
+            emit_chunk(chunks, &pending);
+        }
+
+        // Estimate only what the provider did not report, and flag the usage as estimated
+        let estimated = reported_prompt.is_none() || reported_completion.is_none();
+        let prompt_tokens =
+            reported_prompt.unwrap_or_else(|| u32::try_from(prompt_tokens).unwrap_or(u32::MAX));
+        let completion_tokens =
+            reported_completion.unwrap_or_else(|| estimate_tokens(&content) as u32);
+        Ok(CompletionResponse {
             content,
-            request.frame_type.clone(),
-            request.agent_id.clone(),
-            generated_metadata,
-        )?;
+            model: client.model_name().to_string(),
+            usage: TokenUsage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens.saturating_add(completion_tokens),
+                estimated,
+            },
+            finish_reason,
+            tool_calls: Vec::new(),
+        })
+    }
 
-        // Store frame using put_frame
-        let frame_id = api.put_frame(request.node_id, frame, request.agent_id.clone())?;
+    /// Build the provider messages for a node: system prompt, then the user prompt
+    /// with its grounding context. Directory prompts also include the child frames of
+    /// every agent in `consumes`.
+    pub(crate) fn build_prompt_messages(
+        api: &ContextApi,
+        node_record: &NodeRecord,
+        system_prompt: String,
+        user_prompt: &str,
+        agent_id: &str,
+        frame_type: &str,
+        consumes: &[ConsumedFrames],
+    ) -> Result<Vec<ChatMessage>, ApiError> {
+        // Build prompt context based on node kind.
+        // File nodes are grounded on the scanned file bytes, while directory nodes
+        // are grounded on child context frames.
+        let prompt_context = match node_record.node_type {
+            crate::store::NodeType::File { .. } => {
+                Some(Self::collect_file_source_context(api, node_record)?)
+            }
+            crate::store::NodeType::Directory => {
+                let child_context_text = Self::collect_directory_child_context_text(
+                    api,
+                    node_record,
+                    agent_id,
+                    frame_type,
+                    consumes,
+                )?;
+                if child_context_text.is_empty() {
+                    let node_context_text = Self::collect_scoped_node_frame_context(
+                        api,
+                        node_record.node_id,
+                        agent_id,
+                        frame_type,
+                    )?;
+                    if node_context_text.is_empty() {
+                        None
+                    } else {
+                        Some(node_context_text)
+                    }
+                } else {
+                    Some(format!(
+                        "Path: {}\nType: Directory\nChildren:\n{}",
+                        node_record.path.display(),
+                        child_context_text
+                    ))
+                }
+            }
+        };
 
-        info!(
-            request_id = ?request.request_id,
-            node_id = %hex::encode(request.node_id),
-            agent_id = %request.agent_id,
-            frame_id = %hex::encode(frame_id),
-            duration_ms = duration.as_millis(),
-            "Frame generation completed"
-        );
+        // Build messages for LLM
+        let mut messages = vec![ChatMessage {
+            role: crate::provider::MessageRole::System,
+            content: system_prompt,
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }];
 
-        Ok(frame_id)
+        // Add context from existing frames
+        if let Some(context_text) = prompt_context {
+            messages.push(ChatMessage {
+                role: crate::provider::MessageRole::User,
+                content: format!("Context:\n{}\n\nTask: {}", context_text, user_prompt),
+                tool_calls: Vec::new(),
+                tool_call_id: None,
+            });
+        } else {
+            messages.push(ChatMessage {
+                role: crate::provider::MessageRole::User,
+                content: user_prompt.to_string(),
+                tool_calls: Vec::new(),
+                tool_call_id: None,
+            });
+        }
+        Ok(messages)
     }
 
     fn collect_directory_child_context_text(
         api: &ContextApi,
         node_record: &NodeRecord,
-        request: &GenerationRequest,
+        agent_id: &str,
+        frame_type: &str,
+        consumes: &[ConsumedFrames],
     ) -> Result<String, ApiError> {
         if !matches!(node_record.node_type, crate::store::NodeType::Directory) {
             return Ok(String::new());
         }
 
-        let child_view = ContextView::builder()
-            .max_frames(1)
-            .recent()
-            .by_type(request.frame_type.clone())
-            .by_agent(request.agent_id.clone())
-            .build();
-
+        // The agent's own child frames first, then each consumed agent's
+        let sources = std::iter::once((agent_id, frame_type, false)).chain(
+            consumes
+                .iter()
+                .map(|c| (c.agent_id.as_str(), c.frame_type.as_str(), true)),
+        );
         let mut child_sections = Vec::new();
-        for child_id in &node_record.children {
-            let child_context = api.get_node(*child_id, child_view.clone())?;
-            if child_context.frames.is_empty() {
-                continue;
-            }
+        for (source_agent, source_frame_type, consumed) in sources {
+            let child_view = ContextView::builder()
+                .max_frames(1)
+                .recent()
+                .by_type(source_frame_type.to_string())
+                .by_agent(source_agent.to_string())
+                .build();
+
+            for child_id in &node_record.children {
+                let child_context = api.get_node(*child_id, child_view.clone())?;
+                if child_context.frames.is_empty() {
+                    continue;
+                }
 
-            let child_kind = match child_context.node_record.node_type {
-                crate::store::NodeType::File { .. } => "File",
-                crate::store::NodeType::Directory => "Directory",
-            };
-            let child_text = child_context
-                .frames
-                .iter()
-                .map(|f| String::from_utf8_lossy(&f.content))
-                .collect::<Vec<_>>()
-                .join("\n\n");
-
-            child_sections.push(format!(
-                "Path: {}\nType: {}\nContent:\n{}",
-                child_context.node_record.path.display(),
-                child_kind,
-                child_text
-            ));
+                let child_kind = match child_context.node_record.node_type {
+                    crate::store::NodeType::File { .. } => "File",
+                    crate::store::NodeType::Directory => "Directory",
+                };
+                let child_text = child_context
+                    .frames
+                    .iter()
+                    .map(|f| String::from_utf8_lossy(&f.content))
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+
+                let source = if consumed {
+                    format!("Agent: {}\n", source_agent)
+                } else {
+                    String::new()
+                };
+                child_sections.push(format!(
+                    "Path: {}\nType: {}\n{}Content:\n{}",
+                    child_context.node_record.path.display(),
+                    child_kind,
+                    source,
+                    child_text
+                ));
+            }
         }
 
         if !child_sections.is_empty() {
@@ -1333,8 +2126,8 @@ impl FrameGenerationQueue {
                 node_id = %hex::encode(node_record.node_id),
                 direct_children = node_record.children.len(),
                 child_context_nodes = child_sections.len(),
-                frame_type = %request.frame_type,
-                agent_id = %request.agent_id,
+                frame_type = %frame_type,
+                agent_id = %agent_id,
                 "Collected directory child context for generation"
             );
         }
@@ -1344,15 +2137,17 @@ impl FrameGenerationQueue {
 
     fn collect_scoped_node_frame_context(
         api: &ContextApi,
-        request: &GenerationRequest,
+        node_id: NodeID,
+        agent_id: &str,
+        frame_type: &str,
     ) -> Result<String, ApiError> {
         let view = ContextView::builder()
             .max_frames(10)
             .recent()
-            .by_type(request.frame_type.clone())
-            .by_agent(request.agent_id.clone())
+            .by_type(frame_type.to_string())
+            .by_agent(agent_id.to_string())
             .build();
-        let context = api.get_node(request.node_id, view)?;
+        let context = api.get_node(node_id, view)?;
         Ok(context
             .frames
             .iter()
@@ -1361,45 +2156,69 @@ impl FrameGenerationQueue {
             .join("\n\n"))
     }
 
-    fn collect_file_source_context(node_record: &NodeRecord) -> Result<String, ApiError> {
-        if !matches!(node_record.node_type, crate::store::NodeType::File { .. }) {
+    fn collect_file_source_context(
+        api: &ContextApi,
+        node_record: &NodeRecord,
+    ) -> Result<String, ApiError> {
+        let crate::store::NodeType::File { content_hash, .. } = node_record.node_type else {
             return Ok(String::new());
-        }
-
-        let bytes = std::fs::read(&node_record.path).map_err(|e| {
-            ApiError::StorageError(crate::error::StorageError::IoError(std::io::Error::new(
-                e.kind(),
-                format!(
-                    "Failed to read file source content for generation {}: {}",
-                    node_record.path.display(),
-                    e
-                ),
-            )))
-        })?;
-
-        let truncated = bytes.len() > Self::FILE_CONTEXT_MAX_BYTES;
-        let slice = if truncated {
-            &bytes[..Self::FILE_CONTEXT_MAX_BYTES]
-        } else {
-            &bytes
         };
-        let mut text = String::from_utf8_lossy(slice).to_string();
-        if truncated {
-            text.push_str(&format!(
-                "\n\n[Truncated to {} bytes for prompt safety]",
-                Self::FILE_CONTEXT_MAX_BYTES
-            ));
-        }
+        let bytes = Self::read_scanned_file_bytes(api, node_record, &content_hash)?;
 
         Ok(format!(
             "Path: {}\nType: File\nContent:\n{}",
             node_record.path.display(),
-            text
+            String::from_utf8_lossy(&bytes)
         ))
     }
 
+    /// Read the file bytes that were hashed at scan time.
+    ///
+    /// The live file is used when its hash still matches the node; otherwise the blob
+    /// cache is consulted, and without a cached copy the node is reported as changed.
+    pub(crate) fn read_scanned_file_bytes(
+        api: &ContextApi,
+        node_record: &NodeRecord,
+        content_hash: &Hash,
+    ) -> Result<Vec<u8>, ApiError> {
+        let live = match std::fs::read(&node_record.path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(ApiError::StorageError(crate::error::StorageError::IoError(
+                    std::io::Error::new(
+                        e.kind(),
+                        format!(
+                            "Failed to read file source content for generation {}: {}",
+                            node_record.path.display(),
+                            e
+                        ),
+                    ),
+                )))
+            }
+        };
+        if let Some(bytes) = live {
+            if compute_content_hash(&bytes) == *content_hash {
+                return Ok(bytes);
+            }
+        }
+        if let Some(blob_store) = api.blob_store() {
+            if let Some(bytes) = blob_store.get(content_hash)? {
+                debug!(
+                    path = %node_record.path.display(),
+                    "File changed since scan; using cached scanned content"
+                );
+                return Ok(bytes);
+            }
+        }
+        Err(ApiError::SourceChanged(node_record.path.clone()))
+    }
+
     /// Generate prompts from the explicit prompt contract adapter.
-    fn generate_prompts(prompt_contract: &PromptContract, node_record: &NodeRecord) -> (String, String) {
+    pub(crate) fn generate_prompts(
+        prompt_contract: &PromptContract,
+        node_record: &NodeRecord,
+    ) -> (String, String) {
         let user_prompt = prompt_contract.render_user_prompt(
             node_record.node_type.clone(),
             &node_record.path.display().to_string(),
@@ -1419,13 +2238,36 @@ impl FrameGenerationQueue {
             ApiError::MissingPromptContractField { .. } => false,
             ApiError::FrameMetadataPolicyViolation(_) => false,
             ApiError::ProviderNotConfigured(_) => false,
-            ApiError::ProviderRateLimit(_) => true,
+            ApiError::BudgetExceeded(_) => false,
+            ApiError::SourceChanged(_) => false,
+            ApiError::ProviderAuthFailed(_) => false, // Fail over instead
+            ApiError::ReplayMismatch(_) => false,     // Same request, same miss
+            // Repair and tool loops already ran to their limits; a retry repeats them
+            ApiError::StructuredOutputInvalid(_) => false,
+            ApiError::ToolLimitExceeded(_) => false,
+            ApiError::ProviderRateLimit { .. } => true,
             ApiError::ProviderRequestFailed(_) => true,
             ApiError::ProviderError(_) => true,
             _ => true, // Retry other errors by default
         }
     }
 
+    /// Provider-side failures move to the next fallback provider once retries are exhausted
+    fn can_fail_over(request: &GenerationRequest, error: &ApiError) -> bool {
+        !request.options.fallback_providers.is_empty()
+            && matches!(
+                error,
+                ApiError::ProviderError(_)
+                    | ApiError::ProviderNotConfigured(_)
+                    | ApiError::ProviderRequestFailed(_)
+                    | ApiError::ProviderAuthFailed(_)
+                    | ApiError::ProviderRateLimit { .. }
+                    | ApiError::ProviderModelNotFound(_)
+                    | ApiError::StructuredOutputInvalid(_)
+                    | ApiError::ToolLimitExceeded(_)
+            )
+    }
+
     fn emit_queue_event(&self, event_type: &str, payload: QueueEventData) {
         Self::emit_queue_event_static(self.event_context.clone(), event_type, payload);
     }
@@ -1475,3 +2317,68 @@ impl FrameGenerationQueue {
         }
     }
 }
+
+/// Finish reasons providers report when output stopped at the token limit
+const TRUNCATED_FINISH_REASONS: &[&str] = &["length", "max_tokens"];
+
+/// Record provider response provenance: token usage, latency, finish reason, and whether
+/// output was cut off at the token limit.
+fn insert_response_provenance(
+    metadata: &mut FrameMetadata,
+    usage: &TokenUsage,
+    latency_ms: u128,
+    finish_reason: Option<&str>,
+) {
+    metadata.insert(
+        KEY_PROMPT_TOKENS.to_string(),
+        usage.prompt_tokens.to_string(),
+    );
+    metadata.insert(
+        KEY_COMPLETION_TOKENS.to_string(),
+        usage.completion_tokens.to_string(),
+    );
+    metadata.insert(KEY_TOTAL_TOKENS.to_string(), usage.total_tokens.to_string());
+    if usage.estimated {
+        metadata.insert(KEY_USAGE_ESTIMATED.to_string(), "true".to_string());
+    }
+    metadata.insert(KEY_LATENCY_MS.to_string(), latency_ms.to_string());
+    if let Some(reason) = finish_reason {
+        metadata.insert(KEY_FINISH_REASON.to_string(), reason.to_string());
+    }
+    let truncated = finish_reason.is_some_and(|r| TRUNCATED_FINISH_REASONS.contains(&r));
+    metadata.insert(KEY_TRUNCATED.to_string(), truncated.to_string());
+}
+
+fn add_usage(total: &mut TokenUsage, usage: &TokenUsage) {
+    total.prompt_tokens += usage.prompt_tokens;
+    total.completion_tokens += usage.completion_tokens;
+    total.total_tokens += usage.total_tokens;
+    total.estimated |= usage.estimated;
+}
+
+/// Frame content for a classified file that is described rather than summarized
+fn render_template_frame(node_record: &NodeRecord) -> String {
+    let file_class = node_record
+        .metadata
+        .get(KEY_FILE_CLASS)
+        .map(String::as_str)
+        .unwrap_or("text");
+    let reason = node_record
+        .metadata
+        .get(KEY_FILE_CLASS_REASON)
+        .map(String::as_str)
+        .unwrap_or("unknown");
+    let size = match node_record.node_type {
+        crate::store::NodeType::File { size, .. } => size,
+        crate::store::NodeType::Directory => 0,
+    };
+    let name = node_record
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_else(|| node_record.path.to_string_lossy());
+    format!(
+        "{} is a {} file ({} bytes, detected by {}). Its contents were not summarized.",
+        name, file_class, size, reason
+    )
+}
@@ -0,0 +1,325 @@
+//! Per-provider adaptive rate limiting for provider calls.
+//!
+//! Each provider gets token buckets for requests and tokens per minute (from provider
+//! config), an optional minimum spacing between requests, and a concurrency limit that
+//! halves on every 429 and grows back by one after a full window of successes.
+//! Waits requested by the provider (`Retry-After`) pause every caller of that provider.
+
+use crate::provider::ProviderRateLimits;
+use parking_lot::Mutex;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::time::sleep;
+
+/// Effective limits for one provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ProviderLimits {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u32>,
+    pub max_concurrency: usize,
+    pub min_interval: Option<Duration>,
+}
+
+impl ProviderLimits {
+    /// Provider config limits over the queue defaults
+    pub fn resolve(
+        configured: Option<&ProviderRateLimits>,
+        default_concurrency: usize,
+        min_interval_ms: Option<u64>,
+    ) -> Self {
+        let configured = configured.copied().unwrap_or_default();
+        Self {
+            requests_per_minute: configured.requests_per_minute,
+            tokens_per_minute: configured.tokens_per_minute,
+            max_concurrency: configured
+                .max_concurrency
+                .unwrap_or(default_concurrency)
+                .max(1),
+            min_interval: min_interval_ms.map(Duration::from_millis),
+        }
+    }
+}
+
+/// Continuously refilled budget of `capacity` units per minute
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    available: f64,
+    refill_per_sec: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn per_minute(limit: u32, now: Instant) -> Self {
+        let capacity = f64::from(limit);
+        Self {
+            capacity,
+            available: capacity,
+            refill_per_sec: capacity / 60.0,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Time until `amount` units are available; requests above capacity wait for a full bucket
+    fn wait_for(&mut self, amount: f64, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        let needed = amount.min(self.capacity) - self.available;
+        (needed > 0.0).then(|| Duration::from_secs_f64(needed / self.refill_per_sec))
+    }
+
+    /// Remove (or, when negative, return) units; the balance may go below zero
+    fn take(&mut self, amount: f64) {
+        self.available = (self.available - amount).min(self.capacity);
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    in_flight: usize,
+    concurrency: usize,
+    successes: usize,
+    requests: Option<TokenBucket>,
+    tokens: Option<TokenBucket>,
+    blocked_until: Option<Instant>,
+    last_start: Option<Instant>,
+}
+
+/// Why a caller cannot start yet
+enum Wait {
+    /// Until a running request finishes
+    Release,
+    /// For a fixed time (or until a release, whichever comes first)
+    For(Duration),
+}
+
+/// Adaptive limiter shared by every request to one provider
+#[derive(Debug)]
+pub(crate) struct ProviderRateLimiter {
+    limits: ProviderLimits,
+    state: Mutex<LimiterState>,
+    released: Notify,
+}
+
+/// Held while a provider call runs; releases its concurrency slot when dropped
+pub(crate) struct ProviderPermit<'a> {
+    limiter: &'a ProviderRateLimiter,
+    reserved_tokens: u64,
+}
+
+impl ProviderRateLimiter {
+    pub fn new(limits: ProviderLimits) -> Self {
+        let now = Instant::now();
+        Self {
+            limits,
+            state: Mutex::new(LimiterState {
+                in_flight: 0,
+                concurrency: limits.max_concurrency,
+                successes: 0,
+                requests: limits
+                    .requests_per_minute
+                    .map(|limit| TokenBucket::per_minute(limit, now)),
+                tokens: limits
+                    .tokens_per_minute
+                    .map(|limit| TokenBucket::per_minute(limit, now)),
+                blocked_until: None,
+                last_start: None,
+            }),
+            released: Notify::new(),
+        }
+    }
+
+    /// Current adaptive concurrency limit
+    pub fn concurrency(&self) -> usize {
+        self.state.lock().concurrency
+    }
+
+    /// Wait for a slot, then reserve one request and `estimated_tokens` tokens
+    pub async fn acquire(&self, estimated_tokens: u64) -> ProviderPermit<'_> {
+        loop {
+            let released = self.released.notified();
+            let wait = match self.try_start(estimated_tokens, Instant::now()) {
+                Ok(()) => {
+                    return ProviderPermit {
+                        limiter: self,
+                        reserved_tokens: estimated_tokens,
+                    }
+                }
+                Err(wait) => wait,
+            };
+            match wait {
+                Wait::Release => released.await,
+                Wait::For(duration) => {
+                    tokio::select! {
+                        _ = sleep(duration) => {}
+                        _ = released => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn try_start(&self, estimated_tokens: u64, now: Instant) -> Result<(), Wait> {
+        let mut state = self.state.lock();
+        if let Some(until) = state.blocked_until {
+            if until > now {
+                return Err(Wait::For(until - now));
+            }
+            state.blocked_until = None;
+        }
+        if state.in_flight >= state.concurrency {
+            return Err(Wait::Release);
+        }
+        if let (Some(interval), Some(last)) = (self.limits.min_interval, state.last_start) {
+            let elapsed = now.saturating_duration_since(last);
+            if elapsed < interval {
+                return Err(Wait::For(interval - elapsed));
+            }
+        }
+        let request_wait = state
+            .requests
+            .as_mut()
+            .and_then(|bucket| bucket.wait_for(1.0, now));
+        let token_wait = state
+            .tokens
+            .as_mut()
+            .and_then(|bucket| bucket.wait_for(estimated_tokens as f64, now));
+        if let Some(wait) = request_wait.max(token_wait) {
+            return Err(Wait::For(wait));
+        }
+
+        if let Some(bucket) = state.requests.as_mut() {
+            bucket.take(1.0);
+        }
+        if let Some(bucket) = state.tokens.as_mut() {
+            bucket.take(estimated_tokens as f64);
+        }
+        state.in_flight += 1;
+        state.last_start = Some(now);
+        Ok(())
+    }
+}
+
+impl ProviderPermit<'_> {
+    /// Settle the token reservation against actual usage and grow concurrency after a
+    /// window of successes
+    pub fn succeeded(&self, used_tokens: u64) {
+        let mut state = self.limiter.state.lock();
+        if let Some(bucket) = state.tokens.as_mut() {
+            bucket.take(used_tokens as f64 - self.reserved_tokens as f64);
+        }
+        state.successes += 1;
+        if state.successes >= state.concurrency {
+            state.successes = 0;
+            state.concurrency = (state.concurrency + 1).min(self.limiter.limits.max_concurrency);
+        }
+    }
+
+    /// Halve concurrency and pause the provider for the wait it requested
+    pub fn rate_limited(&self, retry_after: Option<Duration>) {
+        let mut state = self.limiter.state.lock();
+        state.successes = 0;
+        state.concurrency = (state.concurrency / 2).max(1);
+        if let Some(wait) = retry_after {
+            let until = Instant::now() + wait;
+            state.blocked_until = Some(state.blocked_until.map_or(until, |b| b.max(until)));
+        }
+    }
+}
+
+impl Drop for ProviderPermit<'_> {
+    fn drop(&mut self) {
+        self.limiter.state.lock().in_flight -= 1;
+        self.limiter.released.notify_waiters();
+    }
+}
+
+/// Exponential backoff with jitter: a random delay in `[d/2, d]` where
+/// `d = base * 2^attempt`, capped at `max`
+pub(crate) fn backoff_delay(base: Duration, max: Duration, attempt: usize) -> Duration {
+    let factor = 1u32 << attempt.min(16);
+    let delay = base.saturating_mul(factor).min(max);
+    let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+    delay.mul_f64(0.5 + jitter / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_concurrency: usize) -> ProviderLimits {
+        ProviderLimits {
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrency,
+            min_interval: None,
+        }
+    }
+
+    #[test]
+    fn concurrency_halves_on_rate_limit_and_recovers_with_successes() {
+        let limiter = ProviderRateLimiter::new(limits(8));
+        let now = Instant::now();
+        assert!(limiter.try_start(0, now).is_ok());
+        let permit = ProviderPermit {
+            limiter: &limiter,
+            reserved_tokens: 0,
+        };
+        permit.rate_limited(None);
+        assert_eq!(limiter.concurrency(), 4);
+        permit.rate_limited(None);
+        assert_eq!(limiter.concurrency(), 2);
+        for _ in 0..2 {
+            permit.succeeded(0);
+        }
+        assert_eq!(limiter.concurrency(), 3);
+        drop(permit);
+        assert_eq!(limiter.state.lock().in_flight, 0);
+    }
+
+    #[test]
+    fn buckets_and_retry_after_delay_new_requests() {
+        let limiter = ProviderRateLimiter::new(ProviderLimits {
+            requests_per_minute: Some(2),
+            tokens_per_minute: Some(600),
+            ..limits(10)
+        });
+        let now = Instant::now();
+        assert!(limiter.try_start(100, now).is_ok());
+        assert!(limiter.try_start(100, now).is_ok());
+        // Request bucket empty: one request refills in 30s
+        match limiter.try_start(100, now) {
+            Err(Wait::For(wait)) => assert!(wait > Duration::from_secs(29)),
+            _ => panic!("expected a timed wait"),
+        }
+
+        let permit = ProviderPermit {
+            limiter: &limiter,
+            reserved_tokens: 0,
+        };
+        permit.rate_limited(Some(Duration::from_secs(5)));
+        match limiter.try_start(0, Instant::now()) {
+            Err(Wait::For(wait)) => assert!(wait <= Duration::from_secs(5)),
+            _ => panic!("expected a timed wait"),
+        }
+        drop(permit);
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_within_jitter_bounds() {
+        let base = Duration::from_millis(100);
+        let max = Duration::from_secs(1);
+        for attempt in 0..6 {
+            let full = base.saturating_mul(1 << attempt).min(max);
+            let delay = backoff_delay(base, max, attempt);
+            assert!(delay >= full / 2 && delay <= full, "{:?}", delay);
+        }
+    }
+}
@@ -0,0 +1,555 @@
+//! Semantic and lexical search over head frames.
+//!
+//! The semantic index holds one embedding per active head frame, keyed by FrameID, in a
+//! bincode file next to the store. `put_frame` only appends the new head to a pending
+//! journal; the next search embeds the journaled frames, drops the entries they replace,
+//! and saves the index. Changing the embedder (provider or model) rebuilds the index from
+//! all heads. The lexical (BM25) index in `lexical` is kept the same way and needs no
+//! provider.
+//!
+//! ```toml
+//! [context.search]
+//! provider = "openai-embeddings"   # unset: local hashing embedder
+//! batch_size = 32
+//! ```
+
+use crate::api::ContextApi;
+use crate::error::{ApiError, StorageError};
+use crate::provider::embedding::cosine_similarity;
+use crate::provider::template::TemplateClient;
+use crate::provider::ModelProviderClient;
+use crate::types::{FrameID, NodeID};
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Frame text beyond this many characters is not embedded
+const MAX_EMBEDDED_CHARS: usize = 8000;
+/// Longest snippet shown per hit
+const MAX_SNIPPET_CHARS: usize = 160;
+
+pub mod lexical;
+
+pub use lexical::LexicalIndex;
+
+fn default_batch_size() -> usize {
+    32
+}
+
+/// Semantic search settings (`[context.search]`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SearchConfig {
+    /// Provider whose `embed` builds the index; unset uses the local hashing embedder
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Texts sent per embedding request
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            provider: None,
+            batch_size: default_batch_size(),
+        }
+    }
+}
+
+/// What to search for and how
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchRequest {
+    pub query: String,
+    pub top_k: usize,
+    pub frame_type: Option<String>,
+    pub agent_id: Option<String>,
+    /// Rank with the BM25 index instead of embeddings
+    pub lexical: bool,
+}
+
+impl SearchRequest {
+    fn matches(&self, frame_type: &str, agent_id: Option<&str>) -> bool {
+        self.frame_type.as_deref().is_none_or(|t| t == frame_type)
+            && self.agent_id.as_deref().is_none_or(|a| Some(a) == agent_id)
+    }
+}
+
+/// One search result
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    /// Node path relative to the workspace root
+    pub path: String,
+    #[serde(serialize_with = "serialize_hex")]
+    pub node_id: NodeID,
+    #[serde(serialize_with = "serialize_hex")]
+    pub frame_id: FrameID,
+    pub frame_type: String,
+    pub agent_id: Option<String>,
+    pub score: f32,
+    pub snippet: String,
+}
+
+fn serialize_hex<S: serde::Serializer>(id: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(id))
+}
+
+/// Search results with what the refresh before the query did
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchReport {
+    pub query: String,
+    /// `semantic` or `lexical`
+    pub mode: String,
+    /// `provider/model` of the embedder, for semantic searches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedder: Option<String>,
+    pub refresh: RefreshStats,
+    pub hits: Vec<SearchHit>,
+}
+
+/// Counts from bringing an index up to date
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RefreshStats {
+    /// Frames (re)indexed in this refresh
+    pub updated: usize,
+    /// Frames in the index afterwards
+    pub indexed: usize,
+    /// There was no usable index (or the embedder changed), so every head was indexed
+    pub rebuilt: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexData {
+    /// `provider/model` the vectors were made with
+    embedder: String,
+    entries: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexEntry {
+    node_id: NodeID,
+    frame_id: FrameID,
+    frame_type: String,
+    agent_id: Option<String>,
+    vector: Vec<f32>,
+}
+
+/// Append-only file of index updates waiting to be applied
+#[derive(Debug)]
+pub(crate) struct PendingJournal {
+    path: PathBuf,
+    /// Serializes appends against trimming
+    lock: Mutex<()>,
+}
+
+impl PendingJournal {
+    /// Journal for the index at `index_path`: a `.pending` file beside it
+    pub(crate) fn for_index(index_path: &Path) -> Self {
+        Self {
+            path: index_path.with_extension("pending"),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn append(&self, line: &str) -> Result<(), StorageError> {
+        let _guard = self.lock.lock();
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(format!("{}\n", line).as_bytes())?;
+        Ok(())
+    }
+
+    pub(crate) fn read(&self) -> Result<String, ApiError> {
+        let _guard = self.lock.lock();
+        read_optional(&self.path)
+    }
+
+    /// Drop the first `processed` bytes, keeping lines appended since they were read
+    pub(crate) fn trim(&self, processed: usize) -> Result<(), ApiError> {
+        let _guard = self.lock.lock();
+        let journal = read_optional(&self.path)?;
+        let rest = journal.get(processed..).unwrap_or_default();
+        let result = if rest.is_empty() {
+            fs::remove_file(&self.path).or_else(|e| match e.kind() {
+                ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            })
+        } else {
+            fs::write(&self.path, rest)
+        };
+        result.map_err(|e| StorageError::IoError(e).into())
+    }
+}
+
+/// Vector index file plus the journal of heads waiting to be embedded
+#[derive(Debug)]
+pub struct SearchIndex {
+    path: PathBuf,
+    journal: PendingJournal,
+}
+
+impl SearchIndex {
+    /// Index at `path`; the journal is a `.pending` file beside it
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            journal: PendingJournal::for_index(&path),
+            path,
+        }
+    }
+
+    /// Journal a frame that just became the head of its node and type
+    pub fn record_head(&self, node_id: NodeID, frame_id: FrameID) -> Result<(), StorageError> {
+        self.journal.append(&format!(
+            "{} {}",
+            hex::encode(node_id),
+            hex::encode(frame_id)
+        ))
+    }
+
+    /// Embed journaled heads (or every head, when the embedder changed) and save
+    pub async fn refresh(
+        &self,
+        api: &ContextApi,
+        workspace_root: &Path,
+        embedder: &dyn ModelProviderClient,
+        embedder_id: &str,
+        batch_size: usize,
+    ) -> Result<RefreshStats, ApiError> {
+        let journal = self.journal.read()?;
+        let mut data: IndexData = load_index(&self.path)?.unwrap_or_default();
+        let rebuilt = data.embedder != embedder_id;
+        let candidates: Vec<(NodeID, FrameID)> = if rebuilt {
+            data = IndexData {
+                embedder: embedder_id.to_string(),
+                entries: Vec::new(),
+            };
+            let head_index = api.head_index().read();
+            head_index
+                .get_all_node_ids()
+                .into_iter()
+                .flat_map(|node_id| {
+                    head_index
+                        .get_all_heads_for_node(&node_id)
+                        .into_iter()
+                        .map(move |frame_id| (node_id, frame_id))
+                })
+                .collect()
+        } else {
+            parse_journal(&journal)
+        };
+
+        let mut seen = HashSet::new();
+        let mut pending = Vec::new();
+        for (node_id, frame_id) in candidates {
+            if !seen.insert(frame_id) {
+                continue;
+            }
+            let Some(frame) = api.frame_storage().get(&frame_id).map_err(ApiError::from)? else {
+                continue;
+            };
+            let is_head = api
+                .head_index()
+                .read()
+                .get_active_head(&node_id, &frame.frame_type)
+                .map_err(ApiError::from)?
+                == Some(frame_id);
+            let text = frame.text_content().unwrap_or_default();
+            if !is_head || frame.is_deleted() || text.trim().is_empty() {
+                continue;
+            }
+            data.entries.retain(|entry| {
+                entry.frame_id != frame_id
+                    && (entry.node_id != node_id || entry.frame_type != frame.frame_type)
+            });
+            let path = node_path(api, workspace_root, &node_id)?;
+            let text: String = format!("{}\n\n{}", path, text)
+                .chars()
+                .take(MAX_EMBEDDED_CHARS)
+                .collect();
+            let agent_id = frame.agent_id().map(str::to_string);
+            pending.push((node_id, frame_id, frame.frame_type, agent_id, text));
+        }
+
+        let updated = pending.len();
+        for batch in pending.chunks(batch_size.max(1)) {
+            let texts = batch.iter().map(|(.., text)| text.clone()).collect();
+            let vectors = embedder.embed(texts).await?;
+            if vectors.len() != batch.len() {
+                return Err(ApiError::ProviderError(format!(
+                    "Embedder returned {} vectors for {} texts",
+                    vectors.len(),
+                    batch.len()
+                )));
+            }
+            for ((node_id, frame_id, frame_type, agent_id, _), vector) in batch.iter().zip(vectors)
+            {
+                data.entries.push(IndexEntry {
+                    node_id: *node_id,
+                    frame_id: *frame_id,
+                    frame_type: frame_type.clone(),
+                    agent_id: agent_id.clone(),
+                    vector,
+                });
+            }
+        }
+        save_index(&self.path, &data)?;
+        self.journal.trim(journal.len())?;
+        Ok(RefreshStats {
+            updated,
+            indexed: data.entries.len(),
+            rebuilt,
+        })
+    }
+
+    /// Best-scoring indexed head frames for the request; call `refresh` first
+    pub async fn search(
+        &self,
+        api: &ContextApi,
+        workspace_root: &Path,
+        embedder: &dyn ModelProviderClient,
+        request: &SearchRequest,
+    ) -> Result<Vec<SearchHit>, ApiError> {
+        let data: IndexData = load_index(&self.path)?.unwrap_or_default();
+        let query_vector = embedder
+            .embed(vec![request.query.clone()])
+            .await?
+            .pop()
+            .ok_or_else(|| ApiError::ProviderError("Embedder returned no vectors".to_string()))?;
+
+        let mut scored: Vec<(f32, &IndexEntry)> = {
+            let head_index = api.head_index().read();
+            data.entries
+                .iter()
+                .filter(|entry| request.matches(&entry.frame_type, entry.agent_id.as_deref()))
+                .filter(|entry| {
+                    head_index
+                        .get_active_head(&entry.node_id, &entry.frame_type)
+                        .ok()
+                        .flatten()
+                        == Some(entry.frame_id)
+                })
+                .map(|entry| (cosine_similarity(&query_vector, &entry.vector), entry))
+                .collect()
+        };
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(request.top_k);
+
+        let mut hits = Vec::with_capacity(scored.len());
+        for (score, entry) in scored {
+            let Some(frame) = api
+                .frame_storage()
+                .get(&entry.frame_id)
+                .map_err(ApiError::from)?
+            else {
+                continue;
+            };
+            hits.push(SearchHit {
+                path: node_path(api, workspace_root, &entry.node_id)?,
+                node_id: entry.node_id,
+                frame_id: entry.frame_id,
+                frame_type: entry.frame_type.clone(),
+                agent_id: frame.agent_id().map(str::to_string),
+                score,
+                snippet: snippet(&frame.text_content().unwrap_or_default(), &request.query),
+            });
+        }
+        Ok(hits)
+    }
+}
+
+/// Refresh the requested index and search it; semantic searches use the configured
+/// embedder
+pub fn search_for_cli(
+    api: &ContextApi,
+    workspace_root: &Path,
+    config: &SearchConfig,
+    request: &SearchRequest,
+) -> Result<SearchReport, ApiError> {
+    let root = crate::tree::path::canonicalize_path(workspace_root)
+        .unwrap_or_else(|_| workspace_root.to_path_buf());
+    if request.lexical {
+        let index = api
+            .lexical_index()
+            .ok_or_else(|| ApiError::ConfigError("No lexical index is attached".to_string()))?;
+        let refresh = index.refresh(api, &root)?;
+        return Ok(SearchReport {
+            query: request.query.clone(),
+            mode: "lexical".to_string(),
+            embedder: None,
+            refresh,
+            hits: index.search(api, &root, request)?,
+        });
+    }
+
+    let index = api
+        .search_index()
+        .ok_or_else(|| ApiError::ConfigError("No search index is attached".to_string()))?;
+    let (embedder, provider): (Box<dyn ModelProviderClient>, &str) = match &config.provider {
+        Some(name) => (api.provider_registry().read().create_client(name)?, name),
+        None => (
+            Box::new(TemplateClient::new("hashing".to_string())),
+            "template",
+        ),
+    };
+    let embedder_id = format!("{}/{}", provider, embedder.model_name());
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| ApiError::ConfigError(format!("Failed to start runtime: {}", e)))?;
+    runtime.block_on(async {
+        let refresh = index
+            .refresh(
+                api,
+                &root,
+                embedder.as_ref(),
+                &embedder_id,
+                config.batch_size,
+            )
+            .await?;
+        let hits = index.search(api, &root, embedder.as_ref(), request).await?;
+        Ok(SearchReport {
+            query: request.query.clone(),
+            mode: "semantic".to_string(),
+            embedder: Some(embedder_id.clone()),
+            refresh,
+            hits,
+        })
+    })
+}
+
+/// Saved index at `path`; `None` when missing or unreadable, so it is rebuilt
+fn load_index<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, ApiError> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(bincode::deserialize(&bytes).ok()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(StorageError::IoError(e).into()),
+    }
+}
+
+/// Write an index through a temporary file and rename
+fn save_index<T: Serialize>(path: &Path, data: &T) -> Result<(), ApiError> {
+    let bytes = bincode::serialize(data).map_err(|e| {
+        ApiError::StorageError(StorageError::InvalidPath(format!(
+            "Failed to serialize search index: {}",
+            e
+        )))
+    })?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(StorageError::IoError)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes).map_err(StorageError::IoError)?;
+    fs::rename(&tmp, path).map_err(StorageError::IoError)?;
+    Ok(())
+}
+
+fn read_optional(path: &Path) -> Result<String, ApiError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(StorageError::IoError(e).into()),
+    }
+}
+
+fn decode_id(hex_id: &str) -> Option<[u8; 32]> {
+    hex::decode(hex_id).ok()?.try_into().ok()
+}
+
+/// Journal lines are `<node hex> <frame hex>`; malformed lines are skipped
+fn parse_journal(journal: &str) -> Vec<(NodeID, FrameID)> {
+    journal
+        .lines()
+        .filter_map(|line| {
+            let (node, frame) = line.trim().split_once(' ')?;
+            Some((decode_id(node)?, decode_id(frame)?))
+        })
+        .collect()
+}
+
+fn node_path(
+    api: &ContextApi,
+    workspace_root: &Path,
+    node_id: &NodeID,
+) -> Result<String, ApiError> {
+    let Some(record) = api.node_store().get(node_id).map_err(ApiError::from)? else {
+        return Ok(hex::encode(node_id));
+    };
+    Ok(match record.path.strip_prefix(workspace_root) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => relative.display().to_string(),
+        Err(_) => record.path.display().to_string(),
+    })
+}
+
+/// Line sharing the most words with the query, else the first non-empty line
+fn snippet(text: &str, query: &str) -> String {
+    let words: HashSet<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let overlap = |line: &str| {
+        line.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| words.contains(&word.to_lowercase()))
+            .count()
+    };
+    let lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let best = lines
+        .clone()
+        .enumerate()
+        .max_by_key(|(index, line)| (overlap(line), std::cmp::Reverse(*index)))
+        .filter(|(_, line)| overlap(line) > 0)
+        .map(|(_, line)| line);
+    let line = best.or_else(|| lines.clone().next()).unwrap_or_default();
+    let mut snippet: String = line.chars().take(MAX_SNIPPET_CHARS).collect();
+    if line.chars().count() > MAX_SNIPPET_CHARS {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippet_prefers_the_line_matching_the_query() {
+        let text = "# src/config.rs\n\nRenders output.\nLoads the config file from disk.\n";
+        assert_eq!(snippet(text, "config loading"), "# src/config.rs");
+        assert_eq!(
+            snippet(text, "read file from disk"),
+            "Loads the config file from disk."
+        );
+        assert_eq!(snippet(text, "unrelated"), "# src/config.rs");
+        assert!(snippet(&"x".repeat(400), "x").ends_with('…'));
+    }
+
+    #[test]
+    fn journal_is_appended_and_trimmed() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let index = SearchIndex::new(dir.path().join("search_index.bin"));
+        index.record_head([1; 32], [2; 32]).unwrap();
+        let processed = index.journal.read().unwrap().len();
+        index.record_head([3; 32], [4; 32]).unwrap();
+        assert_eq!(
+            parse_journal(&index.journal.read().unwrap()),
+            vec![([1; 32], [2; 32]), ([3; 32], [4; 32])]
+        );
+
+        index.journal.trim(processed).unwrap();
+        assert_eq!(
+            parse_journal(&index.journal.read().unwrap()),
+            vec![([3; 32], [4; 32])]
+        );
+        index.journal.trim(processed * 2).unwrap();
+        assert!(!index.journal.path.exists());
+        assert!(parse_journal("not hex\nab cd\n").is_empty());
+    }
+}
@@ -0,0 +1,413 @@
+//! Lexical (BM25) search over head frame text and node paths.
+//!
+//! An inverted index maps each lowercase alphanumeric token to the frames containing it
+//! and its positions there, so quoted phrases match consecutive tokens. A document is one
+//! active head frame: its node path followed by its text. `put_frame`, tombstone, and
+//! restore journal the node; the next search re-reads that node's active heads from
+//! `FrameStorage`. No provider is involved, so it works fully offline.
+
+use super::{
+    decode_id, load_index, node_path, save_index, snippet, PendingJournal, RefreshStats, SearchHit,
+    SearchRequest,
+};
+use crate::api::ContextApi;
+use crate::error::{ApiError, StorageError};
+use crate::types::{FrameID, NodeID};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Bumped when the saved layout changes; older indexes are rebuilt
+const INDEX_VERSION: u32 = 1;
+/// BM25 term-frequency saturation
+const K1: f64 = 1.2;
+/// BM25 length normalization
+const B: f64 = 0.75;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Document {
+    node_id: NodeID,
+    frame_type: String,
+    agent_id: Option<String>,
+    /// Token count, path included
+    length: u32,
+    /// Distinct tokens, for removing the document's postings
+    terms: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LexicalData {
+    version: u32,
+    docs: HashMap<FrameID, Document>,
+    /// Token to the frames containing it and the (sorted) positions there
+    postings: HashMap<String, HashMap<FrameID, Vec<u32>>>,
+}
+
+/// Free terms plus quoted phrases; phrase words also count as terms
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ParsedQuery {
+    terms: Vec<String>,
+    phrases: Vec<Vec<String>>,
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Split a query on double quotes: quoted runs of two or more words are phrases
+fn parse_query(query: &str) -> ParsedQuery {
+    let mut parsed = ParsedQuery::default();
+    for (index, part) in query.split('"').enumerate() {
+        let tokens: Vec<String> = tokenize(part).collect();
+        if index % 2 == 1 && tokens.len() > 1 {
+            parsed.phrases.push(tokens.clone());
+        }
+        parsed.terms.extend(tokens);
+    }
+    parsed
+}
+
+impl LexicalData {
+    fn new() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            docs: HashMap::new(),
+            postings: HashMap::new(),
+        }
+    }
+
+    /// Index `path` then `text` as one document; a position gap keeps phrases from
+    /// spanning the two
+    fn insert(&mut self, frame_id: FrameID, mut document: Document, path: &str, text: &str) {
+        self.remove(&frame_id);
+        let mut positions: HashMap<String, Vec<u32>> = HashMap::new();
+        let path_tokens = tokenize(path).enumerate();
+        let text_tokens = tokenize(text).enumerate().map(|(i, t)| (i + 1, t));
+        let mut length = 0;
+        let mut offset = 0;
+        for (position, token) in path_tokens {
+            positions.entry(token).or_default().push(position as u32);
+            length += 1;
+            offset = position + 1;
+        }
+        for (position, token) in text_tokens {
+            positions
+                .entry(token)
+                .or_default()
+                .push((offset + position) as u32);
+            length += 1;
+        }
+        document.length = length;
+        document.terms = positions.keys().cloned().collect();
+        for (token, token_positions) in positions {
+            self.postings
+                .entry(token)
+                .or_default()
+                .insert(frame_id, token_positions);
+        }
+        self.docs.insert(frame_id, document);
+    }
+
+    fn remove(&mut self, frame_id: &FrameID) {
+        let Some(document) = self.docs.remove(frame_id) else {
+            return;
+        };
+        for term in &document.terms {
+            if let Some(frames) = self.postings.get_mut(term) {
+                frames.remove(frame_id);
+                if frames.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+    }
+
+    fn contains_phrase(&self, frame_id: &FrameID, phrase: &[String]) -> bool {
+        let positions: Option<Vec<&Vec<u32>>> = phrase
+            .iter()
+            .map(|term| self.postings.get(term)?.get(frame_id))
+            .collect();
+        let Some(positions) = positions else {
+            return false;
+        };
+        positions[0].iter().any(|&start| {
+            positions[1..]
+                .iter()
+                .enumerate()
+                .all(|(i, next)| next.binary_search(&(start + i as u32 + 1)).is_ok())
+        })
+    }
+
+    /// BM25 scores of documents passing `accept` that contain a term and every phrase
+    fn rank(&self, query: &ParsedQuery, accept: impl Fn(&Document) -> bool) -> Vec<(FrameID, f64)> {
+        let count = self.docs.len() as f64;
+        let total_length: u64 = self.docs.values().map(|d| d.length as u64).sum();
+        let average_length = (total_length as f64 / count.max(1.0)).max(1.0);
+        let terms: HashSet<&String> = query.terms.iter().collect();
+
+        let mut scores: HashMap<FrameID, f64> = HashMap::new();
+        for term in terms {
+            let Some(frames) = self.postings.get(term) else {
+                continue;
+            };
+            let frequency = frames.len() as f64;
+            let idf = ((count - frequency + 0.5) / (frequency + 0.5) + 1.0).ln();
+            for (frame_id, positions) in frames {
+                let document = &self.docs[frame_id];
+                if !accept(document) {
+                    continue;
+                }
+                let tf = positions.len() as f64;
+                let norm = 1.0 - B + B * document.length as f64 / average_length;
+                *scores.entry(*frame_id).or_default() += idf * tf * (K1 + 1.0) / (tf + K1 * norm);
+            }
+        }
+        let mut ranked: Vec<(FrameID, f64)> = scores
+            .into_iter()
+            .filter(|(frame_id, _)| {
+                query
+                    .phrases
+                    .iter()
+                    .all(|phrase| self.contains_phrase(frame_id, phrase))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked
+    }
+}
+
+/// BM25 index file plus the journal of nodes whose heads changed
+#[derive(Debug)]
+pub struct LexicalIndex {
+    path: PathBuf,
+    journal: PendingJournal,
+}
+
+impl LexicalIndex {
+    /// Index at `path`; the journal is a `.pending` file beside it
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            journal: PendingJournal::for_index(&path),
+            path,
+        }
+    }
+
+    /// Journal a node whose heads were written, tombstoned, or restored
+    pub fn record_node(&self, node_id: NodeID) -> Result<(), StorageError> {
+        self.journal.append(&hex::encode(node_id))
+    }
+
+    /// Re-index journaled nodes (or every node, when there is no usable index) and save
+    pub fn refresh(
+        &self,
+        api: &ContextApi,
+        workspace_root: &Path,
+    ) -> Result<RefreshStats, ApiError> {
+        let journal = self.journal.read()?;
+        let loaded =
+            load_index::<LexicalData>(&self.path)?.filter(|data| data.version == INDEX_VERSION);
+        let rebuilt = loaded.is_none();
+        let mut data = loaded.unwrap_or_else(LexicalData::new);
+        if !rebuilt && journal.is_empty() {
+            return Ok(RefreshStats {
+                updated: 0,
+                indexed: data.docs.len(),
+                rebuilt,
+            });
+        }
+        let nodes: Vec<NodeID> = if rebuilt {
+            api.head_index().read().get_all_node_ids()
+        } else {
+            journal
+                .lines()
+                .filter_map(|line| decode_id(line.trim()))
+                .collect()
+        };
+
+        let mut by_node: HashMap<NodeID, Vec<FrameID>> = HashMap::new();
+        for (frame_id, document) in &data.docs {
+            by_node.entry(document.node_id).or_default().push(*frame_id);
+        }
+        let mut seen = HashSet::new();
+        let mut updated = 0;
+        for node_id in nodes {
+            if !seen.insert(node_id) {
+                continue;
+            }
+            for frame_id in by_node.remove(&node_id).unwrap_or_default() {
+                data.remove(&frame_id);
+            }
+            let heads = api.head_index().read().get_all_heads_for_node(&node_id);
+            for frame_id in heads {
+                let Some(frame) = api.frame_storage().get(&frame_id).map_err(ApiError::from)?
+                else {
+                    continue;
+                };
+                let is_head = api
+                    .head_index()
+                    .read()
+                    .get_active_head(&node_id, &frame.frame_type)
+                    .map_err(ApiError::from)?
+                    == Some(frame_id);
+                let text = frame.text_content().unwrap_or_default();
+                if !is_head || frame.is_deleted() || text.trim().is_empty() {
+                    continue;
+                }
+                let path = node_path(api, workspace_root, &node_id)?;
+                let document = Document {
+                    node_id,
+                    frame_type: frame.frame_type.clone(),
+                    agent_id: frame.agent_id().map(str::to_string),
+                    length: 0,
+                    terms: Vec::new(),
+                };
+                data.insert(frame_id, document, &path, &text);
+                updated += 1;
+            }
+        }
+        save_index(&self.path, &data)?;
+        self.journal.trim(journal.len())?;
+        Ok(RefreshStats {
+            updated,
+            indexed: data.docs.len(),
+            rebuilt,
+        })
+    }
+
+    /// Best BM25 matches for the request; call `refresh` first
+    pub fn search(
+        &self,
+        api: &ContextApi,
+        workspace_root: &Path,
+        request: &SearchRequest,
+    ) -> Result<Vec<SearchHit>, ApiError> {
+        let query = parse_query(&request.query);
+        if query.terms.is_empty() {
+            return Err(ApiError::ConfigError(format!(
+                "Search query '{}' has no words to match",
+                request.query
+            )));
+        }
+        let data = load_index::<LexicalData>(&self.path)?
+            .filter(|data| data.version == INDEX_VERSION)
+            .unwrap_or_else(LexicalData::new);
+        let ranked = {
+            let head_index = api.head_index().read();
+            data.rank(&query, |document| {
+                request.matches(&document.frame_type, document.agent_id.as_deref())
+            })
+            .into_iter()
+            .filter(|(frame_id, _)| {
+                let document = &data.docs[frame_id];
+                head_index
+                    .get_active_head(&document.node_id, &document.frame_type)
+                    .ok()
+                    .flatten()
+                    == Some(*frame_id)
+            })
+            .take(request.top_k)
+            .collect::<Vec<_>>()
+        };
+
+        let mut hits = Vec::with_capacity(ranked.len());
+        for (frame_id, score) in ranked {
+            let Some(frame) = api.frame_storage().get(&frame_id).map_err(ApiError::from)? else {
+                continue;
+            };
+            let document = &data.docs[&frame_id];
+            hits.push(SearchHit {
+                path: node_path(api, workspace_root, &document.node_id)?,
+                node_id: document.node_id,
+                frame_id,
+                frame_type: document.frame_type.clone(),
+                agent_id: document.agent_id.clone(),
+                score: score as f32,
+                snippet: snippet(&frame.text_content().unwrap_or_default(), &request.query),
+            });
+        }
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(frame_type: &str) -> Document {
+        Document {
+            node_id: [0; 32],
+            frame_type: frame_type.to_string(),
+            agent_id: Some("writer".to_string()),
+            length: 0,
+            terms: Vec::new(),
+        }
+    }
+
+    fn sample() -> LexicalData {
+        let mut data = LexicalData::new();
+        data.insert(
+            [1; 32],
+            document("context"),
+            "src/config.rs",
+            "Loads the config file. Config values are cached.",
+        );
+        data.insert(
+            [2; 32],
+            document("context"),
+            "src/render.rs",
+            "Renders the progress bar from config.",
+        );
+        data.insert(
+            [3; 32],
+            document("summary"),
+            "src/file.rs",
+            "Reads a file and loads it into memory.",
+        );
+        data
+    }
+
+    #[test]
+    fn parses_terms_and_quoted_phrases() {
+        assert_eq!(
+            parse_query(r#"cache "Config File" x "single""#),
+            ParsedQuery {
+                terms: vec!["cache", "config", "file", "x", "single"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+                phrases: vec![vec!["config".to_string(), "file".to_string()]],
+            }
+        );
+    }
+
+    #[test]
+    fn ranks_by_bm25_with_phrases_and_filters() {
+        let mut data = sample();
+        let ranked = |data: &LexicalData, query: &str| -> Vec<FrameID> {
+            data.rank(&parse_query(query), |_| true)
+                .into_iter()
+                .map(|(frame_id, _)| frame_id)
+                .collect()
+        };
+        assert_eq!(ranked(&data, "config"), vec![[1; 32], [2; 32]]);
+        assert_eq!(ranked(&data, "\"config file\""), vec![[1; 32]]);
+        assert_eq!(ranked(&data, "\"config loads\""), Vec::<FrameID>::new());
+        // Path and text are separate: "rs loads" never matches across them
+        assert_eq!(ranked(&data, "\"rs loads\""), Vec::<FrameID>::new());
+        assert_eq!(ranked(&data, "\"src render\""), vec![[2; 32]]);
+
+        let summaries: Vec<FrameID> = data
+            .rank(&parse_query("loads"), |d| d.frame_type == "summary")
+            .into_iter()
+            .map(|(frame_id, _)| frame_id)
+            .collect();
+        assert_eq!(summaries, vec![[3; 32]]);
+
+        data.remove(&[1; 32]);
+        assert_eq!(ranked(&data, "config"), vec![[2; 32]]);
+        assert!(!data.postings.contains_key("cached"));
+    }
+}
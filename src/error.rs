@@ -75,12 +75,25 @@ pub enum ApiError {
     #[error("Provider authentication failed: {0}")]
     ProviderAuthFailed(String),
 
-    #[error("Provider rate limit exceeded: {0}")]
-    ProviderRateLimit(String),
+    #[error("Provider rate limit exceeded: {message}")]
+    ProviderRateLimit {
+        message: String,
+        /// Wait requested by the provider via `Retry-After` or rate-limit reset headers
+        retry_after: Option<std::time::Duration>,
+    },
 
     #[error("Provider model not found: {0}")]
     ProviderModelNotFound(String),
 
+    #[error("Replay cassette has no recording for this request: {0}")]
+    ReplayMismatch(String),
+
+    #[error("Response does not match the output schema: {0}")]
+    StructuredOutputInvalid(String),
+
+    #[error("Tool-calling limit reached: {0}")]
+    ToolLimitExceeded(String),
+
     #[error("Storage error: {0}")]
     StorageError(#[from] StorageError),
 
@@ -90,6 +103,15 @@ pub enum ApiError {
     #[error("Generation failed: {0}")]
     GenerationFailed(String),
 
+    #[error("Generation budget exceeded: {0}")]
+    BudgetExceeded(String),
+
+    #[error(
+        "File changed since scan: {0}. Run `meld scan` to update the tree, or enable \
+         system.storage.blob_cache to generate from scanned content."
+    )]
+    SourceChanged(std::path::PathBuf),
+
     #[error(
         "Path not found in tree: {0}. Run `meld scan` to update tree or start `meld watch`."
     )]
@@ -120,13 +142,26 @@ impl Clone for ApiError {
                 ApiError::ProviderRequestFailed(message.clone())
             }
             ApiError::ProviderAuthFailed(message) => ApiError::ProviderAuthFailed(message.clone()),
-            ApiError::ProviderRateLimit(message) => ApiError::ProviderRateLimit(message.clone()),
+            ApiError::ProviderRateLimit {
+                message,
+                retry_after,
+            } => ApiError::ProviderRateLimit {
+                message: message.clone(),
+                retry_after: *retry_after,
+            },
             ApiError::ProviderModelNotFound(message) => {
                 ApiError::ProviderModelNotFound(message.clone())
             }
+            ApiError::ReplayMismatch(message) => ApiError::ReplayMismatch(message.clone()),
+            ApiError::StructuredOutputInvalid(message) => {
+                ApiError::StructuredOutputInvalid(message.clone())
+            }
+            ApiError::ToolLimitExceeded(message) => ApiError::ToolLimitExceeded(message.clone()),
             ApiError::StorageError(err) => ApiError::StorageError(err.clone()),
             ApiError::ConfigError(message) => ApiError::ConfigError(message.clone()),
             ApiError::GenerationFailed(message) => ApiError::GenerationFailed(message.clone()),
+            ApiError::BudgetExceeded(message) => ApiError::BudgetExceeded(message.clone()),
+            ApiError::SourceChanged(path) => ApiError::SourceChanged(path.clone()),
             ApiError::PathNotInTree(path) => ApiError::PathNotInTree(path.clone()),
         }
     }
@@ -140,6 +140,15 @@ impl HeadIndex {
         node_ids.len()
     }
 
+    /// Frame IDs of the active heads for the given frame type.
+    pub fn active_heads_for_frame_type(&self, frame_type: &str) -> Vec<FrameID> {
+        self.heads
+            .iter()
+            .filter(|((_, ft), e)| ft.as_str() == frame_type && e.tombstoned_at.is_none())
+            .map(|(_, e)| e.frame_id)
+            .collect()
+    }
+
     /// Get the persistence path for a workspace root
     ///
     /// Uses XDG data directory: $XDG_DATA_HOME/meld/workspaces/<hash>/head_index.bin
@@ -21,7 +21,7 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 
 /// Built-in ignore patterns (same as WalkerConfig default).
-const BUILTIN_DEFAULTS: &[&str] = &[".git", "target", "node_modules", ".cargo"];
+const BUILTIN_DEFAULTS: &[&str] = &[".git", "target", "node_modules", ".cargo", ".meld"];
 
 /// Special token in ignore_list that means "expand to patterns from workspace .gitignore".
 const GITIGNORE_ENTRY: &str = ".gitignore";
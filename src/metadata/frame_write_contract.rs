@@ -1,7 +1,11 @@
 //! Shared frame metadata write boundary.
+//!
+//! Writers serialize their values to strings and insert them under the keys below; the
+//! contract checks that each key is known and that its value has the expected shape.
 
 use crate::error::ApiError;
 use crate::metadata::frame_types::FrameMetadata;
+use crate::tree::classify;
 
 pub const KEY_AGENT_ID: &str = "agent_id";
 pub const KEY_PROVIDER: &str = "provider";
@@ -9,16 +13,85 @@ pub const KEY_MODEL: &str = "model";
 pub const KEY_PROVIDER_TYPE: &str = "provider_type";
 pub const KEY_PROMPT: &str = "prompt";
 pub const KEY_DELETED: &str = "deleted";
+pub const KEY_PROMPT_TOKENS: &str = "prompt_tokens";
+pub const KEY_COMPLETION_TOKENS: &str = "completion_tokens";
+pub const KEY_TOTAL_TOKENS: &str = "total_tokens";
+/// Set to `true` when the token counts are local estimates, not provider-reported usage
+pub const KEY_USAGE_ESTIMATED: &str = "usage_estimated";
+pub const KEY_LATENCY_MS: &str = "latency_ms";
+pub const KEY_FINISH_REASON: &str = "finish_reason";
+pub const KEY_TRUNCATED: &str = "truncated";
+pub const KEY_CHUNK_INDEX: &str = "chunk_index";
+pub const KEY_CHUNK_COUNT: &str = "chunk_count";
+pub const KEY_CHUNK_LINES: &str = "chunk_lines";
+pub const KEY_FILE_CLASS: &str = classify::KEY_FILE_CLASS;
+/// Providers a request failed over from before `provider` produced the frame
+pub const KEY_FALLBACK_FROM: &str = "fallback_from";
+/// Set to `true` when every provider response behind the frame came from the response cache
+pub const KEY_RESPONSE_CACHED: &str = "response_cached";
+/// Media type of the frame content; unset means free text
+pub const KEY_CONTENT_TYPE: &str = "content_type";
+/// Output schema a structured frame was validated against
+pub const KEY_OUTPUT_SCHEMA_ID: &str = "output_schema_id";
+/// Repair rounds the provider needed before its output matched the schema
+pub const KEY_OUTPUT_REPAIRS: &str = "output_repairs";
+/// Tool-calling rounds a tool-using agent took before it answered
+pub const KEY_TOOL_STEPS: &str = "tool_steps";
+/// JSON array of the tool calls behind the frame: tool, arguments, and result size
+pub const KEY_TOOL_TRANSCRIPT: &str = "tool_transcript";
 
-const ALLOWED_KEYS: &[&str] = &[
-    KEY_AGENT_ID,
-    KEY_PROVIDER,
-    KEY_MODEL,
-    KEY_PROVIDER_TYPE,
-    KEY_PROMPT,
-    KEY_DELETED,
+pub const CONTENT_TYPE_JSON: &str = "application/json";
+
+/// Shape a metadata value must have
+#[derive(Debug, Clone, Copy)]
+enum ValueShape {
+    Text,
+    /// Non-negative integer
+    Count,
+    /// `true` or `false`
+    Flag,
+    /// JSON document
+    Json,
+}
+
+const ALLOWED_KEYS: &[(&str, ValueShape)] = &[
+    (KEY_AGENT_ID, ValueShape::Text),
+    (KEY_PROVIDER, ValueShape::Text),
+    (KEY_MODEL, ValueShape::Text),
+    (KEY_PROVIDER_TYPE, ValueShape::Text),
+    (KEY_PROMPT, ValueShape::Text),
+    (KEY_DELETED, ValueShape::Flag),
+    (KEY_PROMPT_TOKENS, ValueShape::Count),
+    (KEY_COMPLETION_TOKENS, ValueShape::Count),
+    (KEY_TOTAL_TOKENS, ValueShape::Count),
+    (KEY_USAGE_ESTIMATED, ValueShape::Flag),
+    (KEY_LATENCY_MS, ValueShape::Count),
+    (KEY_FINISH_REASON, ValueShape::Text),
+    (KEY_TRUNCATED, ValueShape::Flag),
+    (KEY_CHUNK_INDEX, ValueShape::Count),
+    (KEY_CHUNK_COUNT, ValueShape::Count),
+    (KEY_CHUNK_LINES, ValueShape::Text),
+    (KEY_FILE_CLASS, ValueShape::Text),
+    (KEY_FALLBACK_FROM, ValueShape::Text),
+    (KEY_RESPONSE_CACHED, ValueShape::Flag),
+    (KEY_CONTENT_TYPE, ValueShape::Text),
+    (KEY_OUTPUT_SCHEMA_ID, ValueShape::Text),
+    (KEY_OUTPUT_REPAIRS, ValueShape::Count),
+    (KEY_TOOL_STEPS, ValueShape::Count),
+    (KEY_TOOL_TRANSCRIPT, ValueShape::Json),
 ];
 
+impl ValueShape {
+    fn accepts(self, value: &str) -> bool {
+        match self {
+            ValueShape::Text => true,
+            ValueShape::Count => value.parse::<u64>().is_ok(),
+            ValueShape::Flag => matches!(value, "true" | "false"),
+            ValueShape::Json => serde_json::from_str::<serde::de::IgnoredAny>(value).is_ok(),
+        }
+    }
+}
+
 /// Build frame metadata for generation queue writes.
 pub fn build_generated_metadata(
     agent_id: &str,
@@ -36,14 +109,28 @@ pub fn build_generated_metadata(
     metadata
 }
 
+/// Build frame metadata for template frames written without a provider call.
+pub fn build_template_metadata(agent_id: &str, file_class: &str) -> FrameMetadata {
+    let mut metadata = FrameMetadata::new();
+    metadata.insert(KEY_AGENT_ID.to_string(), agent_id.to_string());
+    metadata.insert(KEY_FILE_CLASS.to_string(), file_class.to_string());
+    metadata
+}
+
 /// Validate frame metadata at the shared write boundary.
 pub fn validate_frame_metadata(metadata: &FrameMetadata, agent_id: &str) -> Result<(), ApiError> {
-    for key in metadata.keys() {
-        if !ALLOWED_KEYS.contains(&key.as_str()) {
+    for (key, value) in metadata {
+        let Some((_, shape)) = ALLOWED_KEYS.iter().find(|(allowed, _)| allowed == key) else {
             return Err(ApiError::FrameMetadataPolicyViolation(format!(
                 "Frame metadata key is not allowed: {}",
                 key
             )));
+        };
+        if !shape.accepts(value) {
+            return Err(ApiError::FrameMetadataPolicyViolation(format!(
+                "Frame metadata {} has an invalid value: {}",
+                key, value
+            )));
         }
     }
 
@@ -63,3 +150,31 @@ pub fn validate_frame_metadata(metadata: &FrameMetadata, agent_id: &str) -> Resu
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_keys_and_value_shapes() {
+        let mut metadata = build_generated_metadata("writer", "local", "m", "ollama", "p");
+        metadata.insert(KEY_TOTAL_TOKENS.to_string(), "42".to_string());
+        metadata.insert(KEY_TRUNCATED.to_string(), "false".to_string());
+        metadata.insert(KEY_TOOL_TRANSCRIPT.to_string(), "[]".to_string());
+        validate_frame_metadata(&metadata, "writer").unwrap();
+
+        for (key, value) in [
+            (KEY_TOTAL_TOKENS, "many"),
+            (KEY_TRUNCATED, "yes"),
+            (KEY_TOOL_TRANSCRIPT, "[unclosed"),
+            ("leaked_key", "value"),
+        ] {
+            let mut invalid = metadata.clone();
+            invalid.insert(key.to_string(), value.to_string());
+            assert!(matches!(
+                validate_frame_metadata(&invalid, "writer"),
+                Err(ApiError::FrameMetadataPolicyViolation(_))
+            ));
+        }
+    }
+}
@@ -9,19 +9,29 @@ use async_trait::async_trait;
 use futures::Stream;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Duration;
 
+pub mod cache;
 pub mod clients;
 pub mod commands;
 pub mod diagnostics;
+pub mod embedding;
 pub mod generation;
 pub mod profile;
+pub mod rate_limit;
+pub mod replay;
 pub mod storage;
+pub mod streaming;
+pub mod template;
+pub mod transport;
 
-pub use profile::{ProviderConfig, ProviderType, ValidationResult};
+pub use profile::{
+    ModelPricing, ProviderConfig, ProviderRateLimits, ProviderType, ReplayConfig, ReplayMode,
+    TransportConfig, ValidationResult,
+};
 
 /// Model provider configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +54,9 @@ pub enum ModelProvider {
         endpoint: String, // Full endpoint URL (e.g., http://localhost:8080/v1)
         api_key: Option<String>,
     },
+    Template {
+        model: String, // Offline: frames come from local analysis
+    },
 }
 
 /// Chat message role
@@ -52,6 +65,8 @@ pub enum MessageRole {
     System,
     User,
     Assistant,
+    /// Result of a tool call, answering `tool_call_id`
+    Tool,
 }
 
 /// Chat message
@@ -59,6 +74,75 @@ pub enum MessageRole {
 pub struct ChatMessage {
     pub role: MessageRole,
     pub content: String,
+    /// Tools an assistant message asked to call
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// Call a tool message answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+/// Copy of `messages` with the workspace root stripped from their text, so request keys
+/// built from it match across checkouts of the same tree
+pub fn workspace_relative_messages(
+    messages: &[ChatMessage],
+    workspace_root: Option<&Path>,
+) -> Vec<ChatMessage> {
+    let Some(root) = workspace_root
+        .and_then(Path::to_str)
+        .filter(|r| r.len() > 1)
+    else {
+        return messages.to_vec();
+    };
+    messages
+        .iter()
+        .map(|message| ChatMessage {
+            content: strip_workspace_root(&message.content, root),
+            ..message.clone()
+        })
+        .collect()
+}
+
+/// Rewrite `<root>/a/b` as `a/b` and a bare `<root>` as `.`; longer names that merely
+/// start with the root are left alone
+fn strip_workspace_root(text: &str, root: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(root) {
+        stripped.push_str(&rest[..index]);
+        let after = &rest[index + root.len()..];
+        match after.chars().next() {
+            Some(c) if std::path::is_separator(c) => rest = &after[c.len_utf8()..],
+            Some(c) if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') => {
+                stripped.push_str(root);
+                rest = after;
+            }
+            _ => {
+                stripped.push('.');
+                rest = after;
+            }
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Tool a model may call during a completion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    /// JSON schema of the arguments object
+    pub parameters: Value,
+}
+
+/// Tool call requested by a model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Provider-assigned id, echoed back with the result
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
 }
 
 /// Completion options
@@ -70,6 +154,21 @@ pub struct CompletionOptions {
     pub frequency_penalty: Option<f32>, // -2.0 to 2.0
     pub presence_penalty: Option<f32>,  // -2.0 to 2.0
     pub stop: Option<Vec<String>>,      // Stop sequences
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>, // Structured output, where supported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolDefinition>, // Tools offered to providers that support calling them
+}
+
+/// Output format a provider should constrain a completion to. Providers without a
+/// native mode ignore it; callers validate the output either way.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Any JSON object
+    JsonObject,
+    /// JSON conforming to `schema`
+    JsonSchema { name: String, schema: Value },
 }
 
 impl Default for CompletionOptions {
@@ -81,6 +180,8 @@ impl Default for CompletionOptions {
             frequency_penalty: None,
             presence_penalty: None,
             stop: None,
+            response_format: None,
+            tools: Vec::new(),
         }
     }
 }
@@ -91,6 +192,9 @@ pub struct TokenUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    /// Counted locally because the provider reported no usage; not charged to budgets
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub estimated: bool,
 }
 
 /// Completion response
@@ -100,10 +204,27 @@ pub struct CompletionResponse {
     pub model: String,
     pub usage: TokenUsage,
     pub finish_reason: Option<String>,
+    /// Tools the model asked to call before it answers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// One item of a completion stream
+#[derive(Debug, Clone)]
+pub enum StreamChunk {
+    /// Text delta
+    Text(String),
+    /// Usage the provider reported; fields it left out are `None`. Later values win.
+    Usage {
+        prompt_tokens: Option<u32>,
+        completion_tokens: Option<u32>,
+    },
+    /// Why the provider stopped generating
+    FinishReason(String),
 }
 
 /// Streaming completion type
-pub type CompletionStream = Pin<Box<dyn Stream<Item = Result<String, ApiError>> + Send>>;
+pub type CompletionStream = Pin<Box<dyn Stream<Item = Result<StreamChunk, ApiError>> + Send>>;
 
 /// Model provider client trait
 #[async_trait]
@@ -122,6 +243,20 @@ pub trait ModelProviderClient: Send + Sync {
         options: CompletionOptions,
     ) -> Result<CompletionStream, ApiError>;
 
+    /// Whether `complete` honors `CompletionOptions::tools` and reports tool calls
+    fn supports_tools(&self) -> bool {
+        false
+    }
+
+    /// Embed each text as a vector, in input order
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ApiError> {
+        let _ = texts;
+        Err(ApiError::ProviderError(format!(
+            "Provider '{}' does not support embeddings",
+            self.provider_name()
+        )))
+    }
+
     /// Get the provider name
     fn provider_name(&self) -> &str;
 
@@ -149,13 +284,118 @@ struct ChatCompletionRequest {
     presence_penalty: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Value>,
     stream: bool,
+    /// Asks for a final usage chunk when streaming
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<Value>,
+}
+
+impl ChatCompletionRequest {
+    fn new(
+        model: &str,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
+        stream: bool,
+    ) -> Self {
+        Self {
+            model: model.to_string(),
+            messages: messages.into_iter().map(OpenAIMessage::from).collect(),
+            temperature: options.temperature,
+            max_tokens: options.max_tokens,
+            top_p: options.top_p,
+            frequency_penalty: options.frequency_penalty,
+            presence_penalty: options.presence_penalty,
+            stop: options.stop,
+            response_format: options.response_format.map(|format| match format {
+                ResponseFormat::JsonObject => json!({ "type": "json_object" }),
+                ResponseFormat::JsonSchema { name, schema } => json!({
+                    "type": "json_schema",
+                    "json_schema": { "name": name, "schema": schema },
+                }),
+            }),
+            tools: options
+                .tools
+                .into_iter()
+                .map(|tool| {
+                    json!({
+                        "type": "function",
+                        "function": {
+                            "name": tool.name,
+                            "description": tool.description,
+                            "parameters": tool.parameters,
+                        },
+                    })
+                })
+                .collect(),
+            stream,
+            stream_options: stream.then(|| json!({ "include_usage": true })),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 struct OpenAIMessage {
     role: String,
-    content: String,
+    /// Null on assistant messages that only call tools
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OpenAIToolCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+impl From<ChatMessage> for OpenAIMessage {
+    fn from(msg: ChatMessage) -> Self {
+        let content = if msg.content.is_empty() && !msg.tool_calls.is_empty() {
+            None
+        } else {
+            Some(msg.content)
+        };
+        Self {
+            role: role_to_string(msg.role).to_string(),
+            content,
+            tool_calls: msg
+                .tool_calls
+                .into_iter()
+                .map(|call| OpenAIToolCall {
+                    id: call.id,
+                    kind: "function".to_string(),
+                    function: OpenAIFunctionCall {
+                        name: call.name,
+                        // Arguments travel as a JSON-encoded string
+                        arguments: match call.arguments {
+                            Value::String(raw) => raw,
+                            arguments => arguments.to_string(),
+                        },
+                    },
+                })
+                .collect(),
+            tool_call_id: msg.tool_call_id,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpenAIToolCall {
+    id: String,
+    #[serde(rename = "type", default = "default_tool_call_kind")]
+    kind: String,
+    function: OpenAIFunctionCall,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpenAIFunctionCall {
+    name: String,
+    arguments: String,
+}
+
+fn default_tool_call_kind() -> String {
+    "function".to_string()
 }
 
 #[derive(Deserialize)]
@@ -180,12 +420,52 @@ struct Usage {
     total_tokens: u32,
 }
 
+impl ChatCompletionResponse {
+    fn into_completion(self) -> Result<CompletionResponse, ApiError> {
+        let choice = self
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| ApiError::ProviderError("No choices in response".to_string()))?;
+
+        let usage = self.usage.unwrap_or(Usage {
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            total_tokens: 0,
+        });
+
+        Ok(CompletionResponse {
+            content: choice.message.content.unwrap_or_default(),
+            model: self.model,
+            usage: TokenUsage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+                total_tokens: usage.total_tokens,
+                estimated: false,
+            },
+            finish_reason: choice.finish_reason,
+            tool_calls: choice
+                .message
+                .tool_calls
+                .into_iter()
+                .map(|call| ToolCall {
+                    arguments: serde_json::from_str(&call.function.arguments)
+                        .unwrap_or(Value::String(call.function.arguments)),
+                    id: call.id,
+                    name: call.function.name,
+                })
+                .collect(),
+        })
+    }
+}
+
 // Helper function to convert MessageRole to string
 fn role_to_string(role: MessageRole) -> &'static str {
     match role {
         MessageRole::System => "system",
         MessageRole::User => "user",
         MessageRole::Assistant => "assistant",
+        MessageRole::Tool => "tool",
     }
 }
 
@@ -195,7 +475,10 @@ fn map_http_error(error: reqwest::Error) -> ApiError {
         let status = error.status().unwrap();
         match status.as_u16() {
             401 => ApiError::ProviderAuthFailed(format!("Authentication failed: {}", error)),
-            429 => ApiError::ProviderRateLimit(format!("Rate limit exceeded: {}", error)),
+            429 => ApiError::ProviderRateLimit {
+                message: format!("Rate limit exceeded: {}", error),
+                retry_after: None,
+            },
             404 => ApiError::ProviderModelNotFound(format!("Model not found: {}", error)),
             _ => ApiError::ProviderRequestFailed(format!(
                 "Request failed with status {}: {}",
@@ -211,16 +494,71 @@ fn map_http_error(error: reqwest::Error) -> ApiError {
     }
 }
 
-const PROVIDER_HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
-const PROVIDER_HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+// Helper function to map a non-success provider response to ApiError.
+// Rate-limit responses carry the wait the provider asked for.
+async fn map_error_response(response: reqwest::Response) -> ApiError {
+    let status = response.status();
+    let retry_after = rate_limit::retry_after(response.headers());
+    let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+    match status.as_u16() {
+        401 => ApiError::ProviderAuthFailed(format!("Authentication failed: {}", error_text)),
+        429 => ApiError::ProviderRateLimit {
+            message: format!("Rate limit exceeded: {}", error_text),
+            retry_after,
+        },
+        404 => ApiError::ProviderModelNotFound(format!("Model not found: {}", error_text)),
+        _ => ApiError::ProviderRequestFailed(format!(
+            "Request failed with status {}: {}",
+            status, error_text
+        )),
+    }
+}
 
-fn build_provider_http_client() -> Result<Client, ApiError> {
-    Client::builder()
-        .no_proxy()
-        .connect_timeout(PROVIDER_HTTP_CONNECT_TIMEOUT)
-        .timeout(PROVIDER_HTTP_REQUEST_TIMEOUT)
-        .build()
-        .map_err(|e| ApiError::ProviderError(format!("Failed to create HTTP client: {}", e)))
+/// Call an OpenAI-compatible `/embeddings` endpoint; vectors come back in input order
+async fn openai_embeddings(
+    request: reqwest::RequestBuilder,
+    model: &str,
+    texts: Vec<String>,
+) -> Result<Vec<Vec<f32>>, ApiError> {
+    #[derive(Deserialize)]
+    struct EmbeddingsResponse {
+        data: Vec<EmbeddingData>,
+    }
+    #[derive(Deserialize)]
+    struct EmbeddingData {
+        index: usize,
+        embedding: Vec<f32>,
+    }
+
+    let count = texts.len();
+    let response = request
+        .header("Content-Type", "application/json")
+        .json(&json!({ "model": model, "input": texts }))
+        .send()
+        .await
+        .map_err(map_http_error)?;
+    if !response.status().is_success() {
+        return Err(map_error_response(response).await);
+    }
+    let mut embeddings: EmbeddingsResponse = response.json().await.map_err(|e| {
+        ApiError::ProviderError(format!("Failed to parse embeddings response: {}", e))
+    })?;
+    if embeddings.data.len() != count {
+        return Err(ApiError::ProviderError(format!(
+            "Embeddings response has {} vectors for {} inputs",
+            embeddings.data.len(),
+            count
+        )));
+    }
+    embeddings.data.sort_by_key(|data| data.index);
+    Ok(embeddings
+        .data
+        .into_iter()
+        .map(|data| data.embedding)
+        .collect())
 }
 
 /// OpenAI provider client
@@ -232,8 +570,13 @@ pub struct OpenAIClient {
 }
 
 impl OpenAIClient {
-    pub fn new(model: String, api_key: String, base_url: Option<String>) -> Result<Self, ApiError> {
-        let client = build_provider_http_client()?;
+    pub fn new(
+        model: String,
+        api_key: String,
+        base_url: Option<String>,
+        transport: &TransportConfig,
+    ) -> Result<Self, ApiError> {
+        let client = transport::build_http_client(transport)?;
         let base_url = base_url.unwrap_or_else(|| "https://api.openai.com/v1".to_string());
 
         Ok(Self {
@@ -252,25 +595,7 @@ impl ModelProviderClient for OpenAIClient {
         messages: Vec<ChatMessage>,
         options: CompletionOptions,
     ) -> Result<CompletionResponse, ApiError> {
-        let openai_messages: Vec<OpenAIMessage> = messages
-            .into_iter()
-            .map(|msg| OpenAIMessage {
-                role: role_to_string(msg.role).to_string(),
-                content: msg.content,
-            })
-            .collect();
-
-        let request = ChatCompletionRequest {
-            model: self.model.clone(),
-            messages: openai_messages,
-            temperature: options.temperature,
-            max_tokens: options.max_tokens,
-            top_p: options.top_p,
-            frequency_penalty: options.frequency_penalty,
-            presence_penalty: options.presence_penalty,
-            stop: options.stop,
-            stream: false,
-        };
+        let request = ChatCompletionRequest::new(&self.model, messages, options, false);
 
         let url = format!("{}/chat/completions", self.base_url);
         let response = self
@@ -284,19 +609,7 @@ impl ModelProviderClient for OpenAIClient {
             .map_err(map_http_error)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(match status.as_u16() {
-                401 => {
-                    ApiError::ProviderAuthFailed(format!("Authentication failed: {}", error_text))
-                }
-                429 => ApiError::ProviderRateLimit(format!("Rate limit exceeded: {}", error_text)),
-                404 => ApiError::ProviderModelNotFound(format!("Model not found: {}", error_text)),
-                _ => ApiError::ProviderRequestFailed(format!("Request failed: {}", error_text)),
-            });
+            return Err(map_error_response(response).await);
         }
 
         let completion: ChatCompletionResponse = response
@@ -304,39 +617,42 @@ impl ModelProviderClient for OpenAIClient {
             .await
             .map_err(|e| ApiError::ProviderError(format!("Failed to parse response: {}", e)))?;
 
-        let choice = completion
-            .choices
-            .first()
-            .ok_or_else(|| ApiError::ProviderError("No choices in response".to_string()))?;
-
-        let usage = completion.usage.unwrap_or(Usage {
-            prompt_tokens: 0,
-            completion_tokens: 0,
-            total_tokens: 0,
-        });
-
-        Ok(CompletionResponse {
-            content: choice.message.content.clone(),
-            model: completion.model,
-            usage: TokenUsage {
-                prompt_tokens: usage.prompt_tokens,
-                completion_tokens: usage.completion_tokens,
-                total_tokens: usage.total_tokens,
-            },
-            finish_reason: choice.finish_reason.clone(),
-        })
+        completion.into_completion()
     }
 
     async fn stream(
         &self,
-        _messages: Vec<ChatMessage>,
-        _options: CompletionOptions,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
     ) -> Result<CompletionStream, ApiError> {
-        // Streaming implementation would go here
-        // For now, return an error indicating it's not implemented
-        Err(ApiError::ProviderError(
-            "Streaming not yet implemented for OpenAI".to_string(),
-        ))
+        let request = ChatCompletionRequest::new(&self.model, messages, options, true);
+        let url = format!("{}/chat/completions", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await
+            .map_err(map_http_error)?;
+
+        if !response.status().is_success() {
+            return Err(map_error_response(response).await);
+        }
+        Ok(streaming::openai_chunk_stream(response))
+    }
+
+    fn supports_tools(&self) -> bool {
+        true
+    }
+
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ApiError> {
+        let request = self
+            .client
+            .post(format!("{}/embeddings", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key));
+        openai_embeddings(request, &self.model, texts).await
     }
 
     fn provider_name(&self) -> &str {
@@ -386,6 +702,8 @@ impl ModelProviderClient for OpenAIClient {
     }
 }
 
+const ANTHROPIC_MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
+
 /// Anthropic provider client (using OpenAI-compatible format via Claude API)
 pub struct AnthropicClient {
     client: Client,
@@ -394,41 +712,28 @@ pub struct AnthropicClient {
 }
 
 impl AnthropicClient {
-    pub fn new(model: String, api_key: String) -> Result<Self, ApiError> {
-        let client = build_provider_http_client()?;
+    pub fn new(
+        model: String,
+        api_key: String,
+        transport: &TransportConfig,
+    ) -> Result<Self, ApiError> {
+        let client = transport::build_http_client(transport)?;
         Ok(Self {
             client,
             model,
             api_key,
         })
     }
-}
-
-#[async_trait]
-impl ModelProviderClient for AnthropicClient {
-    async fn complete(
-        &self,
-        messages: Vec<ChatMessage>,
-        options: CompletionOptions,
-    ) -> Result<CompletionResponse, ApiError> {
-        // Anthropic API uses a different format, but we'll map it to OpenAI-compatible
-        // For now, we'll use a simplified approach that works with OpenAI-compatible endpoints
-        // In a real implementation, we'd use the Anthropic SDK or map their API format
-
-        // Anthropic API endpoint
-        let url = "https://api.anthropic.com/v1/messages";
 
+    /// Messages API request body; `stream` is set by the caller
+    fn request_body(&self, messages: Vec<ChatMessage>, options: &CompletionOptions) -> Value {
         // Convert messages to Anthropic format
         let system_message = messages
             .iter()
             .find(|m| m.role == MessageRole::System)
             .map(|m| m.content.clone());
 
-        let user_messages: Vec<String> = messages
-            .iter()
-            .filter(|m| m.role == MessageRole::User)
-            .map(|m| m.content.clone())
-            .collect();
+        let conversation = anthropic_messages(&messages);
 
         let mut request_body = json!({
             "model": self.model,
@@ -439,20 +744,98 @@ impl ModelProviderClient for AnthropicClient {
             request_body["system"] = json!(system);
         }
 
-        if !user_messages.is_empty() {
-            request_body["messages"] = json!(user_messages
-                .into_iter()
-                .map(|content| { json!({"role": "user", "content": content}) })
-                .collect::<Vec<_>>());
+        if !conversation.is_empty() {
+            request_body["messages"] = json!(conversation);
         }
 
         if let Some(temp) = options.temperature {
             request_body["temperature"] = json!(temp);
         }
 
+        if !options.tools.is_empty() {
+            request_body["tools"] = json!(options
+                .tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "input_schema": tool.parameters,
+                    })
+                })
+                .collect::<Vec<_>>());
+        }
+
+        request_body
+    }
+}
+
+/// Conversation turns in Messages API form. Tool calls become `tool_use` blocks, tool
+/// results become `tool_result` blocks in a user turn, and consecutive turns of the same
+/// role are merged since the API expects roles to alternate.
+fn anthropic_messages(messages: &[ChatMessage]) -> Vec<Value> {
+    let mut turns: Vec<(&str, Vec<Value>)> = Vec::new();
+    for message in messages {
+        let (role, blocks) = match message.role {
+            MessageRole::System => continue,
+            MessageRole::User => (
+                "user",
+                vec![json!({"type": "text", "text": message.content})],
+            ),
+            MessageRole::Assistant => {
+                let mut blocks = Vec::new();
+                if !message.content.is_empty() {
+                    blocks.push(json!({"type": "text", "text": message.content}));
+                }
+                blocks.extend(message.tool_calls.iter().map(|call| {
+                    json!({
+                        "type": "tool_use",
+                        "id": call.id,
+                        "name": call.name,
+                        "input": call.arguments,
+                    })
+                }));
+                ("assistant", blocks)
+            }
+            MessageRole::Tool => (
+                "user",
+                vec![json!({
+                    "type": "tool_result",
+                    "tool_use_id": message.tool_call_id,
+                    "content": message.content,
+                })],
+            ),
+        };
+        match turns.last_mut() {
+            Some((last_role, last_blocks)) if *last_role == role => last_blocks.extend(blocks),
+            _ => turns.push((role, blocks)),
+        }
+    }
+    turns
+        .into_iter()
+        .map(|(role, blocks)| match blocks.as_slice() {
+            // Plain text turns keep the compact string form
+            [block] if block["type"] == "text" => json!({"role": role, "content": block["text"]}),
+            _ => json!({"role": role, "content": blocks}),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl ModelProviderClient for AnthropicClient {
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
+    ) -> Result<CompletionResponse, ApiError> {
+        // Anthropic API uses a different format, but we'll map it to OpenAI-compatible
+        // For now, we'll use a simplified approach that works with OpenAI-compatible endpoints
+        // In a real implementation, we'd use the Anthropic SDK or map their API format
+
+        let request_body = self.request_body(messages, &options);
         let response = self
             .client
-            .post(url)
+            .post(ANTHROPIC_MESSAGES_URL)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("Content-Type", "application/json")
@@ -462,19 +845,7 @@ impl ModelProviderClient for AnthropicClient {
             .map_err(map_http_error)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(match status.as_u16() {
-                401 => {
-                    ApiError::ProviderAuthFailed(format!("Authentication failed: {}", error_text))
-                }
-                429 => ApiError::ProviderRateLimit(format!("Rate limit exceeded: {}", error_text)),
-                404 => ApiError::ProviderModelNotFound(format!("Model not found: {}", error_text)),
-                _ => ApiError::ProviderRequestFailed(format!("Request failed: {}", error_text)),
-            });
+            return Err(map_error_response(response).await);
         }
 
         #[derive(Deserialize)]
@@ -482,11 +853,22 @@ impl ModelProviderClient for AnthropicClient {
             content: Vec<AnthropicContent>,
             model: String,
             usage: Option<AnthropicUsage>,
+            stop_reason: Option<String>,
         }
 
         #[derive(Deserialize)]
-        struct AnthropicContent {
-            text: String,
+        #[serde(tag = "type", rename_all = "snake_case")]
+        enum AnthropicContent {
+            Text {
+                text: String,
+            },
+            ToolUse {
+                id: String,
+                name: String,
+                input: Value,
+            },
+            #[serde(other)]
+            Other,
         }
 
         #[derive(Deserialize)]
@@ -500,11 +882,19 @@ impl ModelProviderClient for AnthropicClient {
             .await
             .map_err(|e| ApiError::ProviderError(format!("Failed to parse response: {}", e)))?;
 
-        let content = completion
-            .content
-            .first()
-            .map(|c| c.text.clone())
-            .unwrap_or_default();
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        for block in completion.content {
+            match block {
+                AnthropicContent::Text { text } => content.push_str(&text),
+                AnthropicContent::ToolUse { id, name, input } => tool_calls.push(ToolCall {
+                    id,
+                    name,
+                    arguments: input,
+                }),
+                AnthropicContent::Other => {}
+            }
+        }
 
         let usage = completion.usage.unwrap_or(AnthropicUsage {
             input_tokens: 0,
@@ -518,19 +908,39 @@ impl ModelProviderClient for AnthropicClient {
                 prompt_tokens: usage.input_tokens,
                 completion_tokens: usage.output_tokens,
                 total_tokens: usage.input_tokens + usage.output_tokens,
+                estimated: false,
             },
-            finish_reason: Some("stop".to_string()),
+            finish_reason: completion.stop_reason,
+            tool_calls,
         })
     }
 
     async fn stream(
         &self,
-        _messages: Vec<ChatMessage>,
-        _options: CompletionOptions,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
     ) -> Result<CompletionStream, ApiError> {
-        Err(ApiError::ProviderError(
-            "Streaming not yet implemented for Anthropic".to_string(),
-        ))
+        let mut request_body = self.request_body(messages, &options);
+        request_body["stream"] = json!(true);
+        let response = self
+            .client
+            .post(ANTHROPIC_MESSAGES_URL)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("Content-Type", "application/json")
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(map_http_error)?;
+
+        if !response.status().is_success() {
+            return Err(map_error_response(response).await);
+        }
+        Ok(streaming::anthropic_chunk_stream(response))
+    }
+
+    fn supports_tools(&self) -> bool {
+        true
     }
 
     fn provider_name(&self) -> &str {
@@ -558,9 +968,13 @@ pub struct OllamaClient {
 }
 
 impl OllamaClient {
-    pub fn new(model: String, base_url: Option<String>) -> Result<Self, ApiError> {
+    pub fn new(
+        model: String,
+        base_url: Option<String>,
+        transport: &TransportConfig,
+    ) -> Result<Self, ApiError> {
         let base_url = base_url.unwrap_or_else(|| "http://localhost:11434".to_string());
-        let client = build_provider_http_client()?;
+        let client = transport::build_http_client(transport)?;
 
         Ok(Self {
             client,
@@ -578,25 +992,7 @@ impl ModelProviderClient for OllamaClient {
         options: CompletionOptions,
     ) -> Result<CompletionResponse, ApiError> {
         // Ollama uses OpenAI-compatible API format
-        let openai_messages: Vec<OpenAIMessage> = messages
-            .into_iter()
-            .map(|msg| OpenAIMessage {
-                role: role_to_string(msg.role).to_string(),
-                content: msg.content,
-            })
-            .collect();
-
-        let request = ChatCompletionRequest {
-            model: self.model.clone(),
-            messages: openai_messages,
-            temperature: options.temperature,
-            max_tokens: options.max_tokens,
-            top_p: options.top_p,
-            frequency_penalty: options.frequency_penalty,
-            presence_penalty: options.presence_penalty,
-            stop: options.stop,
-            stream: false,
-        };
+        let request = ChatCompletionRequest::new(&self.model, messages, options, false);
 
         let url = format!("{}/v1/chat/completions", self.base_url);
         let response = self
@@ -609,15 +1005,7 @@ impl ModelProviderClient for OllamaClient {
             .map_err(map_http_error)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(ApiError::ProviderRequestFailed(format!(
-                "Request failed with status {}: {}",
-                status, error_text
-            )));
+            return Err(map_error_response(response).await);
         }
 
         let completion: ChatCompletionResponse = response
@@ -625,37 +1013,38 @@ impl ModelProviderClient for OllamaClient {
             .await
             .map_err(|e| ApiError::ProviderError(format!("Failed to parse response: {}", e)))?;
 
-        let choice = completion
-            .choices
-            .first()
-            .ok_or_else(|| ApiError::ProviderError("No choices in response".to_string()))?;
-
-        let usage = completion.usage.unwrap_or(Usage {
-            prompt_tokens: 0,
-            completion_tokens: 0,
-            total_tokens: 0,
-        });
-
-        Ok(CompletionResponse {
-            content: choice.message.content.clone(),
-            model: completion.model,
-            usage: TokenUsage {
-                prompt_tokens: usage.prompt_tokens,
-                completion_tokens: usage.completion_tokens,
-                total_tokens: usage.total_tokens,
-            },
-            finish_reason: choice.finish_reason.clone(),
-        })
+        completion.into_completion()
     }
 
     async fn stream(
         &self,
-        _messages: Vec<ChatMessage>,
-        _options: CompletionOptions,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
     ) -> Result<CompletionStream, ApiError> {
-        Err(ApiError::ProviderError(
-            "Streaming not yet implemented for Ollama".to_string(),
-        ))
+        let request = ChatCompletionRequest::new(&self.model, messages, options, true);
+        let url = format!("{}/v1/chat/completions", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await
+            .map_err(map_http_error)?;
+
+        if !response.status().is_success() {
+            return Err(map_error_response(response).await);
+        }
+        Ok(streaming::openai_chunk_stream(response))
+    }
+
+    fn supports_tools(&self) -> bool {
+        true
+    }
+
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ApiError> {
+        let request = self.client.post(format!("{}/v1/embeddings", self.base_url));
+        openai_embeddings(request, &self.model, texts).await
     }
 
     fn provider_name(&self) -> &str {
@@ -708,8 +1097,13 @@ pub struct CustomLocalClient {
 }
 
 impl CustomLocalClient {
-    pub fn new(model: String, endpoint: String, api_key: Option<String>) -> Result<Self, ApiError> {
-        let client = build_provider_http_client()?;
+    pub fn new(
+        model: String,
+        endpoint: String,
+        api_key: Option<String>,
+        transport: &TransportConfig,
+    ) -> Result<Self, ApiError> {
+        let client = transport::build_http_client(transport)?;
         Ok(Self {
             client,
             model,
@@ -726,25 +1120,7 @@ impl ModelProviderClient for CustomLocalClient {
         messages: Vec<ChatMessage>,
         options: CompletionOptions,
     ) -> Result<CompletionResponse, ApiError> {
-        let openai_messages: Vec<OpenAIMessage> = messages
-            .into_iter()
-            .map(|msg| OpenAIMessage {
-                role: role_to_string(msg.role).to_string(),
-                content: msg.content,
-            })
-            .collect();
-
-        let request = ChatCompletionRequest {
-            model: self.model.clone(),
-            messages: openai_messages,
-            temperature: options.temperature,
-            max_tokens: options.max_tokens,
-            top_p: options.top_p,
-            frequency_penalty: options.frequency_penalty,
-            presence_penalty: options.presence_penalty,
-            stop: options.stop,
-            stream: false,
-        };
+        let request = ChatCompletionRequest::new(&self.model, messages, options, false);
 
         let url = format!("{}/chat/completions", self.endpoint);
         let mut request_builder = self
@@ -764,15 +1140,7 @@ impl ModelProviderClient for CustomLocalClient {
             .map_err(map_http_error)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(ApiError::ProviderRequestFailed(format!(
-                "Request failed with status {}: {}",
-                status, error_text
-            )));
+            return Err(map_error_response(response).await);
         }
 
         let completion: ChatCompletionResponse = response
@@ -780,37 +1148,48 @@ impl ModelProviderClient for CustomLocalClient {
             .await
             .map_err(|e| ApiError::ProviderError(format!("Failed to parse response: {}", e)))?;
 
-        let choice = completion
-            .choices
-            .first()
-            .ok_or_else(|| ApiError::ProviderError("No choices in response".to_string()))?;
-
-        let usage = completion.usage.unwrap_or(Usage {
-            prompt_tokens: 0,
-            completion_tokens: 0,
-            total_tokens: 0,
-        });
-
-        Ok(CompletionResponse {
-            content: choice.message.content.clone(),
-            model: completion.model,
-            usage: TokenUsage {
-                prompt_tokens: usage.prompt_tokens,
-                completion_tokens: usage.completion_tokens,
-                total_tokens: usage.total_tokens,
-            },
-            finish_reason: choice.finish_reason.clone(),
-        })
+        completion.into_completion()
     }
 
     async fn stream(
         &self,
-        _messages: Vec<ChatMessage>,
-        _options: CompletionOptions,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
     ) -> Result<CompletionStream, ApiError> {
-        Err(ApiError::ProviderError(
-            "Streaming not yet implemented for CustomLocal".to_string(),
-        ))
+        let request = ChatCompletionRequest::new(&self.model, messages, options, true);
+        let url = format!("{}/chat/completions", self.endpoint);
+        let mut request_builder = self
+            .client
+            .post(&url)
+            .header("Content-Type", "application/json");
+
+        if let Some(api_key) = &self.api_key {
+            request_builder =
+                request_builder.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        let response = request_builder
+            .json(&request)
+            .send()
+            .await
+            .map_err(map_http_error)?;
+
+        if !response.status().is_success() {
+            return Err(map_error_response(response).await);
+        }
+        Ok(streaming::openai_chunk_stream(response))
+    }
+
+    fn supports_tools(&self) -> bool {
+        true
+    }
+
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ApiError> {
+        let mut request = self.client.post(format!("{}/embeddings", self.endpoint));
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        openai_embeddings(request, &self.model, texts).await
     }
 
     fn provider_name(&self) -> &str {
@@ -869,8 +1248,10 @@ impl ModelProviderClient for CustomLocalClient {
 pub struct ProviderFactory;
 
 impl ProviderFactory {
+    /// Create a client; network clients apply `transport` to their HTTP client
     pub fn create_client(
         provider: &ModelProvider,
+        transport: &TransportConfig,
     ) -> Result<Box<dyn ModelProviderClient>, ApiError> {
         match provider {
             ModelProvider::OpenAI {
@@ -881,14 +1262,17 @@ impl ProviderFactory {
                 model.clone(),
                 api_key.clone(),
                 base_url.clone(),
+                transport,
             )?)),
             ModelProvider::Anthropic { model, api_key } => Ok(Box::new(AnthropicClient::new(
                 model.clone(),
                 api_key.clone(),
+                transport,
             )?)),
             ModelProvider::Ollama { model, base_url } => Ok(Box::new(OllamaClient::new(
                 model.clone(),
                 base_url.clone(),
+                transport,
             )?)),
             ModelProvider::LocalCustom {
                 model,
@@ -898,7 +1282,11 @@ impl ProviderFactory {
                 model.clone(),
                 endpoint.clone(),
                 api_key.clone(),
+                transport,
             )?)),
+            ModelProvider::Template { model } => {
+                Ok(Box::new(template::TemplateClient::new(model.clone())))
+            }
         }
     }
 }
@@ -910,8 +1298,15 @@ impl ProviderFactory {
 pub struct ProviderRegistry {
     providers: std::collections::HashMap<String, ProviderConfig>,
     storage: Arc<dyn storage::ProviderStorage>,
+    /// Resolved API keys by provider, with the `api_key` value they were resolved from.
+    /// Keeps `cmd:` references from running once per client.
+    resolved_keys: parking_lot::Mutex<ResolvedKeys>,
+    /// Canonical workspace root, stripped from prompts before they are keyed for replay
+    workspace_root: Option<PathBuf>,
 }
 
+type ResolvedKeys = std::collections::HashMap<String, (Option<String>, Option<String>)>;
+
 impl ProviderRegistry {
     /// Create a new empty provider registry
     pub fn new() -> Self {
@@ -922,9 +1317,24 @@ impl ProviderRegistry {
         Self {
             providers: std::collections::HashMap::new(),
             storage,
+            resolved_keys: parking_lot::Mutex::new(ResolvedKeys::new()),
+            workspace_root: None,
         }
     }
 
+    /// Key replay cassettes on prompts relative to `workspace_root`
+    pub fn set_workspace_root(&mut self, workspace_root: &Path) {
+        self.workspace_root = Some(
+            crate::tree::path::canonicalize_path(workspace_root)
+                .unwrap_or_else(|_| workspace_root.to_path_buf()),
+        );
+    }
+
+    /// Workspace root set with `set_workspace_root`
+    pub fn workspace_root(&self) -> Option<&Path> {
+        self.workspace_root.as_deref()
+    }
+
     /// Load providers from configuration
     pub fn load_from_config(
         &mut self,
@@ -978,8 +1388,33 @@ impl ProviderRegistry {
         provider_name: &str,
     ) -> Result<Box<dyn ModelProviderClient>, ApiError> {
         let provider_config = self.get_or_error(provider_name)?;
-        let model_provider = provider_config.to_model_provider()?;
-        ProviderFactory::create_client(&model_provider)
+        if provider_config.provider_type == ProviderType::Replay {
+            return replay::create_replay_client(self, provider_name, provider_config);
+        }
+        let api_key = self.resolved_api_key(provider_name, provider_config)?;
+        let model_provider = provider_config.to_model_provider_with_key(api_key)?;
+        let transport = provider_config.transport.clone().unwrap_or_default();
+        ProviderFactory::create_client(&model_provider, &transport)
+    }
+
+    /// Resolve a provider's API key once; later calls reuse it until `api_key` changes
+    fn resolved_api_key(
+        &self,
+        provider_name: &str,
+        config: &ProviderConfig,
+    ) -> Result<Option<String>, ApiError> {
+        let mut resolved = self.resolved_keys.lock();
+        if let Some((source, key)) = resolved.get(provider_name) {
+            if *source == config.api_key {
+                return Ok(key.clone());
+            }
+        }
+        let key = config.resolve_api_key()?;
+        resolved.insert(
+            provider_name.to_string(),
+            (config.api_key.clone(), key.clone()),
+        );
+        Ok(key)
     }
 
     /// List providers filtered by type
@@ -1082,19 +1517,31 @@ impl ModelProviderClient for MockProvider {
                 prompt_tokens: 10,
                 completion_tokens: 20,
                 total_tokens: 30,
+                estimated: false,
             },
             finish_reason: Some("stop".to_string()),
+            tool_calls: Vec::new(),
         })
     }
 
     async fn stream(
         &self,
-        _messages: Vec<ChatMessage>,
-        _options: CompletionOptions,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
     ) -> Result<CompletionStream, ApiError> {
-        Err(ApiError::ProviderError(
-            "Streaming not implemented for mock".to_string(),
-        ))
+        // Stream the next response word by word
+        let response = self.complete(messages, options).await?;
+        let mut chunks: Vec<Result<StreamChunk, ApiError>> = response
+            .content
+            .split_inclusive(' ')
+            .map(|word| Ok(StreamChunk::Text(word.to_string())))
+            .collect();
+        chunks.extend(
+            streaming::completion_metadata_chunks(&response)
+                .into_iter()
+                .map(Ok),
+        );
+        Ok(Box::pin(futures::stream::iter(chunks)))
     }
 
     fn provider_name(&self) -> &str {
@@ -1154,6 +1601,10 @@ mod tests {
             api_key: None,
             endpoint: None,
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         };
 
         let provider2 = ProviderConfig {
@@ -1163,6 +1614,10 @@ mod tests {
             api_key: None,
             endpoint: Some("http://localhost:11434".to_string()),
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         };
 
         let provider3 = ProviderConfig {
@@ -1172,6 +1627,10 @@ mod tests {
             api_key: None,
             endpoint: None,
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         };
 
         registry
@@ -1204,6 +1663,43 @@ mod tests {
         assert_eq!(all_providers.len(), 3);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_registry_resolves_command_keys_once() {
+        let dir = TempDir::new().unwrap();
+        let runs = dir.path().join("runs");
+        let mut registry = ProviderRegistry::new();
+        let mut provider = ProviderConfig {
+            provider_name: Some("local".to_string()),
+            provider_type: ProviderType::LocalCustom,
+            model: "custom-model".to_string(),
+            api_key: Some(format!(
+                "cmd:echo run >> '{}'; echo sk-local",
+                runs.display()
+            )),
+            endpoint: Some("http://localhost:8080/v1".to_string()),
+            default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
+        };
+        registry
+            .providers
+            .insert("local".to_string(), provider.clone());
+        let run_count = || std::fs::read_to_string(&runs).unwrap().lines().count();
+
+        registry.create_client("local").unwrap();
+        registry.create_client("local").unwrap();
+        assert_eq!(run_count(), 1);
+
+        // Editing the reference resolves it again
+        provider.api_key = Some(format!("cmd:echo run >> '{}'; echo sk-new", runs.display()));
+        registry.providers.insert("local".to_string(), provider);
+        registry.create_client("local").unwrap();
+        assert_eq!(run_count(), 2);
+    }
+
     #[test]
     fn test_provider_registry_get_provider_config_path() {
         let test_dir = TempDir::new().unwrap();
@@ -1226,6 +1722,10 @@ mod tests {
                 api_key: None,
                 endpoint: Some("http://localhost:11434".to_string()),
                 default_options: CompletionOptions::default(),
+                pricing: None,
+                rate_limits: None,
+                replay: None,
+                transport: None,
             };
 
             // Save provider config
@@ -1264,6 +1764,10 @@ mod tests {
                 api_key: None,
                 endpoint: Some("http://localhost:11434".to_string()),
                 default_options: CompletionOptions::default(),
+                pricing: None,
+                rate_limits: None,
+                replay: None,
+                transport: None,
             };
 
             let registry = ProviderRegistry::new();
@@ -1298,6 +1802,10 @@ mod tests {
                 api_key: None,
                 endpoint: Some("localhost:8080/v1".to_string()),
                 default_options: CompletionOptions::default(),
+                pricing: None,
+                rate_limits: None,
+                replay: None,
+                transport: None,
             };
 
             let registry = ProviderRegistry::new();
@@ -1386,6 +1894,8 @@ mod tests {
         let messages = vec![ChatMessage {
             role: MessageRole::User,
             content: "Test".to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
         }];
 
         let response1 = mock
@@ -1410,7 +1920,8 @@ mod tests {
             base_url: None,
         };
 
-        let client = ProviderFactory::create_client(&provider).unwrap();
+        let client =
+            ProviderFactory::create_client(&provider, &TransportConfig::default()).unwrap();
         assert_eq!(client.provider_name(), "openai");
         assert_eq!(client.model_name(), "gpt-4");
     }
@@ -1422,7 +1933,8 @@ mod tests {
             api_key: "test-key".to_string(),
         };
 
-        let client = ProviderFactory::create_client(&provider).unwrap();
+        let client =
+            ProviderFactory::create_client(&provider, &TransportConfig::default()).unwrap();
         assert_eq!(client.provider_name(), "anthropic");
         assert_eq!(client.model_name(), "claude-3-opus");
     }
@@ -1434,7 +1946,8 @@ mod tests {
             base_url: None,
         };
 
-        let client = ProviderFactory::create_client(&provider).unwrap();
+        let client =
+            ProviderFactory::create_client(&provider, &TransportConfig::default()).unwrap();
         assert_eq!(client.provider_name(), "ollama");
         assert_eq!(client.model_name(), "llama2");
     }
@@ -1447,11 +1960,65 @@ mod tests {
             api_key: None,
         };
 
-        let client = ProviderFactory::create_client(&provider).unwrap();
+        let client =
+            ProviderFactory::create_client(&provider, &TransportConfig::default()).unwrap();
         assert_eq!(client.provider_name(), "local");
         assert_eq!(client.model_name(), "custom-model");
     }
 
+    #[test]
+    fn test_anthropic_messages_carry_tool_turns() {
+        let message = |role, content: &str| ChatMessage {
+            role,
+            content: content.to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        };
+        let call = ToolCall {
+            id: "toolu_1".to_string(),
+            name: "read_file".to_string(),
+            arguments: json!({"path": "src/lib.rs"}),
+        };
+        let messages = vec![
+            message(MessageRole::System, "system"),
+            message(MessageRole::User, "task"),
+            ChatMessage {
+                tool_calls: vec![call],
+                ..message(MessageRole::Assistant, "")
+            },
+            ChatMessage {
+                tool_call_id: Some("toolu_1".to_string()),
+                ..message(MessageRole::Tool, "fn main() {}")
+            },
+            message(MessageRole::User, "answer now"),
+        ];
+
+        let turns = anthropic_messages(&messages);
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[0], json!({"role": "user", "content": "task"}));
+        assert_eq!(turns[1]["content"][0]["type"], "tool_use");
+        assert_eq!(turns[1]["content"][0]["input"]["path"], "src/lib.rs");
+        assert_eq!(turns[2]["role"], "user");
+        assert_eq!(turns[2]["content"][0]["tool_use_id"], "toolu_1");
+        assert_eq!(turns[2]["content"][1]["text"], "answer now");
+    }
+
+    #[test]
+    fn test_workspace_relative_messages_strip_root() {
+        let message = ChatMessage {
+            role: MessageRole::User,
+            content: "Path: /work/repo\nPath: /work/repo/src/a.rs\nSee /work/repo2/b.rs"
+                .to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        };
+        let relative = workspace_relative_messages(&[message], Some(Path::new("/work/repo")));
+        assert_eq!(
+            relative[0].content,
+            "Path: .\nPath: src/a.rs\nSee /work/repo2/b.rs"
+        );
+    }
+
     #[test]
     fn test_message_role_serialization() {
         let role = MessageRole::System;
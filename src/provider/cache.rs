@@ -0,0 +1,328 @@
+//! Content-addressed cache of provider responses.
+//!
+//! Entries are keyed by a hash of the provider type, model, messages, and completion
+//! options, so an identical request to the same model is answered from disk instead of
+//! being sent (and billed) again. Entries expire after a TTL; once the cache grows past
+//! its size cap the oldest entries are evicted first.
+
+use crate::error::StorageError;
+use crate::provider::{ChatMessage, CompletionOptions, CompletionResponse};
+use crate::types::Hash;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_ttl_secs() -> Option<u64> {
+    Some(30 * 24 * 60 * 60)
+}
+
+fn default_max_size_mb() -> Option<u64> {
+    Some(512)
+}
+
+/// Response cache settings (`[system.storage.response_cache]`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResponseCacheConfig {
+    /// Answer repeated provider requests from the cache
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Entry lifetime in seconds; unset keeps entries until size eviction
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: Option<u64>,
+    /// Size cap in megabytes; unset lets the cache grow without bound
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: Option<u64>,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            ttl_secs: default_ttl_secs(),
+            max_size_mb: default_max_size_mb(),
+        }
+    }
+}
+
+/// Cache key for one provider request
+pub fn response_cache_key(
+    provider_type: &str,
+    model: &str,
+    messages: &[ChatMessage],
+    options: &CompletionOptions,
+) -> Hash {
+    let encoded = serde_json::to_vec(&(provider_type, model, messages, options))
+        .expect("provider requests serialize to JSON");
+    *blake3::hash(&encoded).as_bytes()
+}
+
+/// Response cache rooted at a directory; entries live at `<root>/<hh>/<key hex>.json`
+#[derive(Debug)]
+pub struct ResponseCache {
+    root: PathBuf,
+    ttl: Option<Duration>,
+    max_bytes: Option<u64>,
+    /// Bytes currently on disk, tracked so puts only scan the cache when over the cap;
+    /// measured on the first put so opening the cache never walks it
+    size: Mutex<Option<u64>>,
+}
+
+impl ResponseCache {
+    /// Open (creating if needed) a response cache at `root`
+    pub fn open(
+        root: impl AsRef<Path>,
+        config: &ResponseCacheConfig,
+    ) -> Result<Self, StorageError> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(&root)?;
+        Ok(Self {
+            root,
+            ttl: config.ttl_secs.map(Duration::from_secs),
+            max_bytes: config.max_size_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+            size: Mutex::new(None),
+        })
+    }
+
+    fn entry_path(&self, key: &Hash) -> PathBuf {
+        let hex = hex::encode(key);
+        self.root.join(&hex[..2]).join(format!("{}.json", hex))
+    }
+
+    fn is_expired(&self, modified: SystemTime) -> bool {
+        self.ttl.is_some_and(|ttl| {
+            SystemTime::now()
+                .duration_since(modified)
+                .is_ok_and(|age| age > ttl)
+        })
+    }
+
+    /// Cached response for `key`; expired or unreadable entries are removed and missed
+    pub fn get(&self, key: &Hash) -> Result<Option<CompletionResponse>, StorageError> {
+        let path = self.entry_path(key);
+        let (bytes, modified) = match fs::read(&path) {
+            Ok(bytes) => (bytes, fs::metadata(&path)?.modified()?),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(StorageError::IoError(e)),
+        };
+        if self.is_expired(modified) {
+            self.remove(&path, bytes.len() as u64)?;
+            return Ok(None);
+        }
+        match serde_json::from_slice(&bytes) {
+            Ok(response) => Ok(Some(response)),
+            Err(_) => {
+                self.remove(&path, bytes.len() as u64)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Store `response` under `key`, then evict if the cache is over its size cap
+    pub fn put(&self, key: &Hash, response: &CompletionResponse) -> Result<(), StorageError> {
+        let path = self.entry_path(key);
+        let bytes = serde_json::to_vec(response).map_err(|e| StorageError::IoError(e.into()))?;
+        let dir = path.parent().expect("cache entry path has a parent");
+        fs::create_dir_all(dir)?;
+        let replaced = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let tmp = dir.join(format!(".{}.{}.tmp", hex::encode(key), std::process::id()));
+        fs::write(&tmp, &bytes)?;
+        fs::rename(&tmp, &path)?;
+
+        let over_cap = {
+            let mut size = self.size.lock();
+            let total = match *size {
+                Some(size) => size.saturating_sub(replaced) + bytes.len() as u64,
+                // The first scan already counts the entry just written
+                None => list_entries(&self.root)?.iter().map(|e| e.len).sum(),
+            };
+            *size = Some(total);
+            self.max_bytes.is_some_and(|max| total > max)
+        };
+        if over_cap {
+            self.evict()?;
+        }
+        Ok(())
+    }
+
+    /// Drop expired entries, then the oldest until the cache fits its size cap.
+    /// Returns the number of entries removed.
+    pub fn evict(&self) -> Result<usize, StorageError> {
+        let mut entries = list_entries(&self.root)?;
+        entries.sort_by_key(|e| e.modified);
+        let mut total: u64 = entries.iter().map(|e| e.len).sum();
+        let mut removed = 0;
+        for entry in &entries {
+            let over_cap = self.max_bytes.is_some_and(|max| total > max);
+            if !over_cap && !self.is_expired(entry.modified) {
+                continue;
+            }
+            match fs::remove_file(&entry.path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(StorageError::IoError(e)),
+            }
+            total -= entry.len;
+            removed += 1;
+        }
+        *self.size.lock() = Some(total);
+        Ok(removed)
+    }
+
+    fn remove(&self, path: &Path, len: u64) -> Result<(), StorageError> {
+        match fs::remove_file(path) {
+            Ok(()) => {
+                if let Some(size) = self.size.lock().as_mut() {
+                    *size = size.saturating_sub(len);
+                }
+                Ok(())
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(StorageError::IoError(e)),
+        }
+    }
+}
+
+struct CacheEntry {
+    path: PathBuf,
+    len: u64,
+    modified: SystemTime,
+}
+
+fn list_entries(root: &Path) -> Result<Vec<CacheEntry>, StorageError> {
+    let mut entries = Vec::new();
+    for shard in fs::read_dir(root)? {
+        let shard = shard?;
+        if !shard.file_type()?.is_dir() {
+            continue;
+        }
+        for file in fs::read_dir(shard.path())? {
+            let file = file?;
+            let path = file.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let metadata = file.metadata()?;
+            entries.push(CacheEntry {
+                path,
+                len: metadata.len(),
+                modified: metadata.modified()?,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{MessageRole, TokenUsage};
+    use tempfile::TempDir;
+
+    fn response(content: &str) -> CompletionResponse {
+        CompletionResponse {
+            content: content.to_string(),
+            model: "gpt-test".to_string(),
+            usage: TokenUsage {
+                prompt_tokens: 10,
+                completion_tokens: 5,
+                total_tokens: 15,
+                estimated: false,
+            },
+            finish_reason: Some("stop".to_string()),
+            tool_calls: Vec::new(),
+        }
+    }
+
+    fn key(text: &str) -> Hash {
+        let messages = vec![ChatMessage {
+            role: MessageRole::User,
+            content: text.to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }];
+        response_cache_key(
+            "openai",
+            "gpt-test",
+            &messages,
+            &CompletionOptions::default(),
+        )
+    }
+
+    #[test]
+    fn keys_cover_model_messages_and_options() {
+        let messages = vec![ChatMessage {
+            role: MessageRole::User,
+            content: "hi".to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }];
+        let options = CompletionOptions::default();
+        let base = response_cache_key("openai", "a", &messages, &options);
+        assert_eq!(base, response_cache_key("openai", "a", &messages, &options));
+        assert_ne!(base, response_cache_key("ollama", "a", &messages, &options));
+        assert_ne!(base, response_cache_key("openai", "b", &messages, &options));
+        let warmer = CompletionOptions {
+            temperature: Some(0.9),
+            ..CompletionOptions::default()
+        };
+        assert_ne!(base, response_cache_key("openai", "a", &messages, &warmer));
+    }
+
+    #[test]
+    fn put_then_get_round_trips_and_ttl_expires() {
+        let dir = TempDir::new().unwrap();
+        let cache = ResponseCache::open(dir.path(), &ResponseCacheConfig::default()).unwrap();
+        assert!(cache.get(&key("a")).unwrap().is_none());
+        cache.put(&key("a"), &response("cached")).unwrap();
+        assert_eq!(cache.get(&key("a")).unwrap().unwrap().content, "cached");
+
+        let expiring = ResponseCache::open(
+            dir.path(),
+            &ResponseCacheConfig {
+                ttl_secs: Some(0),
+                ..ResponseCacheConfig::default()
+            },
+        )
+        .unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(expiring.get(&key("a")).unwrap().is_none());
+        assert!(!cache.entry_path(&key("a")).exists());
+    }
+
+    #[test]
+    fn size_cap_evicts_oldest_entries_first() {
+        let dir = TempDir::new().unwrap();
+        let mut cache = ResponseCache::open(dir.path(), &ResponseCacheConfig::default()).unwrap();
+        cache.put(&key("old"), &response("old")).unwrap();
+        let entry_len = fs::metadata(cache.entry_path(&key("old"))).unwrap().len();
+        std::thread::sleep(Duration::from_millis(20));
+        cache.max_bytes = Some(entry_len + entry_len / 2);
+        cache.put(&key("new"), &response("new")).unwrap();
+        assert!(cache.get(&key("old")).unwrap().is_none());
+        assert_eq!(cache.get(&key("new")).unwrap().unwrap().content, "new");
+    }
+
+    #[test]
+    fn first_put_counts_entries_already_on_disk() {
+        let dir = TempDir::new().unwrap();
+        let cache = ResponseCache::open(dir.path(), &ResponseCacheConfig::default()).unwrap();
+        cache.put(&key("old"), &response("old")).unwrap();
+        let entry_len = fs::metadata(cache.entry_path(&key("old"))).unwrap().len();
+        std::thread::sleep(Duration::from_millis(20));
+
+        let mut reopened =
+            ResponseCache::open(dir.path(), &ResponseCacheConfig::default()).unwrap();
+        assert!(reopened.size.lock().is_none());
+        reopened.max_bytes = Some(entry_len + entry_len / 2);
+        reopened.put(&key("new"), &response("new")).unwrap();
+        assert!(reopened.get(&key("old")).unwrap().is_none());
+        assert_eq!(*reopened.size.lock(), Some(entry_len));
+    }
+}
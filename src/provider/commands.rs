@@ -62,8 +62,11 @@ impl ProviderCommandService {
             "anthropic" => Ok(ProviderType::Anthropic),
             "ollama" => Ok(ProviderType::Ollama),
             "local" => Ok(ProviderType::LocalCustom),
+            "replay" => Ok(ProviderType::Replay),
+            "template" => Ok(ProviderType::Template),
             _ => Err(ApiError::ConfigError(format!(
-                "Invalid type filter: {}. Must be openai, anthropic, ollama, or local",
+                "Invalid type filter: {}. Must be openai, anthropic, ollama, local, replay, or \
+                 template",
                 type_str
             ))),
         }
@@ -73,7 +76,10 @@ impl ProviderCommandService {
         match provider_type {
             ProviderType::OpenAI => Some("https://api.openai.com/v1".to_string()),
             ProviderType::Ollama => Some("http://localhost:11434".to_string()),
-            ProviderType::LocalCustom | ProviderType::Anthropic => None,
+            ProviderType::LocalCustom
+            | ProviderType::Anthropic
+            | ProviderType::Replay
+            | ProviderType::Template => None,
         }
     }
 
@@ -81,7 +87,10 @@ impl ProviderCommandService {
         match provider_type {
             ProviderType::OpenAI => Some("OPENAI_API_KEY"),
             ProviderType::Anthropic => Some("ANTHROPIC_API_KEY"),
-            ProviderType::Ollama | ProviderType::LocalCustom => None,
+            ProviderType::Ollama
+            | ProviderType::LocalCustom
+            | ProviderType::Replay
+            | ProviderType::Template => None,
         }
     }
 
@@ -100,6 +109,10 @@ impl ProviderCommandService {
             api_key,
             endpoint,
             default_options,
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         }
     }
 
@@ -1,6 +1,8 @@
 use crate::error::ApiError;
-use crate::provider::profile::{ProviderConfig, ProviderType, ValidationResult};
-use crate::provider::ProviderRegistry;
+use crate::provider::profile::{
+    ApiKeySource, ProviderConfig, ProviderType, ReplayMode, ValidationResult,
+};
+use crate::provider::{transport, ProviderRegistry};
 
 pub struct ProviderDiagnosticsService;
 
@@ -51,27 +53,22 @@ impl ProviderDiagnosticsService {
         Ok(Some(provider))
     }
 
+    /// Where the API key comes from, without the key itself. `cmd:` references are not run.
     pub fn resolve_api_key_status(provider: &ProviderConfig) -> String {
-        match provider.provider_type {
-            ProviderType::OpenAI => {
-                if provider.api_key.is_some() {
-                    "Set (from config)".to_string()
-                } else if std::env::var("OPENAI_API_KEY").is_ok() {
-                    "Set (from environment)".to_string()
-                } else {
-                    "Not set".to_string()
-                }
-            }
-            ProviderType::Anthropic => {
-                if provider.api_key.is_some() {
-                    "Set (from config)".to_string()
-                } else if std::env::var("ANTHROPIC_API_KEY").is_ok() {
-                    "Set (from environment)".to_string()
-                } else {
-                    "Not set".to_string()
-                }
-            }
-            ProviderType::Ollama | ProviderType::LocalCustom => "Not required".to_string(),
+        if let Some(source) = provider.api_key_source() {
+            return match source {
+                ApiKeySource::Plaintext(_) => "Set (from config, plaintext)".to_string(),
+                ApiKeySource::Command(_) => format!("From {} (command not run)", source),
+                _ => match source.resolve() {
+                    Ok(_) => format!("Set (from {})", source),
+                    Err(_) => format!("Unresolved ({})", source),
+                },
+            };
+        }
+        match provider.default_api_key_env() {
+            Some(name) if std::env::var(name).is_ok() => "Set (from environment)".to_string(),
+            Some(_) => "Not set".to_string(),
+            None => "Not required".to_string(),
         }
     }
 
@@ -126,44 +123,50 @@ impl ProviderDiagnosticsService {
 
         match provider.provider_type {
             ProviderType::OpenAI | ProviderType::Anthropic => {
-                let api_key_available = provider.api_key.is_some()
-                    || match provider.provider_type {
-                        ProviderType::OpenAI => std::env::var("OPENAI_API_KEY").is_ok(),
-                        ProviderType::Anthropic => std::env::var("ANTHROPIC_API_KEY").is_ok(),
-                        _ => false,
-                    };
-
-                if api_key_available {
-                    let source = if provider.api_key.is_some() {
-                        "from config"
-                    } else {
-                        "from environment"
-                    };
-                    result.add_check(&format!("API key available ({})", source), true);
-                } else {
-                    let env_var = match provider.provider_type {
-                        ProviderType::OpenAI => "OPENAI_API_KEY",
-                        ProviderType::Anthropic => "ANTHROPIC_API_KEY",
-                        _ => unreachable!(),
-                    };
-                    result.add_error(format!(
-                        "API key not found (set {} or add to config)",
-                        env_var
-                    ));
-                }
+                Self::validate_api_key(&provider, &mut result);
             }
             ProviderType::Ollama => {
                 result.add_check("API key not required for local provider", true);
             }
+            ProviderType::Template => {
+                result.add_check("Offline provider: no API key or network required", true);
+            }
             ProviderType::LocalCustom => {
                 if provider.api_key.is_some() {
-                    result.add_check("API key configured for local custom provider", true);
+                    Self::validate_api_key(&provider, &mut result);
                 } else {
                     result.add_warning(
                         "No API key configured for local custom provider. Some OpenAI-compatible endpoints require authentication.".to_string(),
                     );
                 }
             }
+            ProviderType::Replay => match &provider.replay {
+                Some(replay) if replay.mode == ReplayMode::Record => {
+                    match replay.record_provider.as_deref() {
+                        Some(recorded) if registry.get(recorded).is_some() => {
+                            result.add_check(&format!("Records provider '{}'", recorded), true);
+                        }
+                        Some(recorded) => {
+                            result.add_error(format!("Recorded provider not found: {}", recorded));
+                        }
+                        None => result
+                            .add_error("Record mode requires replay.record_provider".to_string()),
+                    }
+                }
+                Some(replay) => {
+                    if replay.cassette.is_file() {
+                        result.add_check("Replay cassette exists", true);
+                    } else {
+                        result.add_error(format!(
+                            "Replay cassette not found: {}",
+                            replay.cassette.display()
+                        ));
+                    }
+                }
+                None => result.add_error(
+                    "Replay provider requires a [replay] section with a cassette".to_string(),
+                ),
+            },
         }
 
         if let Some(endpoint) = &provider.endpoint {
@@ -178,6 +181,18 @@ impl ProviderDiagnosticsService {
             result.add_check("Endpoint URL (optional)", true);
         }
 
+        if let Some(transport) = &provider.transport {
+            match provider.provider_type {
+                ProviderType::Replay | ProviderType::Template => result.add_warning(
+                    "[transport] has no effect: this provider makes no HTTP requests".to_string(),
+                ),
+                _ => match transport::build_http_client(transport) {
+                    Ok(_) => result.add_check("Transport settings are valid", true),
+                    Err(e) => result.add_error(e.to_string()),
+                },
+            }
+        }
+
         if let Some(temp) = provider.default_options.temperature {
             if (0.0..=2.0).contains(&temp) {
                 result.add_check("Temperature is in valid range (0.0-2.0)", true);
@@ -208,6 +223,41 @@ impl ProviderDiagnosticsService {
         Ok(result)
     }
 
+    /// Resolve the configured key (running `cmd:` references) and warn on plaintext keys
+    fn validate_api_key(provider: &ProviderConfig, result: &mut ValidationResult) {
+        let Some(source) = provider.api_key_source() else {
+            match provider.default_api_key_env() {
+                Some(name) if std::env::var(name).is_ok() => {
+                    result.add_check("API key available (from environment)", true);
+                }
+                Some(name) => result.add_error(format!(
+                    "API key not found (set {} or add api_key to config)",
+                    name
+                )),
+                None => {}
+            }
+            return;
+        };
+        if source.is_plaintext() {
+            result.add_warning(
+                "API key is stored in plaintext in the provider config; use env:VAR, file:PATH, \
+                 or cmd:COMMAND instead"
+                    .to_string(),
+            );
+        }
+        match source.resolve() {
+            Ok(_) => {
+                let origin = if source.is_plaintext() {
+                    "from config".to_string()
+                } else {
+                    format!("from {}", source)
+                };
+                result.add_check(&format!("API key available ({})", origin), true);
+            }
+            Err(e) => result.add_error(format!("API key unresolved ({}): {}", source, e)),
+        }
+    }
+
     pub fn list_available_models(
         registry: &ProviderRegistry,
         provider_name: &str,
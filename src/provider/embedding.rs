@@ -0,0 +1,61 @@
+//! Vector helpers for embeddings.
+//!
+//! The hashing embedder is a deterministic stand-in for a model: each lowercase
+//! alphanumeric token is hashed to one of `HASHING_DIMENSIONS` signed buckets and the
+//! result is L2-normalized. Texts sharing words score higher under cosine similarity,
+//! which is enough for offline runs and tests.
+
+/// Length of vectors from `hashing_embedding`
+pub const HASHING_DIMENSIONS: usize = 256;
+
+/// Hashed bag-of-words vector for `text`
+pub fn hashing_embedding(text: &str) -> Vec<f32> {
+    let mut vector = vec![0.0f32; HASHING_DIMENSIONS];
+    let tokens = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty());
+    for token in tokens {
+        let hash = blake3::hash(token.to_lowercase().as_bytes());
+        let bytes = hash.as_bytes();
+        let index = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+            % HASHING_DIMENSIONS;
+        vector[index] += if bytes[4] & 1 == 0 { 1.0 } else { -1.0 };
+    }
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+/// Cosine similarity; 0 for empty, zero, or mismatched vectors
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashing_embedding_ranks_shared_words_higher() {
+        let query = hashing_embedding("parse config file");
+        let related = hashing_embedding("Loads the Config file and parses it");
+        let unrelated = hashing_embedding("renders a progress bar");
+        assert_eq!(query.len(), HASHING_DIMENSIONS);
+        assert_eq!(query, hashing_embedding("PARSE config, file"));
+        assert!(cosine_similarity(&query, &related) > cosine_similarity(&query, &unrelated));
+        assert!((cosine_similarity(&query, &query) - 1.0).abs() < 1e-5);
+        assert_eq!(cosine_similarity(&query, &hashing_embedding("")), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+    }
+}
@@ -1,5 +1,10 @@
 pub mod config;
+pub mod secret;
 pub mod validation;
 
-pub use config::{ProviderConfig, ProviderType};
+pub use config::{
+    ModelPricing, ProviderConfig, ProviderRateLimits, ProviderType, ReplayConfig, ReplayMode,
+    TransportConfig,
+};
+pub use secret::ApiKeySource;
 pub use validation::{provider_type_slug, ValidationResult};
@@ -1,6 +1,9 @@
+use super::secret::ApiKeySource;
 use crate::error::ApiError;
 use crate::provider::{CompletionOptions, ModelProvider};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 /// Model provider configuration owned by the provider domain.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,7 +18,8 @@ pub struct ProviderConfig {
     /// Model identifier.
     pub model: String,
 
-    /// API key optional and can be loaded from environment.
+    /// API key, or a reference to it (`env:`, `file:`, `cmd:`); see `ApiKeySource`.
+    /// Optional where the provider reads a default environment variable.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
 
@@ -26,6 +30,102 @@ pub struct ProviderConfig {
     /// Default completion options for this provider.
     #[serde(default)]
     pub default_options: CompletionOptions,
+
+    /// Per-model token prices used for cost estimates and budgets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<ModelPricing>,
+
+    /// Request, token, and concurrency limits applied by the generation queue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limits: Option<ProviderRateLimits>,
+
+    /// Cassette settings for `replay` providers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay: Option<ReplayConfig>,
+
+    /// HTTP proxy, CA, timeout, and header settings for network providers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<TransportConfig>,
+}
+
+/// Token prices in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Price per million prompt (input) tokens.
+    pub input_per_million: f64,
+    /// Price per million completion (output) tokens.
+    pub output_per_million: f64,
+}
+
+impl ModelPricing {
+    /// Cost in USD for the given token counts.
+    pub fn cost(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input_per_million
+            + output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Client-side rate limits for one provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderRateLimits {
+    /// Requests per minute (token bucket).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    /// Prompt plus completion tokens per minute (token bucket).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_per_minute: Option<u32>,
+    /// Upper bound on concurrent requests; the queue adapts below it on 429s.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+}
+
+/// Cassette settings for a `replay` provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayConfig {
+    /// JSONL cassette of recorded request/response pairs.
+    pub cassette: PathBuf,
+    /// Whether to serve from the cassette or record into it.
+    #[serde(default)]
+    pub mode: ReplayMode,
+    /// Provider whose responses are recorded (record mode only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_provider: Option<String>,
+}
+
+/// How a `replay` provider uses its cassette.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayMode {
+    /// Serve recorded responses offline; unmatched requests fail.
+    #[default]
+    Replay,
+    /// Call `record_provider` and append each response to the cassette.
+    Record,
+}
+
+/// HTTP transport settings applied to every request a provider client makes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransportConfig {
+    /// Proxy URL for all requests (`http://`, `https://`, or `socks5://`).
+    /// Without one, environment proxy variables are ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Hosts, domains, or CIDR ranges that bypass `proxy`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+    /// PEM bundle of extra root certificates, trusted alongside the system roots.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<PathBuf>,
+    /// TCP connect timeout in seconds (default 10).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+    /// Whole-request timeout in seconds, including streamed bodies (default 120).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout_secs: Option<u64>,
+    /// Headers added to every request, e.g. for an authenticating gateway.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
 }
 
 /// Provider type enumeration.
@@ -40,6 +140,10 @@ pub enum ProviderType {
     Ollama,
     #[serde(rename = "local")]
     LocalCustom,
+    #[serde(rename = "replay")]
+    Replay,
+    #[serde(rename = "template")]
+    Template,
 }
 
 impl ProviderConfig {
@@ -127,16 +231,76 @@ impl ProviderConfig {
             }
         }
 
+        if let Some(pricing) = &self.pricing {
+            if pricing.input_per_million < 0.0 || pricing.output_per_million < 0.0 {
+                return Err("Pricing must not be negative".to_string());
+            }
+        }
+
+        if let Some(limits) = &self.rate_limits {
+            if limits.requests_per_minute == Some(0)
+                || limits.tokens_per_minute == Some(0)
+                || limits.max_concurrency == Some(0)
+            {
+                return Err("Rate limits must be greater than zero".to_string());
+            }
+        }
+
+        if self.provider_type == ProviderType::Replay {
+            let Some(replay) = &self.replay else {
+                return Err(
+                    "Replay provider requires a [replay] section with a cassette".to_string(),
+                );
+            };
+            if replay.mode == ReplayMode::Record && replay.record_provider.is_none() {
+                return Err("Record mode requires replay.record_provider".to_string());
+            }
+        }
+
         Ok(())
     }
 
-    /// Convert ProviderConfig to ModelProvider.
-    pub fn to_model_provider(&self) -> Result<ModelProvider, ApiError> {
-        let api_key = self.api_key.clone().or_else(|| match self.provider_type {
-            ProviderType::OpenAI => std::env::var("OPENAI_API_KEY").ok(),
-            ProviderType::Anthropic => std::env::var("ANTHROPIC_API_KEY").ok(),
+    /// Configured API key source, if `api_key` is set
+    pub fn api_key_source(&self) -> Option<ApiKeySource> {
+        self.api_key.as_deref().map(ApiKeySource::parse)
+    }
+
+    /// Environment variable read when `api_key` is unset
+    pub fn default_api_key_env(&self) -> Option<&'static str> {
+        match self.provider_type {
+            ProviderType::OpenAI => Some("OPENAI_API_KEY"),
+            ProviderType::Anthropic => Some("ANTHROPIC_API_KEY"),
             _ => None,
-        });
+        }
+    }
+
+    /// Resolve `api_key` (or the default environment variable) to the key itself
+    pub fn resolve_api_key(&self) -> Result<Option<String>, ApiError> {
+        match self.api_key_source() {
+            Some(source) => source.resolve().map(Some).map_err(|e| {
+                ApiError::ProviderNotConfigured(format!(
+                    "Cannot resolve api_key for provider '{}' ({}): {}",
+                    self.provider_name.as_deref().unwrap_or("unknown"),
+                    source,
+                    e
+                ))
+            }),
+            None => Ok(self
+                .default_api_key_env()
+                .and_then(|name| std::env::var(name).ok())),
+        }
+    }
+
+    /// Convert ProviderConfig to ModelProvider, resolving the API key.
+    pub fn to_model_provider(&self) -> Result<ModelProvider, ApiError> {
+        self.to_model_provider_with_key(self.resolve_api_key()?)
+    }
+
+    /// Convert ProviderConfig to ModelProvider with an already resolved API key.
+    pub fn to_model_provider_with_key(
+        &self,
+        api_key: Option<String>,
+    ) -> Result<ModelProvider, ApiError> {
 
         match self.provider_type {
             ProviderType::OpenAI => {
@@ -180,6 +344,12 @@ impl ProviderConfig {
                     api_key,
                 })
             }
+            ProviderType::Template => Ok(ModelProvider::Template {
+                model: self.model.clone(),
+            }),
+            ProviderType::Replay => Err(ApiError::ProviderNotConfigured(
+                "Replay providers are created through the provider registry".to_string(),
+            )),
         }
     }
 }
@@ -199,6 +369,10 @@ mod tests {
             api_key: None,
             endpoint: Some("chat.internal.jerkytreats.dev".to_string()),
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         };
 
         assert!(provider.validate().is_ok());
@@ -217,6 +391,10 @@ mod tests {
             api_key: Some("test-key".to_string()),
             endpoint: Some("chat.internal.jerkytreats.dev".to_string()),
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         };
 
         let model_provider = provider.to_model_provider().unwrap();
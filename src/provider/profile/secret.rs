@@ -0,0 +1,177 @@
+//! API key references for provider configs.
+//!
+//! `api_key` may hold a reference instead of the key itself, so the key never has to be
+//! written to the provider TOML. The provider registry resolves it when it first creates a
+//! client for that provider and reuses the key until `api_key` changes, so a `cmd:` command
+//! runs once per `meld` invocation (or once per `watch` daemon), not once per request.
+//!
+//! ```toml
+//! api_key = "env:OPENAI_API_KEY"        # environment variable
+//! api_key = "file:~/.secrets/openai"    # first line of a file; `~/` is the home directory
+//! api_key = "cmd:pass show openai"      # first line of a command's stdout (run by the shell)
+//! ```
+//!
+//! Any other value is a plaintext key, which still works but `provider validate` warns.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Where a provider's API key comes from
+#[derive(Clone, PartialEq, Eq)]
+pub enum ApiKeySource {
+    Plaintext(String),
+    Env(String),
+    File(String),
+    Command(String),
+}
+
+impl ApiKeySource {
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        if let Some(name) = value.strip_prefix("env:") {
+            Self::Env(name.trim().to_string())
+        } else if let Some(path) = value.strip_prefix("file:") {
+            Self::File(path.trim().to_string())
+        } else if let Some(command) = value.strip_prefix("cmd:") {
+            Self::Command(command.trim().to_string())
+        } else {
+            Self::Plaintext(value.to_string())
+        }
+    }
+
+    pub fn is_plaintext(&self) -> bool {
+        matches!(self, Self::Plaintext(_))
+    }
+
+    /// The key itself. Errors describe the reference, never a secret value.
+    pub fn resolve(&self) -> Result<String, String> {
+        let value = match self {
+            Self::Plaintext(key) => key.clone(),
+            Self::Env(name) => std::env::var(name)
+                .map_err(|_| format!("environment variable {} is not set", name))?,
+            Self::File(path) => {
+                let resolved = expand_home(path)?;
+                std::fs::read_to_string(&resolved)
+                    .map_err(|e| format!("cannot read {}: {}", resolved.display(), e))?
+            }
+            Self::Command(command) => run_command(command)?,
+        };
+        let key = value.lines().next().unwrap_or_default().trim();
+        if key.is_empty() {
+            return Err(format!("{} is empty", self));
+        }
+        Ok(key.to_string())
+    }
+}
+
+/// Plaintext keys are redacted
+impl fmt::Debug for ApiKeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Plaintext(_) => f.write_str("Plaintext(<redacted>)"),
+            Self::Env(name) => f.debug_tuple("Env").field(name).finish(),
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Command(command) => f.debug_tuple("Command").field(command).finish(),
+        }
+    }
+}
+
+/// The reference as written in config; plaintext keys show as `plaintext`
+impl fmt::Display for ApiKeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Plaintext(_) => write!(f, "plaintext"),
+            Self::Env(name) => write!(f, "env:{}", name),
+            Self::File(path) => write!(f, "file:{}", path),
+            Self::Command(command) => write!(f, "cmd:{}", command),
+        }
+    }
+}
+
+fn expand_home(path: &str) -> Result<PathBuf, String> {
+    match path.strip_prefix("~/") {
+        Some(rest) => std::env::var("HOME")
+            .map(|home| PathBuf::from(home).join(rest))
+            .map_err(|_| format!("cannot expand {}: HOME is not set", path)),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+fn run_command(command: &str) -> Result<String, String> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let output = Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("cannot run `{}`: {}", command, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "`{}` failed ({}): {}",
+            command,
+            output.status,
+            stderr.lines().next().unwrap_or_default().trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_references_and_plaintext() {
+        assert_eq!(
+            ApiKeySource::parse("env:OPENAI_API_KEY"),
+            ApiKeySource::Env("OPENAI_API_KEY".to_string())
+        );
+        assert_eq!(
+            ApiKeySource::parse("file:~/.secrets/openai"),
+            ApiKeySource::File("~/.secrets/openai".to_string())
+        );
+        assert_eq!(
+            ApiKeySource::parse("cmd: pass show openai"),
+            ApiKeySource::Command("pass show openai".to_string())
+        );
+        let plain = ApiKeySource::parse("sk-abc");
+        assert!(plain.is_plaintext());
+        assert_eq!(plain.to_string(), "plaintext");
+        assert_eq!(format!("{:?}", plain), "Plaintext(<redacted>)");
+    }
+
+    #[test]
+    fn resolves_files_and_commands_to_their_first_line() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("key");
+        std::fs::write(&path, "sk-from-file\n").unwrap();
+        let file = ApiKeySource::parse(&format!("file:{}", path.display()));
+        assert_eq!(file.resolve().unwrap(), "sk-from-file");
+
+        let missing = ApiKeySource::parse("env:MELD_SECRET_TEST_UNSET_VARIABLE");
+        assert_eq!(
+            missing.resolve().unwrap_err(),
+            "environment variable MELD_SECRET_TEST_UNSET_VARIABLE is not set"
+        );
+
+        if cfg!(unix) {
+            let command = ApiKeySource::parse("cmd:printf 'sk-from-cmd\\nextra'");
+            assert_eq!(command.resolve().unwrap(), "sk-from-cmd");
+            // Failures report the command and its stderr, never its stdout
+            let failing = ApiKeySource::parse("cmd:printf 'sk-%s' leaked; exit 3");
+            let error = failing.resolve().unwrap_err();
+            assert!(error.contains("failed"), "{}", error);
+            assert!(!error.contains("sk-leaked"), "{}", error);
+            assert_eq!(
+                ApiKeySource::parse("cmd:true").resolve().unwrap_err(),
+                "cmd:true is empty"
+            );
+        }
+    }
+}
@@ -50,5 +50,7 @@ pub fn provider_type_slug(provider_type: ProviderType) -> &'static str {
         ProviderType::Anthropic => "anthropic",
         ProviderType::Ollama => "ollama",
         ProviderType::LocalCustom => "local",
+        ProviderType::Replay => "replay",
+        ProviderType::Template => "template",
     }
 }
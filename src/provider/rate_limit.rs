@@ -0,0 +1,152 @@
+//! Rate-limit headers returned by providers.
+//!
+//! Reads `retry-after-ms`, `retry-after` (seconds or HTTP date), and the OpenAI-style
+//! `x-ratelimit-remaining-*` / `x-ratelimit-reset-*` pairs so callers know how long to
+//! wait before the next request.
+
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+
+/// Kinds reported by `x-ratelimit-remaining-<kind>` / `x-ratelimit-reset-<kind>`
+const RATE_LIMIT_KINDS: &[&str] = &["requests", "tokens"];
+
+/// Longest wait taken from the headers; larger values are clamped to it
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// How long the provider asked us to wait, if it said so, capped at [`MAX_RETRY_AFTER`]
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    requested_wait(headers).map(|wait| wait.min(MAX_RETRY_AFTER))
+}
+
+fn requested_wait(headers: &HeaderMap) -> Option<Duration> {
+    if let Some(ms) = header_str(headers, "retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        return non_negative_secs(ms / 1000.0);
+    }
+    if let Some(value) = header_str(headers, "retry-after") {
+        if let Ok(secs) = value.parse::<f64>() {
+            return non_negative_secs(secs);
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            let wait = date.signed_duration_since(chrono::Utc::now());
+            return Some(wait.to_std().unwrap_or(Duration::ZERO));
+        }
+    }
+
+    // Longest reset among exhausted (or unreported) limits
+    RATE_LIMIT_KINDS
+        .iter()
+        .filter(|kind| {
+            header_str(headers, &format!("x-ratelimit-remaining-{}", kind))
+                .and_then(|v| v.parse::<u64>().ok())
+                .is_none_or(|remaining| remaining == 0)
+        })
+        .filter_map(|kind| header_str(headers, &format!("x-ratelimit-reset-{}", kind)))
+        .filter_map(parse_reset_duration)
+        .max()
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok().map(str::trim)
+}
+
+fn non_negative_secs(secs: f64) -> Option<Duration> {
+    // Values too large for a Duration saturate instead of panicking
+    (secs.is_finite() && secs >= 0.0)
+        .then(|| Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX))
+}
+
+/// Parse reset durations such as `1s`, `250ms`, `6m0s`, `1h2m3.5s`, or bare seconds
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<f64>() {
+        return non_negative_secs(secs);
+    }
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += number * scale;
+        rest = &rest[unit_len..];
+    }
+    non_negative_secs(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(
+                HeaderName::from_static(name),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        map
+    }
+
+    #[test]
+    fn retry_after_prefers_explicit_headers() {
+        let map = headers(&[
+            ("retry-after-ms", "1500"),
+            ("retry-after", "9"),
+            ("x-ratelimit-reset-requests", "20s"),
+        ]);
+        assert_eq!(retry_after(&map), Some(Duration::from_millis(1500)));
+        let map = headers(&[("retry-after", "2")]);
+        assert_eq!(retry_after(&map), Some(Duration::from_secs(2)));
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn reset_headers_count_only_exhausted_limits() {
+        let map = headers(&[
+            ("x-ratelimit-remaining-requests", "0"),
+            ("x-ratelimit-reset-requests", "1m30s"),
+            ("x-ratelimit-remaining-tokens", "5000"),
+            ("x-ratelimit-reset-tokens", "5m"),
+        ]);
+        assert_eq!(retry_after(&map), Some(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn parses_go_style_durations() {
+        assert_eq!(
+            parse_reset_duration("250ms"),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(
+            parse_reset_duration("1h2m3.5s"),
+            Some(Duration::from_secs_f64(3723.5))
+        );
+        assert_eq!(parse_reset_duration("soon"), None);
+    }
+
+    #[test]
+    fn huge_waits_are_clamped() {
+        let map = headers(&[("retry-after", "1e30")]);
+        assert_eq!(retry_after(&map), Some(MAX_RETRY_AFTER));
+        let map = headers(&[("x-ratelimit-reset-requests", "1e25")]);
+        assert_eq!(retry_after(&map), Some(MAX_RETRY_AFTER));
+        assert_eq!(
+            parse_reset_duration("99999999999999999999h"),
+            Some(Duration::MAX)
+        );
+    }
+}
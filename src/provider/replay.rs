@@ -0,0 +1,364 @@
+//! Record/replay provider for offline, deterministic generation runs.
+//!
+//! A `replay` provider owns a cassette: a JSONL file with one recorded request/response
+//! pair per line. In record mode it wraps another configured provider and appends every
+//! successful completion; in replay mode it answers from the cassette without network
+//! access and fails on any request it has no recording for. Requests are keyed with the
+//! workspace root stripped from their prompts, so a cassette replays in any checkout.
+//!
+//! ```toml
+//! provider_type = "replay"
+//! model = "gpt-4o"
+//!
+//! [replay]
+//! cassette = "tests/cassettes/docs-run.jsonl"
+//! mode = "record"              # or "replay" (default)
+//! record_provider = "openai"
+//! ```
+
+use crate::error::ApiError;
+use crate::provider::profile::{ProviderConfig, ProviderType, ReplayMode};
+use crate::provider::{
+    streaming, workspace_relative_messages, ChatMessage, CompletionOptions, CompletionResponse,
+    CompletionStream, ModelProviderClient, ProviderRegistry,
+};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
+
+/// Serializes cassette appends across the clients the queue creates per request
+static CASSETTE_WRITES: Mutex<()> = Mutex::new(());
+
+/// Parsed cassettes by path, reused while the file is unchanged
+static LOADED_CASSETTES: OnceLock<Mutex<HashMap<PathBuf, Arc<Cassette>>>> = OnceLock::new();
+
+/// One recorded provider call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CassetteEntry {
+    /// `request_key` of the messages and options
+    pub key: String,
+    /// Model that produced the response
+    pub model: String,
+    /// Messages as keyed, relative to the workspace root
+    pub messages: Vec<ChatMessage>,
+    pub options: CompletionOptions,
+    pub response: CompletionResponse,
+}
+
+/// Cassette key for a request. The model is left out so a cassette recorded through one
+/// provider replays under any model name; callers pass workspace-relative messages.
+pub fn request_key(messages: &[ChatMessage], options: &CompletionOptions) -> String {
+    let encoded =
+        serde_json::to_vec(&(messages, options)).expect("provider requests serialize to JSON");
+    blake3::hash(&encoded).to_hex().to_string()
+}
+
+/// Build the client for a `replay` provider: a cassette reader, or a recorder around
+/// `record_provider`
+pub fn create_replay_client(
+    registry: &ProviderRegistry,
+    provider_name: &str,
+    config: &ProviderConfig,
+) -> Result<Box<dyn ModelProviderClient>, ApiError> {
+    let replay = config.replay.as_ref().ok_or_else(|| {
+        ApiError::ProviderNotConfigured(format!(
+            "Replay provider '{}' requires a [replay] section with a cassette",
+            provider_name
+        ))
+    })?;
+    let workspace_root = registry.workspace_root().map(Path::to_path_buf);
+    match replay.mode {
+        ReplayMode::Replay => Ok(Box::new(
+            ReplayClient::open(&replay.cassette, config.model.clone())?
+                .with_workspace_root(workspace_root),
+        )),
+        ReplayMode::Record => {
+            let recorded = replay.record_provider.as_deref().ok_or_else(|| {
+                ApiError::ProviderNotConfigured(format!(
+                    "Replay provider '{}' in record mode requires replay.record_provider",
+                    provider_name
+                ))
+            })?;
+            if registry.get_or_error(recorded)?.provider_type == ProviderType::Replay {
+                return Err(ApiError::ProviderNotConfigured(format!(
+                    "Replay provider '{}' cannot record another replay provider ('{}')",
+                    provider_name, recorded
+                )));
+            }
+            let inner = registry.create_client(recorded)?;
+            Ok(Box::new(
+                RecordingClient::new(inner, replay.cassette.clone())
+                    .with_workspace_root(workspace_root),
+            ))
+        }
+    }
+}
+
+/// Recorded responses by request key; the first recording of a key wins
+struct Cassette {
+    /// Modification time and length the cassette was parsed at
+    version: (SystemTime, u64),
+    responses: HashMap<String, CompletionResponse>,
+}
+
+impl Cassette {
+    fn load(path: &Path) -> Result<Arc<Self>, ApiError> {
+        let unreadable = |e: std::io::Error| {
+            ApiError::ProviderNotConfigured(format!(
+                "Cannot read replay cassette {}: {}",
+                path.display(),
+                e
+            ))
+        };
+        let metadata = std::fs::metadata(path).map_err(unreadable)?;
+        let version = (metadata.modified().map_err(unreadable)?, metadata.len());
+        let loaded = LOADED_CASSETTES.get_or_init(|| Mutex::new(HashMap::new()));
+        if let Some(cassette) = loaded.lock().get(path) {
+            if cassette.version == version {
+                return Ok(Arc::clone(cassette));
+            }
+        }
+
+        let text = std::fs::read_to_string(path).map_err(unreadable)?;
+        let mut responses = HashMap::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: CassetteEntry = serde_json::from_str(line).map_err(|e| {
+                ApiError::ConfigError(format!(
+                    "{}:{}: invalid cassette entry: {}",
+                    path.display(),
+                    index + 1,
+                    e
+                ))
+            })?;
+            responses.entry(entry.key).or_insert(entry.response);
+        }
+        let cassette = Arc::new(Self { version, responses });
+        loaded
+            .lock()
+            .insert(path.to_path_buf(), Arc::clone(&cassette));
+        Ok(cassette)
+    }
+}
+
+/// Serves recorded responses without network access
+pub struct ReplayClient {
+    path: PathBuf,
+    model: String,
+    cassette: Arc<Cassette>,
+    workspace_root: Option<PathBuf>,
+}
+
+impl ReplayClient {
+    pub fn open(cassette: &Path, model: String) -> Result<Self, ApiError> {
+        Ok(Self {
+            path: cassette.to_path_buf(),
+            model,
+            cassette: Cassette::load(cassette)?,
+            workspace_root: None,
+        })
+    }
+
+    /// Strip `workspace_root` from prompts before looking them up
+    pub fn with_workspace_root(mut self, workspace_root: Option<PathBuf>) -> Self {
+        self.workspace_root = workspace_root;
+        self
+    }
+}
+
+#[async_trait]
+impl ModelProviderClient for ReplayClient {
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
+    ) -> Result<CompletionResponse, ApiError> {
+        let messages = workspace_relative_messages(&messages, self.workspace_root.as_deref());
+        let key = request_key(&messages, &options);
+        self.cassette.responses.get(&key).cloned().ok_or_else(|| {
+            ApiError::ReplayMismatch(format!(
+                "{} has no entry with key {} ({} messages); record it again with mode = \"record\"",
+                self.path.display(),
+                key,
+                messages.len()
+            ))
+        })
+    }
+
+    async fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
+    ) -> Result<CompletionStream, ApiError> {
+        let response = self.complete(messages, options).await?;
+        Ok(streaming::completed_stream(response))
+    }
+
+    /// Recorded tool calls replay like any other response
+    fn supports_tools(&self) -> bool {
+        true
+    }
+
+    fn provider_name(&self) -> &str {
+        "replay"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, ApiError> {
+        Ok(vec![self.model.clone()])
+    }
+}
+
+/// Calls the wrapped client and appends each successful completion to the cassette.
+/// Streaming calls are recorded as a completion and replayed as one chunk.
+pub struct RecordingClient {
+    inner: Box<dyn ModelProviderClient>,
+    cassette: PathBuf,
+    workspace_root: Option<PathBuf>,
+}
+
+impl RecordingClient {
+    pub fn new(inner: Box<dyn ModelProviderClient>, cassette: PathBuf) -> Self {
+        Self {
+            inner,
+            cassette,
+            workspace_root: None,
+        }
+    }
+
+    /// Record prompts relative to `workspace_root`
+    pub fn with_workspace_root(mut self, workspace_root: Option<PathBuf>) -> Self {
+        self.workspace_root = workspace_root;
+        self
+    }
+
+    fn append(&self, entry: &CassetteEntry) -> Result<(), ApiError> {
+        let mut line = serde_json::to_string(entry)
+            .map_err(|e| ApiError::ProviderError(format!("Cannot encode cassette entry: {}", e)))?;
+        line.push('\n');
+        let io_error = |e: std::io::Error| {
+            ApiError::ProviderError(format!(
+                "Cannot write replay cassette {}: {}",
+                self.cassette.display(),
+                e
+            ))
+        };
+        let _guard = CASSETTE_WRITES.lock();
+        if let Some(dir) = self.cassette.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.cassette)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(io_error)
+    }
+}
+
+#[async_trait]
+impl ModelProviderClient for RecordingClient {
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
+    ) -> Result<CompletionResponse, ApiError> {
+        let recorded = workspace_relative_messages(&messages, self.workspace_root.as_deref());
+        let key = request_key(&recorded, &options);
+        let response = self.inner.complete(messages, options.clone()).await?;
+        self.append(&CassetteEntry {
+            key,
+            model: self.inner.model_name().to_string(),
+            messages: recorded,
+            options,
+            response: response.clone(),
+        })?;
+        Ok(response)
+    }
+
+    async fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
+    ) -> Result<CompletionStream, ApiError> {
+        let response = self.complete(messages, options).await?;
+        Ok(streaming::completed_stream(response))
+    }
+
+    fn supports_tools(&self) -> bool {
+        self.inner.supports_tools()
+    }
+
+    /// Embeddings pass through unrecorded
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ApiError> {
+        self.inner.embed(texts).await
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, ApiError> {
+        self.inner.list_models().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{MessageRole, MockProvider};
+    use tempfile::TempDir;
+
+    fn messages(text: &str) -> Vec<ChatMessage> {
+        vec![ChatMessage {
+            role: MessageRole::User,
+            content: text.to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }]
+    }
+
+    #[tokio::test]
+    async fn recorded_cassette_replays_offline() {
+        let dir = TempDir::new().unwrap();
+        let cassette = dir.path().join("cassettes/run.jsonl");
+        let recorder = RecordingClient::new(
+            Box::new(MockProvider::new(
+                "mock".to_string(),
+                "mock-model".to_string(),
+                vec!["first".to_string(), "second".to_string()],
+            )),
+            cassette.clone(),
+        );
+        let options = CompletionOptions::default();
+        recorder
+            .complete(messages("a"), options.clone())
+            .await
+            .unwrap();
+        recorder
+            .complete(messages("a"), options.clone())
+            .await
+            .unwrap();
+
+        let replay = ReplayClient::open(&cassette, "mock-model".to_string()).unwrap();
+        let reply = |text: &'static str| replay.complete(messages(text), options.clone());
+        assert_eq!(reply("a").await.unwrap().content, "first");
+        assert_eq!(reply("a").await.unwrap().content, "first");
+        assert!(matches!(reply("b").await, Err(ApiError::ReplayMismatch(_))));
+    }
+}
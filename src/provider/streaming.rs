@@ -0,0 +1,444 @@
+//! Server-sent-event parsing for streaming provider responses.
+//!
+//! OpenAI-compatible servers (OpenAI, Ollama, custom endpoints) send `data:` lines with
+//! `choices[0].delta.content`, a final usage chunk (requested with
+//! `stream_options.include_usage`), and end with `data: [DONE]`. Anthropic sends typed
+//! events: `content_block_delta` payloads carry `delta.text`, and `message_start` /
+//! `message_delta` carry input and output token usage. Servers that ignore
+//! `stream: true` and answer with a plain JSON completion are streamed as one chunk.
+
+use crate::error::ApiError;
+use crate::provider::{
+    ChatCompletionResponse, CompletionResponse, CompletionStream, StreamChunk, TokenUsage,
+};
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+
+/// One server-sent event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// `event:` field, when the server names its events
+    pub event: Option<String>,
+    /// `data:` lines joined with newlines
+    pub data: String,
+}
+
+/// Incremental SSE decoder: feed body bytes, take complete events.
+/// Bytes are buffered until a line is complete, so UTF-8 characters split across reads
+/// decode intact.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    pending: SseEvent,
+    has_data: bool,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append body bytes and return the events they complete
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            self.push_line(&String::from_utf8_lossy(&line), &mut events);
+        }
+        events
+    }
+
+    /// Event left unterminated when the body ended
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let rest = std::mem::take(&mut self.buffer);
+        let mut events = Vec::new();
+        if !rest.is_empty() {
+            self.push_line(&String::from_utf8_lossy(&rest), &mut events);
+        }
+        if self.has_data {
+            self.has_data = false;
+            events.push(std::mem::take(&mut self.pending));
+        }
+        events.pop()
+    }
+
+    fn push_line(&mut self, line: &str, events: &mut Vec<SseEvent>) {
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.is_empty() {
+            if self.has_data {
+                events.push(std::mem::take(&mut self.pending));
+                self.has_data = false;
+            }
+            self.pending.event = None;
+            return;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.pending.event = Some(value.to_string()),
+            "data" => {
+                if self.has_data {
+                    self.pending.data.push('\n');
+                }
+                self.pending.data.push_str(value);
+                self.has_data = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Decode a response body into server-sent events
+fn sse_events(response: reqwest::Response) -> impl Stream<Item = Result<SseEvent, ApiError>> {
+    let body = response.bytes_stream();
+    futures::stream::unfold(
+        (body, SseDecoder::new(), Vec::<SseEvent>::new(), false),
+        |(mut body, mut decoder, mut ready, mut ended)| async move {
+            loop {
+                if !ready.is_empty() {
+                    let event = ready.remove(0);
+                    return Some((Ok(event), (body, decoder, ready, ended)));
+                }
+                if ended {
+                    return None;
+                }
+                match body.next().await {
+                    Some(Ok(bytes)) => ready = decoder.push(&bytes),
+                    Some(Err(e)) => {
+                        let error =
+                            ApiError::ProviderRequestFailed(format!("Stream interrupted: {}", e));
+                        return Some((Err(error), (body, decoder, ready, true)));
+                    }
+                    None => {
+                        ended = true;
+                        ready.extend(decoder.finish());
+                    }
+                }
+            }
+        },
+    )
+}
+
+fn is_event_stream(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"))
+}
+
+fn parse_payload(data: &str) -> Result<Value, ApiError> {
+    serde_json::from_str(data)
+        .map_err(|e| ApiError::ProviderError(format!("Failed to parse stream event: {}", e)))
+}
+
+fn stream_error(payload: &Value) -> Option<ApiError> {
+    let error = payload.get("error")?;
+    let message = error
+        .get("message")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| error.to_string());
+    Some(ApiError::ProviderError(format!(
+        "Stream error: {}",
+        message
+    )))
+}
+
+/// Chunks of one event; empty for events with nothing to report
+type EventChunks = fn(&SseEvent) -> Result<Vec<StreamChunk>, ApiError>;
+
+fn chunk_stream(
+    response: reqwest::Response,
+    event_chunks: EventChunks,
+    complete_response: fn(Value) -> Result<CompletionResponse, ApiError>,
+) -> CompletionStream {
+    if !is_event_stream(&response) {
+        let chunks = async move {
+            let payload: Value = response
+                .json()
+                .await
+                .map_err(|e| ApiError::ProviderError(format!("Failed to parse response: {}", e)))?;
+            if let Some(error) = stream_error(&payload) {
+                return Err(error);
+            }
+            let response = complete_response(payload)?;
+            let mut chunks = vec![StreamChunk::Text(response.content.clone())];
+            chunks.extend(completion_metadata_chunks(&response));
+            Ok(chunks)
+        };
+        return Box::pin(futures::stream::once(chunks).flat_map(|chunks| {
+            let items: Vec<Result<StreamChunk, ApiError>> = match chunks {
+                Ok(chunks) => chunks.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            };
+            futures::stream::iter(items)
+        }));
+    }
+    Box::pin(sse_events(response).flat_map(move |event| {
+        let items: Vec<Result<StreamChunk, ApiError>> = match event.and_then(|e| event_chunks(&e)) {
+            Ok(chunks) => chunks
+                .into_iter()
+                .filter(|chunk| !matches!(chunk, StreamChunk::Text(text) if text.is_empty()))
+                .map(Ok)
+                .collect(),
+            Err(e) => vec![Err(e)],
+        };
+        futures::stream::iter(items)
+    }))
+}
+
+fn usage_chunk(usage: &Value, prompt_key: &str, completion_key: &str) -> Option<StreamChunk> {
+    let field = |key: &str| {
+        usage[key]
+            .as_u64()
+            .map(|n| u32::try_from(n).unwrap_or(u32::MAX))
+    };
+    let (prompt_tokens, completion_tokens) = (field(prompt_key), field(completion_key));
+    (prompt_tokens.is_some() || completion_tokens.is_some()).then_some(StreamChunk::Usage {
+        prompt_tokens,
+        completion_tokens,
+    })
+}
+
+fn openai_event_chunks(event: &SseEvent) -> Result<Vec<StreamChunk>, ApiError> {
+    if event.data.trim() == "[DONE]" {
+        return Ok(Vec::new());
+    }
+    let payload = parse_payload(&event.data)?;
+    if let Some(error) = stream_error(&payload) {
+        return Err(error);
+    }
+    let mut chunks = Vec::new();
+    let choice = &payload["choices"][0];
+    if let Some(text) = choice["delta"]["content"].as_str() {
+        chunks.push(StreamChunk::Text(text.to_string()));
+    }
+    if let Some(reason) = choice["finish_reason"].as_str() {
+        chunks.push(StreamChunk::FinishReason(reason.to_string()));
+    }
+    // Sent last, with empty choices, when the request sets `stream_options.include_usage`
+    chunks.extend(usage_chunk(
+        &payload["usage"],
+        "prompt_tokens",
+        "completion_tokens",
+    ));
+    Ok(chunks)
+}
+
+fn openai_complete_response(payload: Value) -> Result<CompletionResponse, ApiError> {
+    serde_json::from_value::<ChatCompletionResponse>(payload)
+        .map_err(|e| ApiError::ProviderError(format!("Failed to parse response: {}", e)))?
+        .into_completion()
+}
+
+fn anthropic_event_chunks(event: &SseEvent) -> Result<Vec<StreamChunk>, ApiError> {
+    let payload = parse_payload(&event.data)?;
+    let kind = event
+        .event
+        .as_deref()
+        .or_else(|| payload["type"].as_str())
+        .unwrap_or_default();
+    let mut chunks = Vec::new();
+    match kind {
+        "error" => {
+            if let Some(error) = stream_error(&payload) {
+                return Err(error);
+            }
+        }
+        "content_block_delta" => {
+            if let Some(text) = payload["delta"]["text"].as_str() {
+                chunks.push(StreamChunk::Text(text.to_string()));
+            }
+        }
+        // Input tokens arrive with the message; output tokens with the final delta
+        "message_start" => chunks.extend(usage_chunk(
+            &payload["message"]["usage"],
+            "input_tokens",
+            "output_tokens",
+        )),
+        "message_delta" => {
+            if let Some(reason) = payload["delta"]["stop_reason"].as_str() {
+                chunks.push(StreamChunk::FinishReason(reason.to_string()));
+            }
+            chunks.extend(usage_chunk(
+                &payload["usage"],
+                "input_tokens",
+                "output_tokens",
+            ));
+        }
+        _ => {}
+    }
+    Ok(chunks)
+}
+
+fn anthropic_complete_response(payload: Value) -> Result<CompletionResponse, ApiError> {
+    let text = payload["content"][0]["text"]
+        .as_str()
+        .ok_or_else(|| ApiError::ProviderError("No content in response".to_string()))?;
+    let tokens = |key: &str| payload["usage"][key].as_u64().unwrap_or(0) as u32;
+    let (prompt_tokens, completion_tokens) = (tokens("input_tokens"), tokens("output_tokens"));
+    Ok(CompletionResponse {
+        content: text.to_string(),
+        model: payload["model"].as_str().unwrap_or_default().to_string(),
+        usage: TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            estimated: payload["usage"].is_null(),
+        },
+        finish_reason: payload["stop_reason"].as_str().map(str::to_string),
+        tool_calls: Vec::new(),
+    })
+}
+
+/// Usage and finish reason of a finished response, as trailing stream chunks
+pub(crate) fn completion_metadata_chunks(response: &CompletionResponse) -> Vec<StreamChunk> {
+    let mut chunks = Vec::new();
+    if let Some(reason) = &response.finish_reason {
+        chunks.push(StreamChunk::FinishReason(reason.clone()));
+    }
+    if !response.usage.estimated {
+        chunks.push(StreamChunk::Usage {
+            prompt_tokens: Some(response.usage.prompt_tokens),
+            completion_tokens: Some(response.usage.completion_tokens),
+        });
+    }
+    chunks
+}
+
+/// Stream a response that is already complete as one text chunk plus its usage
+pub(crate) fn completed_stream(response: CompletionResponse) -> CompletionStream {
+    let mut chunks = vec![Ok(StreamChunk::Text(response.content.clone()))];
+    chunks.extend(completion_metadata_chunks(&response).into_iter().map(Ok));
+    Box::pin(futures::stream::iter(chunks))
+}
+
+/// Chunks of an OpenAI-compatible chat completion stream
+pub(crate) fn openai_chunk_stream(response: reqwest::Response) -> CompletionStream {
+    chunk_stream(response, openai_event_chunks, openai_complete_response)
+}
+
+/// Chunks of an Anthropic messages stream
+pub(crate) fn anthropic_chunk_stream(response: reqwest::Response) -> CompletionStream {
+    chunk_stream(
+        response,
+        anthropic_event_chunks,
+        anthropic_complete_response,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoder_joins_events_split_across_reads() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.push(b"event: content_block_delta\nda").is_empty());
+        let events = decoder.push(b"ta: {\"a\":1}\r\n\r\n: keep-alive\n\ndata: [DONE]\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: Some("content_block_delta".to_string()),
+                    data: "{\"a\":1}".to_string(),
+                },
+                SseEvent {
+                    event: None,
+                    data: "[DONE]".to_string(),
+                },
+            ]
+        );
+        decoder.push(b"data: tail");
+        assert_eq!(decoder.finish().unwrap().data, "tail");
+    }
+
+    #[test]
+    fn decoder_keeps_multibyte_characters_split_across_reads() {
+        let mut decoder = SseDecoder::new();
+        let bytes = "data: caf\u{e9} \u{1f600}\n\n".as_bytes();
+        // Split inside both the two-byte and the four-byte character
+        let mut events = decoder.push(&bytes[..10]);
+        events.extend(decoder.push(&bytes[10..13]));
+        events.extend(decoder.push(&bytes[13..]));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "caf\u{e9} \u{1f600}");
+    }
+
+    #[test]
+    fn extracts_chunks_per_provider_format() {
+        let event = |name: Option<&str>, data: &str| SseEvent {
+            event: name.map(str::to_string),
+            data: data.to_string(),
+        };
+        let texts = |chunks: Vec<StreamChunk>| -> Vec<String> {
+            chunks
+                .into_iter()
+                .filter_map(|chunk| match chunk {
+                    StreamChunk::Text(text) => Some(text),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let openai = event(None, r#"{"choices":[{"delta":{"content":"Hel"}}]}"#);
+        assert_eq!(texts(openai_event_chunks(&openai).unwrap()), vec!["Hel"]);
+        assert!(openai_event_chunks(&event(None, "[DONE]"))
+            .unwrap()
+            .is_empty());
+        let usage = event(
+            None,
+            r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":3}}"#,
+        );
+        assert!(matches!(
+            openai_event_chunks(&usage).unwrap()[..],
+            [StreamChunk::Usage {
+                prompt_tokens: Some(12),
+                completion_tokens: Some(3)
+            }]
+        ));
+
+        let delta = event(
+            Some("content_block_delta"),
+            r#"{"type":"content_block_delta","delta":{"type":"text_delta","text":"lo"}}"#,
+        );
+        assert_eq!(texts(anthropic_event_chunks(&delta).unwrap()), vec!["lo"]);
+        let start = event(
+            Some("message_start"),
+            r#"{"type":"message_start","message":{"usage":{"input_tokens":40,"output_tokens":1}}}"#,
+        );
+        assert!(matches!(
+            anthropic_event_chunks(&start).unwrap()[..],
+            [StreamChunk::Usage {
+                prompt_tokens: Some(40),
+                ..
+            }]
+        ));
+        let end = event(
+            Some("message_delta"),
+            r#"{"type":"message_delta","delta":{"stop_reason":"max_tokens"},"usage":{"output_tokens":9}}"#,
+        );
+        assert!(matches!(
+            &anthropic_event_chunks(&end).unwrap()[..],
+            [
+                StreamChunk::FinishReason(reason),
+                StreamChunk::Usage {
+                    prompt_tokens: None,
+                    completion_tokens: Some(9)
+                }
+            ] if reason == "max_tokens"
+        ));
+        let error = event(
+            Some("error"),
+            r#"{"type":"error","error":{"message":"overloaded"}}"#,
+        );
+        assert!(matches!(
+            anthropic_event_chunks(&error),
+            Err(ApiError::ProviderError(message)) if message.contains("overloaded")
+        ));
+    }
+}
@@ -0,0 +1,495 @@
+//! Offline `template` provider: deterministic frames from local analysis, no LLM.
+//!
+//! The client reads the grounding context the queue puts in the prompt and answers with
+//! file stats, line counts, and top-level symbol names found by per-language regexes.
+//! Directory prompts get a listing of their children built from the children's frames.
+//! Chunked files get per-part stats and a synthesis that merges the parts' symbols.
+
+use crate::error::ApiError;
+use crate::provider::embedding::hashing_embedding;
+use crate::provider::{
+    streaming, ChatMessage, CompletionOptions, CompletionResponse, CompletionStream, MessageRole,
+    ModelProviderClient, TokenUsage,
+};
+use async_trait::async_trait;
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Symbols listed per frame before the rest are counted
+const MAX_SYMBOLS: usize = 40;
+/// Longest child summary line repeated in a directory listing
+const MAX_CHILD_SUMMARY_CHARS: usize = 160;
+
+/// Top-level symbol patterns for one language. Each pattern captures `name` and may
+/// capture `kind`; patterns without a `kind` group use their default kind.
+struct Language {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    patterns: &'static [(&'static str, &'static str)],
+}
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "Rust",
+        extensions: &["rs"],
+        patterns: &[(
+            "",
+            r"^(?:pub(?:\([^)]*\))?\s+)?(?:(?:async|const|unsafe|extern)\s+)*(?P<kind>fn|struct|enum|trait|type|mod|const|static|union|macro_rules!)\s*(?P<name>[A-Za-z_][A-Za-z0-9_]*)",
+        )],
+    },
+    Language {
+        name: "Python",
+        extensions: &["py", "pyi"],
+        patterns: &[(
+            "",
+            r"^(?:async\s+)?(?P<kind>def|class)\s+(?P<name>[A-Za-z_]\w*)",
+        )],
+    },
+    Language {
+        name: "JavaScript",
+        extensions: &["js", "jsx", "mjs", "cjs"],
+        patterns: &[(
+            "",
+            r"^(?:export\s+)?(?:default\s+)?(?:async\s+)?(?P<kind>function\*?|class|const|let|var)\s+(?P<name>[A-Za-z_$][\w$]*)",
+        )],
+    },
+    Language {
+        name: "TypeScript",
+        extensions: &["ts", "tsx", "mts", "cts"],
+        patterns: &[(
+            "",
+            r"^(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?(?:async\s+)?(?P<kind>function\*?|class|interface|type|enum|namespace|const|let|var)\s+(?P<name>[A-Za-z_$][\w$]*)",
+        )],
+    },
+    Language {
+        name: "Go",
+        extensions: &["go"],
+        patterns: &[(
+            "",
+            r"^(?P<kind>func|type)\s+(?:\([^)]*\)\s*)?(?P<name>[A-Za-z_]\w*)",
+        )],
+    },
+    Language {
+        name: "Java",
+        extensions: &["java", "kt", "kts", "scala", "cs"],
+        patterns: &[(
+            "",
+            r"^(?:(?:public|private|protected|internal|abstract|final|sealed|static|data|open)\s+)*(?P<kind>class|interface|enum|record|object|trait)\s+(?P<name>[A-Za-z_]\w*)",
+        )],
+    },
+    Language {
+        name: "C/C++",
+        extensions: &["c", "h", "cc", "cpp", "cxx", "hpp", "hh"],
+        patterns: &[
+            (
+                "",
+                r"^(?:typedef\s+)?(?P<kind>struct|enum|union|class|namespace)\s+(?P<name>[A-Za-z_]\w*)",
+            ),
+            ("define", r"^#define\s+(?P<name>[A-Za-z_]\w*)"),
+        ],
+    },
+    Language {
+        name: "Ruby",
+        extensions: &["rb"],
+        patterns: &[(
+            "",
+            r"^(?P<kind>class|module|def)\s+(?P<name>[A-Za-z_][\w:.]*[?!]?)",
+        )],
+    },
+    Language {
+        name: "Shell",
+        extensions: &["sh", "bash", "zsh"],
+        patterns: &[(
+            "function",
+            r"^(?:function\s+)?(?P<name>[A-Za-z_][\w-]*)\s*\(\)",
+        )],
+    },
+    Language {
+        name: "SQL",
+        extensions: &["sql"],
+        patterns: &[(
+            "",
+            r"(?i)^create\s+(?:or\s+replace\s+)?(?P<kind>table|view|index|function|procedure|trigger|type)\s+(?:if\s+not\s+exists\s+)?(?P<name>[\w.]+)",
+        )],
+    },
+    Language {
+        name: "Markdown",
+        extensions: &["md", "markdown"],
+        patterns: &[("heading", r"^#{1,2}\s+(?P<name>\S.*?)\s*#*$")],
+    },
+];
+
+/// Compiled patterns, indexed like `LANGUAGES`
+fn compiled_patterns() -> &'static Vec<Vec<(&'static str, Regex)>> {
+    static COMPILED: OnceLock<Vec<Vec<(&'static str, Regex)>>> = OnceLock::new();
+    COMPILED.get_or_init(|| {
+        LANGUAGES
+            .iter()
+            .map(|language| {
+                language
+                    .patterns
+                    .iter()
+                    .map(|(kind, pattern)| {
+                        let regex = Regex::new(&format!("(?m){}", pattern))
+                            .expect("template symbol patterns are valid");
+                        (*kind, regex)
+                    })
+                    .collect()
+            })
+            .collect()
+    })
+}
+
+fn language_index(path: &str) -> Option<usize> {
+    let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .position(|l| l.extensions.contains(&extension.as_str()))
+}
+
+/// `kind name` for every top-level symbol in `content`, in source order
+pub fn extract_symbols(path: &str, content: &str) -> Vec<String> {
+    let Some(index) = language_index(path) else {
+        return Vec::new();
+    };
+    let mut symbols: Vec<(usize, String)> = Vec::new();
+    for (default_kind, regex) in &compiled_patterns()[index] {
+        for captures in regex.captures_iter(content) {
+            let Some(name) = captures.name("name") else {
+                continue;
+            };
+            let kind = captures
+                .name("kind")
+                .map(|k| k.as_str().to_ascii_lowercase())
+                .unwrap_or_else(|| default_kind.to_string());
+            symbols.push((name.start(), format!("{} {}", kind, name.as_str())));
+        }
+    }
+    symbols.sort_by_key(|(offset, _)| *offset);
+    symbols.into_iter().map(|(_, symbol)| symbol).collect()
+}
+
+/// What the prompt asks about, recovered from the queue's prompt layout
+#[derive(Debug, PartialEq, Eq)]
+enum Subject<'a> {
+    File {
+        path: &'a str,
+        content: &'a str,
+    },
+    FilePart {
+        path: &'a str,
+        part: &'a str,
+        content: &'a str,
+    },
+    Synthesis {
+        path: &'a str,
+        parts: Vec<&'a str>,
+    },
+    Directory {
+        children: Vec<Child<'a>>,
+    },
+    Unknown,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Child<'a> {
+    path: &'a str,
+    kind: &'a str,
+    content: &'a str,
+}
+
+fn parse_subject(prompt: &str) -> Subject<'_> {
+    if let Some(context) = prompt.strip_prefix("Context:\n") {
+        let context = context
+            .rfind("\n\nTask: ")
+            .map_or(context, |end| &context[..end]);
+        if let Some((path, rest)) = header(context, "File") {
+            let content = rest.strip_prefix("Content:\n").unwrap_or(rest);
+            return Subject::File { path, content };
+        }
+        if let Some((_, rest)) = header(context, "Directory") {
+            let children = rest
+                .strip_prefix("Children:\n")
+                .unwrap_or(rest)
+                .split("\n\n---\n\n")
+                .filter_map(parse_child)
+                .collect();
+            return Subject::Directory { children };
+        }
+        return Subject::Unknown;
+    }
+
+    // Chunked files: part prompts and the synthesis prompt end with their own header
+    if let Some(start) = prompt.find("\nType: File (part ") {
+        let path = last_line(&prompt[..start]).strip_prefix("Path: ");
+        let rest = &prompt[start + "\nType: File (part ".len()..];
+        if let (Some(path), Some((part, rest))) = (path, rest.split_once(")\n")) {
+            let content = rest.strip_prefix("Content:\n").unwrap_or(rest);
+            return Subject::FilePart {
+                path,
+                part,
+                content,
+            };
+        }
+    }
+    if let Some(start) = prompt.find("\nType: File\n\n### Part ") {
+        if let Some(path) = last_line(&prompt[..start]).strip_prefix("Path: ") {
+            let parts = prompt[start..]
+                .split("\n### Part ")
+                .skip(1)
+                .map(|part| part.split_once('\n').map_or("", |(_, summary)| summary))
+                .collect();
+            return Subject::Synthesis { path, parts };
+        }
+    }
+    Subject::Unknown
+}
+
+/// Split `Path: ..\nType: <kind>\n` off the front of a section
+fn header<'a>(section: &'a str, kind: &str) -> Option<(&'a str, &'a str)> {
+    let rest = section.strip_prefix("Path: ")?;
+    let (path, rest) = rest.split_once('\n')?;
+    let rest = rest.strip_prefix("Type: ")?.strip_prefix(kind)?;
+    Some((path, rest.strip_prefix('\n').unwrap_or(rest)))
+}
+
+fn parse_child(section: &str) -> Option<Child<'_>> {
+    let rest = section.strip_prefix("Path: ")?;
+    let (path, rest) = rest.split_once('\n')?;
+    let (kind, rest) = rest.strip_prefix("Type: ")?.split_once('\n')?;
+    // Sections consumed from other agents repeat children already listed
+    if rest.starts_with("Agent: ") {
+        return None;
+    }
+    let content = rest.strip_prefix("Content:\n").unwrap_or(rest);
+    Some(Child {
+        path,
+        kind,
+        content,
+    })
+}
+
+fn last_line(text: &str) -> &str {
+    text.rsplit('\n').next().unwrap_or(text)
+}
+
+fn file_name(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(path)
+}
+
+fn language_name(path: &str) -> &'static str {
+    language_index(path).map_or("Text", |i| LANGUAGES[i].name)
+}
+
+fn symbol_line(symbols: &[String]) -> String {
+    if symbols.is_empty() {
+        return "No top-level symbols found.".to_string();
+    }
+    let mut line = format!(
+        "Top-level symbols ({}): {}",
+        symbols.len(),
+        symbols
+            .iter()
+            .take(MAX_SYMBOLS)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if symbols.len() > MAX_SYMBOLS {
+        line.push_str(&format!(" (+{} more)", symbols.len() - MAX_SYMBOLS));
+    }
+    line
+}
+
+fn describe_source(path: &str, content: &str, lines_label: &str) -> String {
+    let lines = content.lines().count();
+    let non_blank = content.lines().filter(|l| !l.trim().is_empty()).count();
+    format!(
+        "{} source file{}, {} lines ({} non-blank), {} bytes.\n{}",
+        language_name(path),
+        lines_label,
+        lines,
+        non_blank,
+        content.len(),
+        symbol_line(&extract_symbols(path, content))
+    )
+}
+
+fn render(subject: Subject<'_>) -> String {
+    match subject {
+        Subject::File { path, content } => describe_source(path, content, ""),
+        Subject::FilePart {
+            path,
+            part,
+            content,
+        } => describe_source(path, content, &format!(" (part {})", part)),
+        Subject::Synthesis { path, parts } => {
+            let prefix = "Top-level symbols (";
+            let symbols: Vec<String> = parts
+                .iter()
+                .filter_map(|part| part.lines().find(|l| l.starts_with(prefix)))
+                .filter_map(|line| line.split_once("): ").map(|(_, list)| list))
+                .flat_map(|list| list.split(" (+").next().unwrap_or(list).split(", "))
+                .map(str::to_string)
+                .collect();
+            format!(
+                "{} source file summarized in {} parts.\n{}",
+                language_name(path),
+                parts.len(),
+                symbol_line(&symbols)
+            )
+        }
+        Subject::Directory { children } => {
+            let files = children.iter().filter(|c| c.kind == "File").count();
+            let directories = children.len() - files;
+            let mut text = format!(
+                "Directory with {} entries: {} files, {} directories.",
+                children.len(),
+                files,
+                directories
+            );
+            for child in &children {
+                let summary: String = child
+                    .content
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .chars()
+                    .take(MAX_CHILD_SUMMARY_CHARS)
+                    .collect();
+                text.push_str(&format!(
+                    "\n- {} ({}): {}",
+                    file_name(child.path),
+                    child.kind.to_ascii_lowercase(),
+                    summary
+                ));
+            }
+            text
+        }
+        Subject::Unknown => "No local context was available for this node.".to_string(),
+    }
+}
+
+/// Provider client that answers every prompt from local analysis
+pub struct TemplateClient {
+    model: String,
+}
+
+impl TemplateClient {
+    pub fn new(model: String) -> Self {
+        Self { model }
+    }
+}
+
+#[async_trait]
+impl ModelProviderClient for TemplateClient {
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        _options: CompletionOptions,
+    ) -> Result<CompletionResponse, ApiError> {
+        let prompt = messages
+            .iter()
+            .rev()
+            .find(|m| m.role == MessageRole::User)
+            .map_or("", |m| m.content.as_str());
+        Ok(CompletionResponse {
+            content: render(parse_subject(prompt)),
+            model: self.model.clone(),
+            usage: TokenUsage {
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                total_tokens: 0,
+                estimated: false,
+            },
+            finish_reason: Some("stop".to_string()),
+            tool_calls: Vec::new(),
+        })
+    }
+
+    async fn stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: CompletionOptions,
+    ) -> Result<CompletionStream, ApiError> {
+        let response = self.complete(messages, options).await?;
+        Ok(streaming::completed_stream(response))
+    }
+
+    /// Deterministic hashed bag-of-words vectors, so search also works offline
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ApiError> {
+        Ok(texts.iter().map(|text| hashing_embedding(text)).collect())
+    }
+
+    fn provider_name(&self) -> &str {
+        "template"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, ApiError> {
+        Ok(vec![self.model.clone()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_top_level_symbols_per_language() {
+        let rust = "use std::fmt;\n\npub struct Config {}\n\npub(crate) async fn load() {}\n\
+                    impl Config {\n    fn inner() {}\n}\nmod tests {}\n";
+        assert_eq!(
+            extract_symbols("src/lib.rs", rust),
+            vec!["struct Config", "fn load", "mod tests"]
+        );
+        let python =
+            "import os\n\nclass Loader:\n    def read(self): pass\n\nasync def main(): pass\n";
+        assert_eq!(
+            extract_symbols("app.py", python),
+            vec!["class Loader", "def main"]
+        );
+        let sql = "CREATE TABLE IF NOT EXISTS users (id int);\ncreate view active as select 1;\n";
+        assert_eq!(
+            extract_symbols("schema.sql", sql),
+            vec!["table users", "view active"]
+        );
+        assert!(extract_symbols("notes.txt", "fn nothing() {}").is_empty());
+    }
+
+    #[test]
+    fn file_and_directory_prompts_render_stats_and_listings() {
+        let file_prompt = "Context:\nPath: /ws/src/lib.rs\nType: File\nContent:\n\
+                           fn a() {}\n\nfn b() {}\n\n\nTask: Summarize";
+        assert_eq!(
+            render(parse_subject(file_prompt)),
+            "Rust source file, 3 lines (2 non-blank), 21 bytes.\n\
+             Top-level symbols (2): fn a, fn b"
+        );
+
+        let dir_prompt = "Context:\nPath: /ws/src\nType: Directory\nChildren:\n\
+                          Path: /ws/src/lib.rs\nType: File\nContent:\nRust source file.\nmore\
+                          \n\n---\n\n\
+                          Path: /ws/src/cli\nType: Directory\nContent:\nDirectory with 1 entries.\
+                          \n\n---\n\n\
+                          Path: /ws/src/lib.rs\nType: File\nAgent: other\nContent:\nskipped\
+                          \n\nTask: Summarize";
+        assert_eq!(
+            render(parse_subject(dir_prompt)),
+            "Directory with 2 entries: 1 files, 1 directories.\n\
+             - lib.rs (file): Rust source file.\n\
+             - cli (directory): Directory with 1 entries."
+        );
+        assert_eq!(
+            render(parse_subject("Summarize")),
+            "No local context was available for this node."
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! HTTP client construction for network providers.
+//!
+//! Every network client (OpenAI, Anthropic, Ollama, custom local) builds its
+//! `reqwest::Client` here from the provider's optional `[transport]` section:
+//!
+//! ```toml
+//! [transport]
+//! proxy = "http://proxy.corp.example:3128"
+//! no_proxy = ["localhost", ".internal.example", "10.0.0.0/8"]
+//! ca_bundle = "/etc/ssl/corp-root.pem"
+//! connect_timeout_secs = 30
+//! request_timeout_secs = 600
+//!
+//! [transport.headers]
+//! X-Gateway-Tenant = "docs"
+//! ```
+
+use crate::error::ApiError;
+use crate::provider::TransportConfig;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use std::time::Duration;
+
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Build the HTTP client for a provider. Settings errors name the offending key.
+pub fn build_http_client(transport: &TransportConfig) -> Result<Client, ApiError> {
+    let connect_timeout = transport
+        .connect_timeout_secs
+        .map_or(DEFAULT_CONNECT_TIMEOUT, Duration::from_secs);
+    let request_timeout = transport
+        .request_timeout_secs
+        .map_or(DEFAULT_REQUEST_TIMEOUT, Duration::from_secs);
+    let mut builder = Client::builder()
+        .connect_timeout(connect_timeout)
+        .timeout(request_timeout);
+
+    // Environment proxy variables are ignored unless a proxy is configured explicitly
+    builder = match transport.proxy.as_deref() {
+        Some(url) => {
+            let proxy = Proxy::all(url).map_err(|e| invalid("proxy", url, e))?;
+            builder.proxy(proxy.no_proxy(NoProxy::from_string(&transport.no_proxy.join(","))))
+        }
+        None => builder.no_proxy(),
+    };
+
+    if let Some(path) = &transport.ca_bundle {
+        let pem = std::fs::read(path).map_err(|e| {
+            ApiError::ConfigError(format!(
+                "Cannot read transport.ca_bundle {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| invalid("ca_bundle", &path.display().to_string(), e))?;
+        if certificates.is_empty() {
+            return Err(ApiError::ConfigError(format!(
+                "transport.ca_bundle {} contains no PEM certificates",
+                path.display()
+            )));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    if !transport.headers.is_empty() {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &transport.headers {
+            let header_name =
+                HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid("headers", name, e))?;
+            // Values may carry gateway credentials; keep them out of debug output
+            let mut header_value = HeaderValue::from_str(value)
+                .map_err(|e| invalid("headers", &format!("{} value", name), e))?;
+            header_value.set_sensitive(true);
+            headers.insert(header_name, header_value);
+        }
+        builder = builder.default_headers(headers);
+    }
+
+    builder
+        .build()
+        .map_err(|e| ApiError::ProviderError(format!("Failed to create HTTP client: {}", e)))
+}
+
+fn invalid(key: &str, value: &str, error: impl std::fmt::Display) -> ApiError {
+    ApiError::ConfigError(format!("Invalid transport.{} '{}': {}", key, value, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_with_settings_and_rejects_bad_values() {
+        assert!(build_http_client(&TransportConfig::default()).is_ok());
+
+        let mut transport = TransportConfig {
+            proxy: Some("http://proxy.example:3128".to_string()),
+            no_proxy: vec!["localhost".to_string(), "10.0.0.0/8".to_string()],
+            connect_timeout_secs: Some(30),
+            request_timeout_secs: Some(600),
+            ..TransportConfig::default()
+        };
+        transport
+            .headers
+            .insert("X-Gateway-Tenant".to_string(), "docs".to_string());
+        assert!(build_http_client(&transport).is_ok());
+
+        transport
+            .headers
+            .insert("bad header".to_string(), "x".to_string());
+        let error = build_http_client(&transport).unwrap_err().to_string();
+        assert!(error.contains("transport.headers"), "{}", error);
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let bundle = dir.path().join("empty.pem");
+        std::fs::write(&bundle, "not a certificate\n").unwrap();
+        let transport = TransportConfig {
+            ca_bundle: Some(bundle),
+            ..TransportConfig::default()
+        };
+        let error = build_http_client(&transport).unwrap_err().to_string();
+        assert!(error.contains("no PEM certificates"), "{}", error);
+    }
+}
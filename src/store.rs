@@ -3,13 +3,16 @@
 //! Provides fast lookup storage for node metadata and relationships.
 //! Acts as an index into the filesystem Merkle tree.
 
+pub mod blob;
 pub mod node_metadata;
 pub mod persistence;
 
+pub use blob::BlobStore;
 pub use persistence::SledNodeRecordStore;
 
 use crate::error::StorageError;
 use crate::store::node_metadata::NodeMetadata;
+use crate::tree::classify::{KEY_FILE_CLASS, KEY_FILE_CLASS_REASON};
 use crate::tree::node::MerkleNode;
 use crate::tree::Tree;
 use crate::types::{Hash, NodeID};
@@ -90,19 +93,34 @@ impl NodeRecord {
         tree: &Tree,
     ) -> Result<Self, StorageError> {
         match node {
-            MerkleNode::File(file) => Ok(NodeRecord {
-                node_id,
-                path: file.path.clone(),
-                node_type: NodeType::File {
-                    size: file.size,
-                    content_hash: file.content_hash,
-                },
-                children: vec![],
-                parent: tree.find_parent(&node_id),
-                frame_set_root: None,
-                metadata: file.metadata.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
-                tombstoned_at: None,
-            }),
+            MerkleNode::File(file) => {
+                let mut metadata: NodeMetadata = file
+                    .metadata
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                metadata.insert(
+                    KEY_FILE_CLASS.to_string(),
+                    file.class.class.as_str().to_string(),
+                );
+                metadata.insert(
+                    KEY_FILE_CLASS_REASON.to_string(),
+                    file.class.reason.to_string(),
+                );
+                Ok(NodeRecord {
+                    node_id,
+                    path: file.path.clone(),
+                    node_type: NodeType::File {
+                        size: file.size,
+                        content_hash: file.content_hash,
+                    },
+                    children: vec![],
+                    parent: tree.find_parent(&node_id),
+                    frame_set_root: None,
+                    metadata,
+                    tombstoned_at: None,
+                })
+            }
             MerkleNode::Directory(dir) => {
                 let children: Vec<NodeID> = dir.children.iter().map(|(_, node_id)| *node_id).collect();
 
@@ -0,0 +1,73 @@
+//! Content-addressed blob cache for scanned file bytes.
+//!
+//! Scan writes each file's bytes under its content hash so generation can prompt from
+//! exactly the scanned content even after the live file changes.
+
+use crate::error::StorageError;
+use crate::types::Hash;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Blob cache rooted at a directory; blobs live at `<root>/<hh>/<hash hex>`
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+impl BlobStore {
+    /// Open (creating if needed) a blob cache at `root`
+    pub fn open(root: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    fn blob_path(&self, hash: &Hash) -> PathBuf {
+        let hex = hex::encode(hash);
+        self.root.join(&hex[..2]).join(hex)
+    }
+
+    /// Store `content` under `hash`; existing blobs are left untouched
+    pub fn put(&self, hash: &Hash, content: &[u8]) -> Result<(), StorageError> {
+        let path = self.blob_path(hash);
+        if path.exists() {
+            return Ok(());
+        }
+        let dir = path.parent().expect("blob path has a parent");
+        fs::create_dir_all(dir)?;
+        let tmp = dir.join(format!(".{}.{}.tmp", hex::encode(hash), std::process::id()));
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Bytes stored under `hash`, if cached
+    pub fn get(&self, hash: &Hash) -> Result<Option<Vec<u8>>, StorageError> {
+        match fs::read(self.blob_path(hash)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(StorageError::IoError(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::hasher::compute_content_hash;
+    use tempfile::TempDir;
+
+    #[test]
+    fn put_then_get_round_trips_by_content_hash() {
+        let dir = TempDir::new().unwrap();
+        let store = BlobStore::open(dir.path().join("blobs")).unwrap();
+        let content = b"fn main() {}";
+        let hash = compute_content_hash(content);
+
+        assert_eq!(store.get(&hash).unwrap(), None);
+        store.put(&hash, content).unwrap();
+        store.put(&hash, content).unwrap();
+        assert_eq!(store.get(&hash).unwrap().as_deref(), Some(&content[..]));
+    }
+}
@@ -106,6 +106,18 @@ pub struct ProviderLifecycleEventData {
     pub retry_count: Option<usize>,
 }
 
+/// Partial response text for one node, in arrival order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderChunkEventData {
+    pub node_id: String,
+    pub agent_id: String,
+    pub provider_name: String,
+    pub frame_type: String,
+    /// Position of this chunk within the response, starting at 0
+    pub index: usize,
+    pub text: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SummaryEventData {
     pub command: String,
@@ -1,6 +1,8 @@
 //! Tree builder for constructing filesystem Merkle trees
 
 use crate::error::StorageError;
+use crate::store::BlobStore;
+use crate::tree::classify;
 use crate::tree::hasher;
 use crate::tree::node::{DirectoryNode, FileNode, MerkleNode};
 use crate::tree::path;
@@ -9,6 +11,7 @@ use crate::types::NodeID;
 use hex;
 use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, error, info, instrument, trace, warn};
 
@@ -63,6 +66,7 @@ impl Tree {
 pub struct TreeBuilder {
     root: PathBuf,
     walker_config: Option<WalkerConfig>,
+    blob_store: Option<Arc<BlobStore>>,
 }
 
 impl TreeBuilder {
@@ -71,6 +75,7 @@ impl TreeBuilder {
         Self {
             root,
             walker_config: None,
+            blob_store: None,
         }
     }
 
@@ -81,6 +86,12 @@ impl TreeBuilder {
         self
     }
 
+    /// Cache each hashed file's bytes in `blob_store` (no-op when `None`).
+    pub fn with_blob_store(mut self, blob_store: Option<Arc<BlobStore>>) -> Self {
+        self.blob_store = blob_store;
+        self
+    }
+
     /// Build the complete Merkle tree from the filesystem
     ///
     /// This processes files and directories bottom-up to compute NodeIDs,
@@ -212,6 +223,9 @@ impl TreeBuilder {
         // Compute content hash
         let content_hash = hasher::compute_content_hash(&content);
         trace!(content_hash = %hex::encode(content_hash), "Computed content hash");
+        if let Some(blob_store) = &self.blob_store {
+            blob_store.put(&content_hash, &content)?;
+        }
 
         // Extract metadata (currently empty, can be extended)
         let metadata = BTreeMap::new();
@@ -219,12 +233,16 @@ impl TreeBuilder {
         // Compute NodeID
         let node_id = hasher::compute_file_node_id(file_path, &content_hash, &metadata)?;
 
+        let relative_path = file_path.strip_prefix(&self.root).unwrap_or(file_path);
+        let class = classify::classify_file(relative_path, &content);
+
         // Create FileNode
         let file_node = FileNode {
             path: file_path.to_path_buf(),
             content_hash,
             size,
             metadata,
+            class,
         };
 
         Ok((node_id, file_node))
@@ -0,0 +1,271 @@
+//! File classification: tell source text apart from binary, generated, and vendored files.
+//!
+//! Heuristics follow git (NUL bytes in the first 8000 bytes) and linguist (lockfiles,
+//! minified bundles, "generated" markers, vendor directories). The class is recorded in
+//! node metadata at scan time and is not part of the NodeID.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Node metadata key holding the file class
+pub const KEY_FILE_CLASS: &str = "file_class";
+/// Node metadata key holding the heuristic that decided the class
+pub const KEY_FILE_CLASS_REASON: &str = "file_class_reason";
+
+/// Bytes inspected for NUL bytes, entropy, and generated markers
+const SNIFF_BYTES: usize = 8000;
+/// Shannon entropy (bits per byte) above which content is treated as compressed or binary
+const BINARY_ENTROPY_BITS: f64 = 7.5;
+/// Average line length above which js/css/json content is treated as minified
+const MINIFIED_AVG_LINE_BYTES: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileClass {
+    Text,
+    Binary,
+    Generated,
+    Vendored,
+}
+
+impl FileClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileClass::Text => "text",
+            FileClass::Binary => "binary",
+            FileClass::Generated => "generated",
+            FileClass::Vendored => "vendored",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(FileClass::Text),
+            "binary" => Some(FileClass::Binary),
+            "generated" => Some(FileClass::Generated),
+            "vendored" => Some(FileClass::Vendored),
+            _ => None,
+        }
+    }
+}
+
+/// Class of a file plus the heuristic that decided it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Classification {
+    pub class: FileClass,
+    pub reason: &'static str,
+}
+
+impl Classification {
+    fn new(class: FileClass, reason: &'static str) -> Self {
+        Self { class, reason }
+    }
+}
+
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "tiff", "psd", "pdf", "zip", "gz", "tgz",
+    "bz2", "xz", "7z", "rar", "tar", "jar", "war", "class", "so", "dylib", "dll", "exe", "o", "a",
+    "lib", "obj", "wasm", "pyc", "pyo", "woff", "woff2", "ttf", "otf", "eot", "mp3", "mp4", "wav",
+    "ogg", "flac", "mov", "avi", "mkv", "webm", "sqlite", "db", "bin", "dat",
+];
+
+const LOCKFILE_NAMES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "poetry.lock",
+    "Pipfile.lock",
+    "uv.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+    "flake.lock",
+    "mix.lock",
+    "pubspec.lock",
+    "Podfile.lock",
+];
+
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".min.js",
+    ".min.css",
+    ".min.mjs",
+    ".js.map",
+    ".css.map",
+    ".pb.go",
+    "_pb2.py",
+    "_pb2_grpc.py",
+    ".pb.h",
+    ".pb.cc",
+    ".g.dart",
+    ".freezed.dart",
+    ".designer.cs",
+    ".generated.ts",
+];
+
+const GENERATED_MARKERS: &[&str] = &[
+    "@generated",
+    "do not edit",
+    "code generated",
+    "auto-generated",
+    "autogenerated",
+    "automatically generated",
+];
+
+/// Directory names that only ever hold third-party code; generic names such as `external`
+/// are left out because first-party trees use them too
+const VENDOR_DIRECTORIES: &[&str] = &[
+    "vendor",
+    "node_modules",
+    "bower_components",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "Pods",
+    "Carthage",
+    ".yarn",
+];
+
+const MINIFIABLE_EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "css", "json"];
+
+/// Classify a file from its workspace-relative path and content.
+pub fn classify_file(relative_path: &Path, content: &[u8]) -> Classification {
+    let file_name = relative_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let extension = relative_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    let head = &content[..content.len().min(SNIFF_BYTES)];
+
+    if head.contains(&0) {
+        return Classification::new(FileClass::Binary, "nul_bytes");
+    }
+    if BINARY_EXTENSIONS.contains(&extension.as_str()) {
+        return Classification::new(FileClass::Binary, "extension");
+    }
+    if head.len() >= 512 && shannon_entropy(head) > BINARY_ENTROPY_BITS {
+        return Classification::new(FileClass::Binary, "entropy");
+    }
+
+    let in_vendor_dir = relative_path
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .any(|c| VENDOR_DIRECTORIES.iter().any(|v| c.as_os_str() == *v));
+    if in_vendor_dir {
+        return Classification::new(FileClass::Vendored, "vendor_directory");
+    }
+
+    if LOCKFILE_NAMES.contains(&file_name) {
+        return Classification::new(FileClass::Generated, "lockfile");
+    }
+    let lower_name = file_name.to_ascii_lowercase();
+    if GENERATED_SUFFIXES.iter().any(|s| lower_name.ends_with(s)) {
+        return Classification::new(FileClass::Generated, "generated_suffix");
+    }
+    if has_generated_marker(head) {
+        return Classification::new(FileClass::Generated, "generated_marker");
+    }
+    if MINIFIABLE_EXTENSIONS.contains(&extension.as_str()) && is_minified(content) {
+        return Classification::new(FileClass::Generated, "minified");
+    }
+
+    Classification::new(FileClass::Text, "default")
+}
+
+fn shannon_entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+    let len = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Generated markers must appear in the first few lines to count
+fn has_generated_marker(head: &[u8]) -> bool {
+    String::from_utf8_lossy(head)
+        .lines()
+        .take(5)
+        .map(str::to_ascii_lowercase)
+        .any(|line| GENERATED_MARKERS.iter().any(|m| line.contains(m)))
+}
+
+fn is_minified(content: &[u8]) -> bool {
+    if content.len() < MINIFIED_AVG_LINE_BYTES {
+        return false;
+    }
+    let lines = content
+        .split(|&b| b == b'\n')
+        .filter(|l| !l.is_empty())
+        .count();
+    content.len() / lines.max(1) > MINIFIED_AVG_LINE_BYTES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class_of(path: &str, content: &[u8]) -> FileClass {
+        classify_file(Path::new(path), content).class
+    }
+
+    #[test]
+    fn binary_by_nul_bytes_extension_and_entropy() {
+        assert_eq!(class_of("data.txt", b"abc\0def"), FileClass::Binary);
+        assert_eq!(class_of("logo.PNG", b"not really png"), FileClass::Binary);
+        let noise: Vec<u8> = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .map(|b| if b == 0 { 1 } else { b })
+            .collect();
+        assert_eq!(
+            classify_file(Path::new("blob.txt"), &noise).reason,
+            "entropy"
+        );
+    }
+
+    #[test]
+    fn generated_by_lockfile_suffix_marker_and_minification() {
+        assert_eq!(class_of("Cargo.lock", b"[[package]]"), FileClass::Generated);
+        assert_eq!(class_of("web/app.min.js", b"var a=1"), FileClass::Generated);
+        assert_eq!(
+            class_of("api.rs", b"// @generated by build.rs\nfn f() {}\n"),
+            FileClass::Generated
+        );
+        let bundle = format!("var x={};", "a".repeat(2000));
+        assert_eq!(
+            class_of("bundle.js", bundle.as_bytes()),
+            FileClass::Generated
+        );
+    }
+
+    #[test]
+    fn vendored_by_directory_and_text_otherwise() {
+        assert_eq!(
+            class_of("vendor/lib/util.go", b"package util"),
+            FileClass::Vendored
+        );
+        assert_eq!(class_of("src/main.rs", b"fn main() {}\n"), FileClass::Text);
+        assert_eq!(
+            class_of("src/vendor.rs", b"fn vendor() {}\n"),
+            FileClass::Text
+        );
+        assert_eq!(
+            class_of("src/external/client.rs", b"pub fn call() {}\n"),
+            FileClass::Text
+        );
+    }
+}
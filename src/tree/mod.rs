@@ -4,6 +4,7 @@
 //! (file or directory) has a deterministic hash based on content and structure.
 
 pub mod builder;
+pub mod classify;
 pub mod hasher;
 pub mod node;
 pub mod path;
@@ -1,5 +1,6 @@
 //! Filesystem node types and NodeID computation
 
+use crate::tree::classify::Classification;
 use crate::types::NodeID;
 use std::collections::BTreeMap;
 use std::path::PathBuf;
@@ -11,6 +12,8 @@ pub struct FileNode {
     pub content_hash: [u8; 32],
     pub size: u64,
     pub metadata: BTreeMap<String, String>,
+    /// Scan-time classification; not part of the NodeID
+    pub class: Classification,
 }
 
 /// Directory node representation
@@ -33,6 +33,7 @@ impl Default for WalkerConfig {
                 "target".to_string(),
                 "node_modules".to_string(),
                 ".cargo".to_string(),
+                ".meld".to_string(),
             ],
             max_depth: None,
         }
@@ -4,5 +4,5 @@
 //! Selects and orders a bounded set of frames based on policies.
 
 pub use crate::context::query::view_policy::{
-    get_context_view, FrameFilter, OrderingPolicy, ViewPolicy,
+    get_context_view, FrameFilter, MetadataPredicate, OrderingPolicy, ViewPolicy,
 };
@@ -153,6 +153,7 @@ impl WorkspaceCommandService {
         section::build_workspace_status(
             node_store,
             &head_index,
+            api.frame_storage(),
             agent_registry,
             &request.workspace_root,
             &request.store_path,
@@ -504,8 +505,9 @@ impl WorkspaceCommandService {
             ignore_patterns,
             max_depth: None,
         };
-        let builder =
-            TreeBuilder::new(workspace_root.clone()).with_walker_config(walker_config);
+        let builder = TreeBuilder::new(workspace_root.clone())
+            .with_walker_config(walker_config)
+            .with_blob_store(api.blob_store().cloned());
         let tree = builder.build().map_err(ApiError::StorageError)?;
         let total_nodes = tree.nodes.len();
 
@@ -59,7 +59,7 @@ pub fn format_workspace_status_text(
         ));
         let mut table = Table::new();
         table.load_preset(UTF8_BORDERS_ONLY);
-        table.set_header(vec!["Agent", "With frame", "Without", "Coverage"]);
+        table.set_header(vec!["Agent", "With frame", "Without", "Coverage", "Tokens"]);
         for row in coverage {
             let pct = row
                 .coverage_pct
@@ -70,6 +70,9 @@ pub fn format_workspace_status_text(
                 row.nodes_with_frame.to_string(),
                 row.nodes_without_frame.to_string(),
                 pct,
+                row.head_tokens
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
             ]);
         }
         out.push_str(&format!("{}\n\n", table));
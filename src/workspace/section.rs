@@ -1,9 +1,11 @@
 //! Internal workspace-section build used by status and unified_status.
 
 use crate::agent::{AgentRegistry, AgentRole};
+use crate::context::frame::storage::FrameStorage;
 use crate::error::ApiError;
 use crate::heads::HeadIndex;
 use crate::ignore;
+use crate::metadata::frame_write_contract::KEY_TOTAL_TOKENS;
 use crate::store::NodeRecordStore;
 use crate::tree::builder::TreeBuilder;
 use crate::tree::walker::WalkerConfig;
@@ -20,6 +22,7 @@ use std::path::{Path, PathBuf};
 pub fn build_workspace_status(
     node_store: &dyn NodeRecordStore,
     head_index: &HeadIndex,
+    frame_storage: &FrameStorage,
     agent_registry: &AgentRegistry,
     workspace_root: &Path,
     store_path: &Path,
@@ -129,11 +132,13 @@ pub fn build_workspace_status(
         } else {
             Some(0)
         };
+        let head_tokens = head_token_total(head_index, frame_storage, &frame_type)?;
         context_coverage.push(ContextCoverageEntry {
             agent_id,
             nodes_with_frame,
             nodes_without_frame,
             coverage_pct,
+            head_tokens,
         });
     }
     context_coverage.sort_by(|a, b| a.agent_id.cmp(&b.agent_id));
@@ -152,6 +157,27 @@ pub fn build_workspace_status(
     })
 }
 
+/// Sum recorded provider tokens over the active heads of `frame_type`.
+fn head_token_total(
+    head_index: &HeadIndex,
+    frame_storage: &FrameStorage,
+    frame_type: &str,
+) -> Result<Option<u64>, ApiError> {
+    let mut total: Option<u64> = None;
+    for frame_id in head_index.active_heads_for_frame_type(frame_type) {
+        let Some(frame) = frame_storage.get(&frame_id).map_err(ApiError::from)? else {
+            continue;
+        };
+        if let Some(tokens) = frame
+            .metadata_value(KEY_TOTAL_TOKENS)
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            total = Some(total.unwrap_or(0) + tokens);
+        }
+    }
+    Ok(total)
+}
+
 fn normalize_display_path(path: &Path) -> String {
     let buf: PathBuf = path.to_path_buf();
     buf.display().to_string()
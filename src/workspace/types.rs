@@ -53,6 +53,9 @@ pub struct ContextCoverageEntry {
     pub nodes_without_frame: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub coverage_pct: Option<u64>,
+    /// Provider tokens recorded on head frames; None when no head frame recorded usage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_tokens: Option<u64>,
 }
 
 // --- Agent status (for unified status) ---
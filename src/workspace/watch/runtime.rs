@@ -2,6 +2,7 @@
 
 use super::events::{ChangeEvent, EventBatcher, WatchConfig};
 use crate::api::ContextApi;
+use crate::context::generation::routing::{RoutedAgent, RoutingRules};
 use crate::context::queue::{FrameGenerationQueue, QueueEventContext};
 use crate::error::ApiError;
 use crate::heads::HeadIndex;
@@ -27,6 +28,7 @@ pub struct WatchDaemon {
     config: WatchConfig,
     running: Arc<RwLock<bool>>,
     generation_queue: Option<Arc<FrameGenerationQueue>>,
+    routing: RoutingRules,
 }
 
 impl WatchDaemon {
@@ -46,6 +48,11 @@ impl WatchDaemon {
             }
         }
 
+        let routing = RoutingRules::load(&config.workspace_root)?;
+        if !routing.is_empty() {
+            info!(rule_count = routing.rules.len(), "Loaded routing rules");
+        }
+
         let generation_queue = if config.auto_generate_frames {
             let queue_event_context = match (&config.session_id, &config.progress) {
                 (Some(session_id), Some(progress)) => Some(QueueEventContext {
@@ -71,6 +78,7 @@ impl WatchDaemon {
             config,
             running: Arc::new(RwLock::new(false)),
             generation_queue,
+            routing,
         })
     }
 
@@ -175,8 +183,9 @@ impl WatchDaemon {
             ignore_patterns: self.config.ignore_patterns.clone(),
             max_depth: None,
         };
-        let builder =
-            TreeBuilder::new(self.config.workspace_root.clone()).with_walker_config(walker_config);
+        let builder = TreeBuilder::new(self.config.workspace_root.clone())
+            .with_walker_config(walker_config)
+            .with_blob_store(self.api.blob_store().cloned());
         let tree = builder.build().map_err(ApiError::from)?;
 
         NodeRecord::populate_store_from_tree(
@@ -281,8 +290,9 @@ impl WatchDaemon {
             ignore_patterns: self.config.ignore_patterns.clone(),
             max_depth: None,
         };
-        let builder =
-            TreeBuilder::new(self.config.workspace_root.clone()).with_walker_config(walker_config);
+        let builder = TreeBuilder::new(self.config.workspace_root.clone())
+            .with_walker_config(walker_config)
+            .with_blob_store(self.api.blob_store().cloned());
         let tree = builder.build().map_err(ApiError::from)?;
 
         let _ = ignore::maybe_sync_gitignore_after_tree(
@@ -368,17 +378,22 @@ impl WatchDaemon {
             agents.len()
         );
 
+        // Same rule resolution as `context generate`, so ambiguous rules fail here too
+        let rule_agents = self.routing.resolve_agents(&agents, None)?;
         let batch_size = self.config.frame_batch_size;
         let mut created_count = 0;
         let mut skipped_count = 0;
 
         for chunk in node_ids.chunks(batch_size) {
             for node_id in chunk {
-                for agent_id in &agents {
+                // Watch writes placeholder frames without a provider, so a routed
+                // user_prompt only takes effect when `context generate` fills the node
+                for routed in self.routed_agents(node_id, &agents, &rule_agents)? {
+                    let agent_id = routed.agent_id;
                     match self.api.ensure_agent_frame(
                         *node_id,
                         agent_id.clone(),
-                        None,
+                        routed.frame_type,
                         self.generation_queue.as_ref().map(Arc::clone),
                     ) {
                         Ok(Some(frame_id)) => {
@@ -422,6 +437,46 @@ impl WatchDaemon {
         Ok(())
     }
 
+    /// Agents for a node: those resolved for the first matching routing rule, else every
+    /// agent with its default frame type
+    fn routed_agents(
+        &self,
+        node_id: &NodeID,
+        agents: &[String],
+        rule_agents: &[Vec<RoutedAgent>],
+    ) -> Result<Vec<RoutedAgent>, ApiError> {
+        let record = if self.routing.is_empty() {
+            None
+        } else {
+            self.api.node_store().get(node_id).map_err(ApiError::from)?
+        };
+        let matched = record.and_then(|record| {
+            self.routing
+                .route(&record.path)
+                .map(|(index, rule)| (index, rule.glob.clone()))
+        });
+        Ok(match matched {
+            Some((index, glob)) => {
+                if rule_agents[index].is_empty() {
+                    debug!(
+                        node_id = %hex::encode(node_id),
+                        rule = %glob,
+                        "Skipped contextframes (routing rule)"
+                    );
+                }
+                rule_agents[index].clone()
+            }
+            None => agents
+                .iter()
+                .map(|agent_id| RoutedAgent {
+                    agent_id: agent_id.clone(),
+                    frame_type: None,
+                    user_prompt: None,
+                })
+                .collect(),
+        })
+    }
+
     fn emit_event_best_effort(&self, event_type: &str, data: serde_json::Value) {
         if let (Some(session_id), Some(progress)) = (&self.config.session_id, &self.config.progress)
         {
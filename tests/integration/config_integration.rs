@@ -95,6 +95,10 @@ fn test_config_provider_conversion() {
             api_key: Some("test-key-123".to_string()),
             endpoint: None,
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         },
     );
 
@@ -108,6 +112,10 @@ fn test_config_provider_conversion() {
             api_key: None,
             endpoint: Some("http://localhost:11434".to_string()),
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         },
     );
 
@@ -48,6 +48,10 @@ fn create_test_api() -> (ContextApi, TempDir) {
             api_key: None,
             endpoint: None,
             default_options: meld::provider::CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         },
     );
     provider_registry.load_from_config(&config).unwrap();
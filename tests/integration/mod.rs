@@ -15,6 +15,7 @@ mod node_deletion;
 mod progress_observability;
 mod provider_cli;
 mod store_integration;
+mod stub_provider;
 mod test_utils;
 mod tooling_integration;
 mod tree_determinism;
@@ -3,9 +3,11 @@
 use meld::agent::{AgentIdentity, AgentRole};
 use meld::config::{MerkleConfig, ProviderConfig, ProviderType};
 use meld::provider::{
-    ChatMessage, CompletionOptions, MessageRole, ModelProvider, ProviderRegistry,
+    ChatMessage, CompletionOptions, MessageRole, ModelProvider, ProviderRegistry, TransportConfig,
 };
 
+use crate::integration::stub_provider::{StubResponse, StubServer};
+
 #[test]
 fn test_provider_registry_with_openai() {
     let mut registry = ProviderRegistry::new();
@@ -20,6 +22,10 @@ fn test_provider_registry_with_openai() {
             api_key: Some("test-key".to_string()),
             endpoint: None,
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         },
     );
 
@@ -43,6 +49,10 @@ fn test_provider_registry_with_anthropic() {
             api_key: Some("test-key".to_string()),
             endpoint: None,
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         },
     );
 
@@ -66,6 +76,10 @@ fn test_provider_registry_with_ollama() {
             api_key: None,
             endpoint: None,
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         },
     );
 
@@ -89,6 +103,10 @@ fn test_provider_registry_with_custom_local() {
             api_key: None,
             endpoint: Some("http://localhost:8080/v1".to_string()),
             default_options: CompletionOptions::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         },
     );
 
@@ -98,6 +116,69 @@ fn test_provider_registry_with_custom_local() {
     assert_eq!(client.model_name(), "custom-model");
 }
 
+#[test]
+fn test_provider_transport_proxies_requests_with_extra_headers() {
+    let gateway = |endpoint: &str, transport: TransportConfig| ProviderConfig {
+        provider_name: Some("gateway".to_string()),
+        provider_type: ProviderType::LocalCustom,
+        model: "custom-model".to_string(),
+        api_key: None,
+        endpoint: Some(endpoint.to_string()),
+        default_options: CompletionOptions::default(),
+        pricing: None,
+        rate_limits: None,
+        replay: None,
+        transport: Some(transport),
+    };
+    let complete = |provider: ProviderConfig| {
+        let mut registry = ProviderRegistry::new();
+        let mut config = MerkleConfig::default();
+        config.providers.insert("gateway".to_string(), provider);
+        registry.load_from_config(&config).unwrap();
+        let client = registry.create_client("gateway").unwrap();
+        let message = ChatMessage {
+            role: MessageRole::User,
+            content: "hello".to_string(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        };
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(client.complete(vec![message], CompletionOptions::default()))
+    };
+
+    // The stub answers absolute-form requests, so it stands in for a forward proxy
+    let proxy = StubServer::start(|_| StubResponse::completion("via proxy", 3, 2, "stop"));
+    let mut transport = TransportConfig {
+        proxy: Some(proxy.url.trim_end_matches("/v1").to_string()),
+        request_timeout_secs: Some(5),
+        ..TransportConfig::default()
+    };
+    transport
+        .headers
+        .insert("X-Gateway-Tenant".to_string(), "docs".to_string());
+    let response = complete(gateway("http://llm.corp.invalid/v1", transport.clone())).unwrap();
+    assert_eq!(response.content, "via proxy");
+    let heads = proxy.request_heads();
+    assert_eq!(heads.len(), 1);
+    assert!(
+        heads[0].starts_with("POST http://llm.corp.invalid/v1/chat/completions "),
+        "{}",
+        heads[0]
+    );
+    assert!(heads[0]
+        .to_ascii_lowercase()
+        .contains("x-gateway-tenant: docs"));
+
+    // Hosts listed in no_proxy are reached directly
+    let direct = StubServer::start(|_| StubResponse::completion("direct", 3, 2, "stop"));
+    transport.no_proxy = vec!["127.0.0.1".to_string()];
+    let response = complete(gateway(&direct.url, transport)).unwrap();
+    assert_eq!(response.content, "direct");
+    assert_eq!(proxy.request_heads().len(), 1);
+    assert!(direct.request_heads()[0].starts_with("POST /v1/chat/completions "));
+}
+
 #[test]
 fn test_agent_without_provider() {
     // Agents are now provider-agnostic
@@ -129,11 +210,15 @@ fn test_chat_message_creation() {
     let system_msg = ChatMessage {
         role: MessageRole::System,
         content: "You are a helpful assistant.".to_string(),
+        tool_calls: Vec::new(),
+        tool_call_id: None,
     };
 
     let user_msg = ChatMessage {
         role: MessageRole::User,
         content: "Hello!".to_string(),
+        tool_calls: Vec::new(),
+        tool_call_id: None,
     };
 
     assert_eq!(system_msg.role, MessageRole::System);
@@ -149,6 +234,8 @@ fn test_completion_options() {
         frequency_penalty: None,
         presence_penalty: None,
         stop: Some(vec!["\n".to_string()]),
+        response_format: None,
+        tools: Vec::new(),
     };
 
     assert_eq!(options.temperature, Some(0.7));
@@ -24,6 +24,10 @@ fn create_test_openai_provider(provider_name: &str, model: &str, endpoint: &str)
         api_key: Some("test-api-key".to_string()),
         endpoint: Some(endpoint.to_string()),
         default_options: CompletionOptions::default(),
+        pricing: None,
+        rate_limits: None,
+        replay: None,
+        transport: None,
     };
     let toml = toml::to_string_pretty(&provider_config).unwrap();
     fs::write(config_path, toml).unwrap();
@@ -201,11 +205,17 @@ fn failed_command_emits_session_end() {
                 node: None,
                 path: Some(target),
                 path_positional: None,
-                agent: None,
+                agent: vec![],
                 provider: None,
+                fallback_provider: vec![],
                 frame_type: None,
                 force: false,
                 no_recursive: false,
+                no_cache: false,
+                estimate: false,
+                max_cost: None,
+                max_tokens: None,
+                stream: false,
             },
         });
         assert!(result.is_err());
@@ -248,11 +258,17 @@ fn context_generate_plan_constructed_includes_path_field() {
                 node: None,
                 path: Some(target.clone()),
                 path_positional: None,
-                agent: Some("obs-agent".to_string()),
+                agent: vec!["obs-agent".to_string()],
                 provider: Some("obs-provider".to_string()),
+                fallback_provider: vec![],
                 frame_type: Some("context-obs-agent".to_string()),
                 force: true,
                 no_recursive: false,
+                no_cache: false,
+                estimate: false,
+                max_cost: None,
+                max_tokens: None,
+                stream: false,
             },
         });
         assert!(result.is_err());
@@ -292,6 +308,101 @@ fn context_generate_plan_constructed_includes_path_field() {
     });
 }
 
+#[test]
+fn context_generate_persists_plan_for_resume() {
+    let temp_dir = TempDir::new().unwrap();
+    with_xdg_env(&temp_dir, || {
+        let workspace_root = temp_dir.path().join("workspace");
+        fs::create_dir_all(&workspace_root).unwrap();
+        let target = workspace_root.join("a.txt");
+        fs::write(&target, "hello").unwrap();
+
+        create_test_writer_agent("resume-agent");
+        create_test_openai_provider("resume-provider", "gpt-4-test", "http://127.0.0.1:9");
+
+        let cli = RunContext::new(workspace_root.clone(), None).unwrap();
+        cli.execute(&Commands::Scan { force: true }).unwrap();
+
+        let result = cli.execute(&Commands::Context {
+            command: ContextCommands::Generate {
+                node: None,
+                path: Some(target.clone()),
+                path_positional: None,
+                agent: vec!["resume-agent".to_string()],
+                provider: Some("resume-provider".to_string()),
+                fallback_provider: vec![],
+                frame_type: Some("context-resume-agent".to_string()),
+                force: true,
+                no_recursive: false,
+                no_cache: false,
+                estimate: false,
+                max_cost: None,
+                max_tokens: None,
+                stream: false,
+            },
+        });
+        assert!(result.is_err());
+
+        let runs_json = cli
+            .execute(&Commands::Context {
+                command: ContextCommands::Runs {
+                    format: "json".to_string(),
+                    limit: 20,
+                },
+            })
+            .unwrap();
+        let runs: serde_json::Value = serde_json::from_str(&runs_json).unwrap();
+        let run = &runs.as_array().unwrap()[0];
+        assert_eq!(run["status"].as_str(), Some("failed"));
+        assert_eq!(run["failed"].as_u64(), Some(1));
+        assert_eq!(run["attempts"].as_u64(), Some(1));
+        let plan_id = run["plan_id"].as_str().unwrap().to_string();
+
+        // Provider is still unreachable: the failed item is retried and fails again
+        let resumed = cli.execute(&Commands::Context {
+            command: ContextCommands::Resume {
+                plan_id: None,
+                max_cost: None,
+                max_tokens: None,
+            },
+        });
+        assert!(resumed.is_err());
+        let store = cli.generation_runs();
+        let record = store.get_run(&plan_id).unwrap().unwrap();
+        assert_eq!(record.attempts, 2);
+
+        // Once every item completed there is nothing left to run
+        let item = &record.plan.levels[0][0];
+        store
+            .record_item(&plan_id, item, Ok(meld::types::Hash::from([7u8; 32])))
+            .unwrap();
+        let output = cli
+            .execute(&Commands::Context {
+                command: ContextCommands::Resume {
+                    plan_id: Some(plan_id.clone()),
+                    max_cost: None,
+                    max_tokens: None,
+                },
+            })
+            .unwrap();
+        assert!(output.contains("no remaining items"));
+        let record = store.get_run(&plan_id).unwrap().unwrap();
+        assert_eq!(
+            record.status,
+            meld::context::generation::RunStatus::Completed
+        );
+        assert!(cli
+            .execute(&Commands::Context {
+                command: ContextCommands::Resume {
+                    plan_id: None,
+                    max_cost: None,
+                    max_tokens: None,
+                },
+            })
+            .is_err());
+    });
+}
+
 #[test]
 fn context_generate_node_skipped_includes_path_field() {
     let temp_dir = TempDir::new().unwrap();
@@ -333,11 +444,17 @@ fn context_generate_node_skipped_includes_path_field() {
                 node: None,
                 path: Some(target.clone()),
                 path_positional: None,
-                agent: Some("skip-agent".to_string()),
+                agent: vec!["skip-agent".to_string()],
                 provider: Some("skip-provider".to_string()),
+                fallback_provider: vec![],
                 frame_type: Some(frame_type),
                 force: false,
                 no_recursive: false,
+                no_cache: false,
+                estimate: false,
+                max_cost: None,
+                max_tokens: None,
+                stream: false,
             },
         });
         assert!(result.is_ok());
@@ -384,6 +501,7 @@ fn context_get_emits_summary_event() {
                 frame_type: None,
                 max_frames: 5,
                 ordering: "recency".to_string(),
+                where_clauses: vec![],
                 combine: false,
                 separator: "\n".to_string(),
                 format: "json".to_string(),
@@ -605,11 +723,17 @@ fn command_summary_failure_message_is_bounded() {
                 node: None,
                 path: Some(target),
                 path_positional: None,
-                agent: Some("summary-agent".to_string()),
+                agent: vec!["summary-agent".to_string()],
                 provider: Some(provider_name),
+                fallback_provider: vec![],
                 frame_type: None,
                 force: false,
                 no_recursive: false,
+                no_cache: false,
+                estimate: false,
+                max_cost: None,
+                max_tokens: None,
+                stream: false,
             },
         });
         assert!(result.is_err());
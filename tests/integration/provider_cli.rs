@@ -30,6 +30,10 @@ fn create_test_provider(
         api_key: None,
         endpoint: endpoint.map(|s| s.to_string()),
         default_options: meld::provider::CompletionOptions::default(),
+        pricing: None,
+        rate_limits: None,
+        replay: None,
+        transport: None,
     };
 
     let toml_content = toml::to_string_pretty(&provider_config)
@@ -432,6 +436,81 @@ fn test_provider_create_non_interactive() {
     });
 }
 
+#[test]
+fn test_provider_api_key_references_hide_values_and_plaintext_warns() {
+    let test_dir = TempDir::new().unwrap();
+    with_xdg_env(&test_dir, || {
+        std::env::set_var("MELD_PROVIDER_CLI_TEST_KEY", "sk-from-reference");
+        let workspace = test_dir.path().to_path_buf();
+        let cli = RunContext::new(workspace, None).unwrap();
+
+        let created = cli
+            .execute(&Commands::Provider {
+                command: ProviderCommands::Create {
+                    provider_name: "keyed".to_string(),
+                    type_: Some("openai".to_string()),
+                    model: Some("gpt-4".to_string()),
+                    endpoint: None,
+                    api_key: Some("sk-plaintext-secret".to_string()),
+                    interactive: false,
+                    non_interactive: true,
+                },
+            })
+            .unwrap();
+        assert!(created.contains("Warning: API key stored in plaintext"));
+
+        let validate = |cli: &RunContext| {
+            cli.execute(&Commands::Provider {
+                command: ProviderCommands::Validate {
+                    provider_name: "keyed".to_string(),
+                    test_connectivity: false,
+                    check_model: false,
+                    verbose: false,
+                },
+            })
+            .unwrap()
+        };
+        let output = validate(&cli);
+        assert!(output.contains("plaintext"), "{}", output);
+
+        let edited = cli
+            .execute(&Commands::Provider {
+                command: ProviderCommands::Edit {
+                    provider_name: "keyed".to_string(),
+                    model: None,
+                    endpoint: None,
+                    api_key: Some("env:MELD_PROVIDER_CLI_TEST_KEY".to_string()),
+                    editor: None,
+                },
+            })
+            .unwrap();
+        assert!(!edited.contains("plaintext"));
+
+        let shown = cli
+            .execute(&Commands::Provider {
+                command: ProviderCommands::Show {
+                    provider_name: "keyed".to_string(),
+                    format: "json".to_string(),
+                    include_credentials: true,
+                },
+            })
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&shown).unwrap();
+        assert_eq!(json["api_key_status"], "set_from_reference");
+        assert_eq!(json["api_key_source"], "env:MELD_PROVIDER_CLI_TEST_KEY");
+        assert!(!shown.contains("sk-from-reference"));
+
+        let output = validate(&cli);
+        assert!(!output.contains("plaintext"), "{}", output);
+        assert!(
+            output.contains("All validation checks passed"),
+            "{}",
+            output
+        );
+        std::env::remove_var("MELD_PROVIDER_CLI_TEST_KEY");
+    });
+}
+
 #[test]
 fn test_provider_create_missing_required_fields() {
     let test_dir = TempDir::new().unwrap();
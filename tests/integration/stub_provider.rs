@@ -0,0 +1,272 @@
+//! Stub OpenAI-compatible chat completions server for integration tests
+//!
+//! Serves `POST .../chat/completions` on a loopback port so generation can run end to end
+//! without network access. Each request body and head is recorded for assertions.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// One HTTP response from the stub
+#[derive(Debug, Clone)]
+pub struct StubResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl StubResponse {
+    /// Successful chat completion with the given content, usage, and finish reason
+    pub fn completion(
+        content: &str,
+        prompt_tokens: u32,
+        completion_tokens: u32,
+        finish_reason: &str,
+    ) -> Self {
+        let body = serde_json::json!({
+            "id": "stub",
+            "model": "stub-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": content },
+                "finish_reason": finish_reason,
+            }],
+            "usage": {
+                "prompt_tokens": prompt_tokens,
+                "completion_tokens": completion_tokens,
+                "total_tokens": prompt_tokens + completion_tokens,
+            },
+        });
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Chat completion that asks for tool calls, given as `(id, name, arguments)`
+    pub fn tool_calls(
+        calls: &[(&str, &str, serde_json::Value)],
+        prompt_tokens: u32,
+        completion_tokens: u32,
+    ) -> Self {
+        let tool_calls: Vec<serde_json::Value> = calls
+            .iter()
+            .map(|(id, name, arguments)| {
+                serde_json::json!({
+                    "id": id,
+                    "type": "function",
+                    "function": { "name": name, "arguments": arguments.to_string() },
+                })
+            })
+            .collect();
+        let body = serde_json::json!({
+            "id": "stub",
+            "model": "stub-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": null, "tool_calls": tool_calls },
+                "finish_reason": "tool_calls",
+            }],
+            "usage": {
+                "prompt_tokens": prompt_tokens,
+                "completion_tokens": completion_tokens,
+                "total_tokens": prompt_tokens + completion_tokens,
+            },
+        });
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Error response with an OpenAI-style error body
+    pub fn error(status: u16, message: &str) -> Self {
+        let body = serde_json::json!({ "error": { "message": message } });
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Streamed chat completion: one server-sent event per delta, then `[DONE]`
+    pub fn stream(deltas: &[&str]) -> Self {
+        Self::stream_events(deltas, None)
+    }
+
+    /// Streamed chat completion ending with a finish reason and a usage-only chunk,
+    /// as sent when `stream_options.include_usage` is requested
+    pub fn stream_with_usage(deltas: &[&str], prompt_tokens: u32, completion_tokens: u32) -> Self {
+        Self::stream_events(deltas, Some((prompt_tokens, completion_tokens)))
+    }
+
+    /// Streamed chat completion that breaks off with an error event after the deltas
+    pub fn stream_interrupted(deltas: &[&str], message: &str) -> Self {
+        let mut response = Self::stream_events(deltas, None);
+        let error = serde_json::json!({ "error": { "message": message } });
+        response.body = response.body.replace("data: [DONE]\n\n", "");
+        response.body.push_str(&format!("data: {}\n\n", error));
+        response
+    }
+
+    fn stream_events(deltas: &[&str], usage: Option<(u32, u32)>) -> Self {
+        let mut body = String::new();
+        for delta in deltas {
+            let chunk = serde_json::json!({
+                "id": "stub",
+                "model": "stub-model",
+                "choices": [{ "index": 0, "delta": { "content": delta } }],
+            });
+            body.push_str(&format!("data: {}\n\n", chunk));
+        }
+        if let Some((prompt_tokens, completion_tokens)) = usage {
+            let finish = serde_json::json!({
+                "id": "stub",
+                "model": "stub-model",
+                "choices": [{ "index": 0, "delta": {}, "finish_reason": "stop" }],
+            });
+            let usage = serde_json::json!({
+                "id": "stub",
+                "model": "stub-model",
+                "choices": [],
+                "usage": {
+                    "prompt_tokens": prompt_tokens,
+                    "completion_tokens": completion_tokens,
+                    "total_tokens": prompt_tokens + completion_tokens,
+                },
+            });
+            body.push_str(&format!("data: {}\n\ndata: {}\n\n", finish, usage));
+        }
+        body.push_str("data: [DONE]\n\n");
+        Self {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "text/event-stream".to_string())],
+            body,
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+type Handler = dyn Fn(&str) -> StubResponse + Send + Sync;
+
+/// Running stub server; the listener thread lives until the test process exits
+pub struct StubServer {
+    /// Base URL to use as a provider endpoint (e.g. `http://127.0.0.1:PORT/v1`)
+    pub url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+    heads: Arc<Mutex<Vec<String>>>,
+}
+
+impl StubServer {
+    /// Start a server that answers each request with `handler(request_body)`
+    pub fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&str) -> StubResponse + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let heads = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let recorded = Arc::clone(&requests);
+        let recorded_heads = Arc::clone(&heads);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let handler = Arc::clone(&handler);
+                let recorded = Arc::clone(&recorded);
+                let recorded_heads = Arc::clone(&recorded_heads);
+                thread::spawn(move || {
+                    serve_connection(stream, handler.as_ref(), &recorded, &recorded_heads)
+                });
+            }
+        });
+
+        Self {
+            url,
+            requests,
+            heads,
+        }
+    }
+
+    /// Request bodies received so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Request lines and headers received so far, one string per request
+    pub fn request_heads(&self) -> Vec<String> {
+        self.heads.lock().unwrap().clone()
+    }
+}
+
+fn serve_connection(
+    stream: TcpStream,
+    handler: &Handler,
+    recorded: &Mutex<Vec<String>>,
+    recorded_heads: &Mutex<Vec<String>>,
+) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+            return;
+        }
+        let mut head = request_line.clone();
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            head.push_str(&line);
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+        let body = String::from_utf8_lossy(&body).to_string();
+        recorded.lock().unwrap().push(body.clone());
+        recorded_heads.lock().unwrap().push(head);
+
+        let response = handler(&body);
+        let mut head = format!(
+            "HTTP/1.1 {} Stub\r\nContent-Length: {}\r\n",
+            response.status,
+            response.body.len()
+        );
+        if !response
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        {
+            head.push_str("Content-Type: application/json\r\n");
+        }
+        for (name, value) in &response.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+        if writer.write_all(head.as_bytes()).is_err()
+            || writer.write_all(response.body.as_bytes()).is_err()
+        {
+            return;
+        }
+    }
+}
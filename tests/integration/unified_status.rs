@@ -76,6 +76,10 @@ fn create_test_provider(
         api_key: None,
         endpoint: endpoint.map(|s| s.to_string()),
         default_options: meld::provider::CompletionOptions::default(),
+        pricing: None,
+        rate_limits: None,
+        replay: None,
+        transport: None,
     };
 
     let toml_content = toml::to_string_pretty(&provider_config)
@@ -506,6 +506,10 @@ fn test_load_order_xdg_overrides_config() {
             api_key: None,
             endpoint: None,
             default_options: Default::default(),
+            pricing: None,
+            rate_limits: None,
+            replay: None,
+            transport: None,
         };
         config
             .providers